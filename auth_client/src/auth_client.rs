@@ -4,13 +4,14 @@ use client_listener::ConnectionId;
 use std::{
     env::current_exe,
     io,
-    net::IpAddr,
+    net::{IpAddr, SocketAddr},
     os::unix::{
         io::{FromRawFd, IntoRawFd, RawFd},
         process::CommandExt,
     },
     path::{Path, PathBuf},
     process::{Child, Command},
+    time::Duration,
 };
 use thiserror::Error;
 use tokio::{
@@ -195,6 +196,40 @@ impl AuthClient {
             .ok();
     }
 
+    /// Begin an ident (RFC 1413) lookup for a client connection, where `remote` is the
+    /// client's end of the connection and `local` is ours. The connection ID is used to
+    /// identify the resulting `IdentResult` when the operation completes.
+    ///
+    /// The client's ident server is asked which user owns the connection. If it
+    /// doesn't answer within `timeout`, returns an error, or names a user which isn't a
+    /// valid [`Username`](sable_network::validated::Username), the result is None.
+    #[tracing::instrument(skip(self))]
+    pub fn start_ident_lookup(
+        &self,
+        conn_id: ConnectionId,
+        remote: SocketAddr,
+        local: SocketAddr,
+        timeout: Duration,
+    ) {
+        self.control_sender
+            .send(ControlMessage::StartIdentLookup(
+                conn_id, remote, local, timeout,
+            ))
+            .ok();
+    }
+
+    /// Begin checking an IP address against DNS blocklists. The connection ID is used to
+    /// identify the resulting `DnsblResult` when the operation completes.
+    ///
+    /// Each of `zones` is queried concurrently for the address, and the result lists
+    /// those in which it was found, along with the reply codes they returned.
+    #[tracing::instrument(skip(self))]
+    pub fn start_dnsbl_lookup(&self, conn_id: ConnectionId, addr: IpAddr, zones: Vec<String>) {
+        self.control_sender
+            .send(ControlMessage::StartDnsblLookup(conn_id, addr, zones))
+            .ok();
+    }
+
     /// Shut down the communications task and child process, then wait for them to exit.
     ///
     /// Note that the child process will only be waited for if this `AuthClient` was created by
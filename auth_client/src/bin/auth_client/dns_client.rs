@@ -2,27 +2,83 @@ use auth_client::*;
 use client_listener::ConnectionId;
 use sable_network::prelude::*;
 
-use std::net::IpAddr;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tokio::{sync::mpsc::UnboundedSender, task};
-use trust_dns_resolver::TokioAsyncResolver;
+use trust_dns_resolver::{
+    config::{LookupIpStrategy, ResolverConfig, ResolverOpts},
+    system_conf, TokioAsyncResolver,
+};
+
+/// How long to remember that an address has no usable hostname
+const NEGATIVE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Upper bound on how long to remember a hostname, whatever its records' TTLs
+const MAX_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// Expired entries are only cleared out once the cache grows beyond this size
+const CACHE_PRUNE_SIZE: usize = 10000;
+
+/// Only this many of an address's PTR records are checked
+const MAX_PTR_NAMES: usize = 4;
+
+/// Results of recent lookups, so that clients reconnecting repeatedly from the same
+/// address don't each cause a new set of queries
+#[derive(Default)]
+struct DnsCache {
+    entries: HashMap<IpAddr, (Option<Hostname>, Instant)>,
+}
+
+impl DnsCache {
+    fn get(&self, addr: IpAddr, now: Instant) -> Option<Option<Hostname>> {
+        self.entries
+            .get(&addr)
+            .filter(|(_, expires)| *expires > now)
+            .map(|(hostname, _)| *hostname)
+    }
+
+    fn insert(&mut self, addr: IpAddr, hostname: Option<Hostname>, expires: Instant) {
+        if self.entries.len() >= CACHE_PRUNE_SIZE {
+            let now = Instant::now();
+            self.entries.retain(|_, (_, expires)| *expires > now);
+        }
+        self.entries.insert(addr, (hostname, expires));
+    }
+}
 
 /// A simple client for [`TokioAsyncResolver`] to handle the DNS lookups
 /// required for connecting IRC clients.
 pub struct InternalDnsClient {
     event_channel: UnboundedSender<DnsResult>,
     resolver: TokioAsyncResolver,
+    cache: Arc<Mutex<DnsCache>>,
 }
 
 impl InternalDnsClient {
     /// Construct an `InternalDnsClient`. Responses to each request will be sent over `event_channel`
     /// as and when they complete.
-    pub fn new(event_channel: UnboundedSender<DnsResult>) -> Self {
-        let resolver =
-            TokioAsyncResolver::tokio_from_system_conf().expect("Failed to create DNS resolver");
-        Self {
+    pub fn new(event_channel: UnboundedSender<DnsResult>) -> std::io::Result<Self> {
+        let (config, mut opts) = system_conf::read_system_conf().unwrap_or_else(|e| {
+            tracing::warn!(
+                "Couldn't read system DNS configuration for hostname lookups, using defaults: {}",
+                e
+            );
+            (ResolverConfig::default(), ResolverOpts::default())
+        });
+        // Forward lookups need to find the client's address whichever family it's in
+        opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+
+        let resolver = TokioAsyncResolver::tokio(config, opts)?;
+        Ok(Self {
             event_channel,
             resolver,
-        }
+            cache: Arc::new(Mutex::new(DnsCache::default())),
+        })
     }
 
     /// Begin a DNS lookup.
@@ -32,37 +88,220 @@ impl InternalDnsClient {
     pub fn start_lookup(&self, conn_id: ConnectionId, addr: IpAddr) {
         let chan = self.event_channel.clone();
         let resolver = self.resolver.clone();
+        let cache = Arc::clone(&self.cache);
 
         task::spawn(async move {
-            let name = match resolver.reverse_lookup(addr).await {
-                Ok(lookup) => match lookup.iter().next() {
-                    Some(name) => {
-                        if Self::verify_forward_matches(&resolver, name, addr).await {
-                            Some(name.to_ascii())
-                        } else {
-                            None
-                        }
-                    }
-                    None => None,
-                },
-                Err(_) => None,
+            let cached = cache.lock().unwrap().get(addr, Instant::now());
+
+            let hostname = match cached {
+                Some(hostname) => hostname,
+                None => {
+                    let (hostname, valid_until) = Self::lookup(&resolver, addr).await;
+                    let expires = valid_until.min(Instant::now() + MAX_CACHE_TTL);
+                    cache.lock().unwrap().insert(addr, hostname, expires);
+                    hostname
+                }
             };
-            let name = name.and_then(|n| Hostname::convert(n.trim_end_matches('.')).ok());
+
             let _res = chan.send(DnsResult {
                 conn: conn_id,
-                hostname: name,
+                hostname,
             });
         });
     }
 
-    async fn verify_forward_matches(
-        resolver: &TokioAsyncResolver,
-        name: &trust_dns_resolver::Name,
-        addr: IpAddr,
-    ) -> bool {
-        match resolver.lookup_ip(name.clone()).await {
-            Ok(lookup) => lookup.iter().any(|ip| ip == addr),
-            Err(_) => false,
+    /// Find a forward-confirmed hostname for `addr` using `resolver`, and when the
+    /// result should expire from the cache
+    async fn lookup(resolver: &TokioAsyncResolver, addr: IpAddr) -> (Option<Hostname>, Instant) {
+        let reverse = |addr| async move {
+            let reverse = resolver.reverse_lookup(addr).await.ok()?;
+            let names = reverse.iter().map(|name| name.to_ascii()).collect();
+            Some((names, reverse.valid_until()))
+        };
+        let forward = |name: String| async move {
+            let lookup = resolver.lookup_ip(name).await.ok()?;
+            Some((lookup.iter().collect(), lookup.valid_until()))
+        };
+
+        confirmed_hostname(addr, reverse, forward).await
+    }
+}
+
+/// Find a forward-confirmed hostname for `addr`: one of its PTR names which itself
+/// resolves back to `addr`. Also returns when the result should expire from the cache.
+///
+/// `reverse` looks up an address's PTR names, and `forward` a name's addresses, each
+/// returning when its answer expires.
+async fn confirmed_hostname<R, RFut, F, FFut>(
+    addr: IpAddr,
+    reverse: R,
+    forward: F,
+) -> (Option<Hostname>, Instant)
+where
+    R: FnOnce(IpAddr) -> RFut,
+    RFut: Future<Output = Option<(Vec<String>, Instant)>>,
+    F: Fn(String) -> FFut,
+    FFut: Future<Output = Option<(Vec<IpAddr>, Instant)>>,
+{
+    let negative = (None, Instant::now() + NEGATIVE_CACHE_TTL);
+
+    // An IPv4 client may appear as a mapped IPv6 address, but its PTR and A records
+    // are those of the IPv4 address
+    let addr = match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        IpAddr::V4(_) => addr,
+    };
+
+    let Some((names, reverse_valid_until)) = reverse(addr).await else {
+        return negative;
+    };
+
+    for name in names.into_iter().take(MAX_PTR_NAMES) {
+        let Some((addrs, forward_valid_until)) = forward(name.clone()).await else {
+            continue;
+        };
+        if !addrs.contains(&addr) {
+            continue;
         }
+
+        if let Ok(hostname) = Hostname::convert(name.trim_end_matches('.')) {
+            return (Some(hostname), reverse_valid_until.min(forward_valid_until));
+        }
+    }
+
+    negative
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_entries_expire() {
+        let mut cache = DnsCache::default();
+        let now = Instant::now();
+        let addr = "192.0.2.1".parse().unwrap();
+        let hostname = Hostname::convert("host.example").ok();
+
+        assert_eq!(cache.get(addr, now), None);
+
+        cache.insert(addr, hostname, now + Duration::from_secs(10));
+        assert_eq!(cache.get(addr, now), Some(hostname));
+        assert_eq!(cache.get(addr, now + Duration::from_secs(10)), None);
+
+        cache.insert(addr, None, now + Duration::from_secs(10));
+        assert_eq!(cache.get(addr, now), Some(None));
+    }
+
+    type Records = HashMap<&'static str, Vec<&'static str>>;
+
+    /// Look up `addr` against stand-in PTR and A/AAAA records, returning the hostname
+    /// found and how long it's cached for
+    async fn lookup(
+        addr: &str,
+        ptr: &[(&'static str, Vec<&'static str>)],
+        forward: &[(&'static str, Vec<&'static str>)],
+    ) -> (Option<Hostname>, Duration) {
+        let ptr: Records = ptr.iter().cloned().collect();
+        let forward: Records = forward.iter().cloned().collect();
+        let start = Instant::now();
+
+        let reverse_lookup = |addr: IpAddr| {
+            let names = ptr.get(addr.to_string().as_str()).map(|names| {
+                let names = names.iter().map(|n| n.to_string()).collect();
+                (names, start + Duration::from_secs(300))
+            });
+            async move { names }
+        };
+        let forward_lookup = |name: String| {
+            let addrs = forward.get(name.as_str()).map(|addrs| {
+                let addrs = addrs.iter().map(|a| a.parse().unwrap()).collect();
+                (addrs, start + Duration::from_secs(600))
+            });
+            async move { addrs }
+        };
+
+        let (hostname, expires) =
+            confirmed_hostname(addr.parse().unwrap(), reverse_lookup, forward_lookup).await;
+        (hostname, expires.duration_since(start))
+    }
+
+    #[tokio::test]
+    async fn matching_forward_record() {
+        let (hostname, ttl) = lookup(
+            "192.0.2.1",
+            &[("192.0.2.1", vec!["host.example."])],
+            &[("host.example.", vec!["192.0.2.1"])],
+        )
+        .await;
+        assert_eq!(hostname, Hostname::convert("host.example").ok());
+        // The shorter of the two records' lifetimes
+        assert_eq!(ttl, Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn mismatched_forward_record() {
+        let (hostname, ttl) = lookup(
+            "192.0.2.1",
+            &[("192.0.2.1", vec!["spoofed.example."])],
+            &[("spoofed.example.", vec!["198.51.100.1"])],
+        )
+        .await;
+        assert_eq!(hostname, None);
+        // Cached for the negative lifetime, not the records'
+        assert!(ttl >= NEGATIVE_CACHE_TTL && ttl < Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn missing_records() {
+        let (hostname, _) = lookup("192.0.2.1", &[], &[]).await;
+        assert_eq!(hostname, None);
+
+        let (hostname, _) = lookup("192.0.2.1", &[("192.0.2.1", vec!["host.example."])], &[]).await;
+        assert_eq!(hostname, None);
+    }
+
+    #[tokio::test]
+    async fn later_ptr_name_can_match() {
+        let (hostname, _) = lookup(
+            "192.0.2.1",
+            &[("192.0.2.1", vec!["other.example.", "host.example."])],
+            &[
+                ("other.example.", vec!["198.51.100.1"]),
+                ("host.example.", vec!["198.51.100.2", "192.0.2.1"]),
+            ],
+        )
+        .await;
+        assert_eq!(hostname, Hostname::convert("host.example").ok());
+    }
+
+    #[tokio::test]
+    async fn ipv6_address() {
+        let (hostname, _) = lookup(
+            "2001:db8::1",
+            &[("2001:db8::1", vec!["host6.example."])],
+            &[("host6.example.", vec!["192.0.2.1", "2001:db8::1"])],
+        )
+        .await;
+        assert_eq!(hostname, Hostname::convert("host6.example").ok());
+
+        let (hostname, _) = lookup(
+            "2001:db8::1",
+            &[("2001:db8::1", vec!["host6.example."])],
+            &[("host6.example.", vec!["2001:db8::2"])],
+        )
+        .await;
+        assert_eq!(hostname, None);
+    }
+
+    #[tokio::test]
+    async fn mapped_ipv4_address_uses_ipv4_records() {
+        let (hostname, _) = lookup(
+            "::ffff:192.0.2.1",
+            &[("192.0.2.1", vec!["host.example."])],
+            &[("host.example.", vec!["192.0.2.1"])],
+        )
+        .await;
+        assert_eq!(hostname, Hostname::convert("host.example").ok());
     }
 }
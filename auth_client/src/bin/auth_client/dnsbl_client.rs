@@ -0,0 +1,191 @@
+use auth_client::*;
+use client_listener::ConnectionId;
+
+use futures::future::join_all;
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+use tokio::{sync::mpsc::UnboundedSender, task};
+use trust_dns_resolver::{
+    config::{ResolverConfig, ResolverOpts},
+    TokioAsyncResolver,
+};
+
+/// How long each zone has to answer before the address is treated as not listed
+const DNSBL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A client to check connecting addresses against DNS blocklists, as described in
+/// RFC 5782
+pub struct InternalDnsblClient {
+    event_channel: UnboundedSender<DnsblResult>,
+    resolver: TokioAsyncResolver,
+}
+
+impl InternalDnsblClient {
+    /// Construct an `InternalDnsblClient`. Responses to each request will be sent over
+    /// `event_channel` as and when they complete.
+    ///
+    /// If the system's DNS configuration can't be read, a default configuration is
+    /// used instead.
+    pub fn new(event_channel: UnboundedSender<DnsblResult>) -> std::io::Result<Self> {
+        let resolver = match TokioAsyncResolver::tokio_from_system_conf() {
+            Ok(resolver) => resolver,
+            Err(e) => {
+                tracing::warn!(
+                    "Couldn't read system DNS configuration for blocklist lookups, using defaults: {}",
+                    e
+                );
+                TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())?
+            }
+        };
+        Ok(Self {
+            event_channel,
+            resolver,
+        })
+    }
+
+    /// Begin checking `addr` against each of `zones`.
+    ///
+    /// `conn_id` is not used internally, but is attached to the response message to allow the result
+    ///  to be associated with the request.
+    pub fn start_lookup(&self, conn_id: ConnectionId, addr: IpAddr, zones: Vec<String>) {
+        let chan = self.event_channel.clone();
+        let resolver = self.resolver.clone();
+
+        task::spawn(async move {
+            let lookup = |name: String| {
+                let resolver = resolver.clone();
+                async move {
+                    match tokio::time::timeout(DNSBL_TIMEOUT, resolver.lookup_ip(name)).await {
+                        Ok(Ok(lookup)) => lookup
+                            .iter()
+                            .filter_map(|ip| match ip {
+                                IpAddr::V4(ip) => Some(ip),
+                                IpAddr::V6(_) => None,
+                            })
+                            .collect(),
+                        // Not listed, or the zone couldn't be reached
+                        _ => Vec::new(),
+                    }
+                }
+            };
+
+            let listings = check(addr, &zones, lookup).await;
+            let _res = chan.send(DnsblResult {
+                conn: conn_id,
+                addr,
+                listings,
+            });
+        });
+    }
+}
+
+/// The name to look up to check `addr` against `zone`: the address's octets (or for
+/// IPv6, nibbles) in reverse order, prepended to the zone
+fn query_name(addr: IpAddr, zone: &str) -> String {
+    // Blocklists list IPv4 clients by their IPv4 address, however they connected
+    let addr = match addr {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        IpAddr::V4(_) => addr,
+    };
+
+    let labels: Vec<String> = match addr {
+        IpAddr::V4(ip) => ip.octets().iter().rev().map(u8::to_string).collect(),
+        IpAddr::V6(ip) => ip
+            .octets()
+            .iter()
+            .rev()
+            .flat_map(|byte| [byte & 0xf, byte >> 4])
+            .map(|nibble| format!("{nibble:x}"))
+            .collect(),
+    };
+
+    format!("{}.{}.", labels.join("."), zone.trim_end_matches('.'))
+}
+
+/// Whether a reply from a zone indicates a listing. Listings are in 127.0.0.0/8, but
+/// some zones use 127.255.255.0/24 to signal errors, such as refusing to answer.
+fn is_listing(reply: &Ipv4Addr) -> bool {
+    let octets = reply.octets();
+    octets[0] == 127 && octets[1..3] != [255, 255]
+}
+
+/// Check `addr` against each of `zones` concurrently, using `lookup` to resolve names
+/// to their IPv4 addresses
+async fn check<F, Fut>(addr: IpAddr, zones: &[String], lookup: F) -> Vec<DnsblListing>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Vec<Ipv4Addr>>,
+{
+    let lookups = zones.iter().map(|zone| {
+        let replies = lookup(query_name(addr, zone));
+        async move { (zone, replies.await) }
+    });
+
+    join_all(lookups)
+        .await
+        .into_iter()
+        .filter_map(|(zone, replies)| {
+            let replies: Vec<_> = replies.into_iter().filter(is_listing).collect();
+            (!replies.is_empty()).then(|| DnsblListing {
+                zone: zone.clone(),
+                replies,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn builds_query_names() {
+        assert_eq!(
+            query_name("192.0.2.99".parse().unwrap(), "dnsbl.example"),
+            "99.2.0.192.dnsbl.example."
+        );
+        assert_eq!(
+            query_name("::ffff:192.0.2.99".parse().unwrap(), "dnsbl.example."),
+            "99.2.0.192.dnsbl.example."
+        );
+        assert_eq!(
+            query_name("2001:db8::1".parse().unwrap(), "dnsbl.example"),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.dnsbl.example."
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_listing_zones() {
+        // Stands in for a resolver with these records
+        let records: HashMap<&str, Vec<Ipv4Addr>> = [
+            (
+                "2.0.0.127.listed.example.",
+                vec!["127.0.0.2".parse().unwrap(), "127.0.0.5".parse().unwrap()],
+            ),
+            (
+                "2.0.0.127.refused.example.",
+                vec!["127.255.255.254".parse().unwrap()],
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let lookup = |name: String| {
+            let replies = records.get(name.as_str()).cloned().unwrap_or_default();
+            async move { replies }
+        };
+
+        let zones = ["listed.example", "refused.example", "clean.example"].map(String::from);
+        let listings = check("127.0.0.2".parse().unwrap(), &zones, lookup).await;
+
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].zone, "listed.example");
+        assert_eq!(
+            listings[0].replies,
+            vec![Ipv4Addr::new(127, 0, 0, 2), Ipv4Addr::new(127, 0, 0, 5)]
+        );
+    }
+}
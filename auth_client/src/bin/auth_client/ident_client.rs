@@ -0,0 +1,179 @@
+use auth_client::*;
+use client_listener::ConnectionId;
+use sable_network::prelude::*;
+
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpSocket,
+    sync::mpsc::UnboundedSender,
+    task,
+};
+
+/// The standard ident port
+const IDENT_PORT: u16 = 113;
+
+/// Responses are much shorter than this; anything longer is ignored
+const MAX_RESPONSE_LENGTH: u64 = 1000;
+
+/// A client for the ident protocol (RFC 1413), used to ask connecting clients' hosts
+/// which user owns the connection.
+pub struct InternalIdentClient {
+    event_channel: UnboundedSender<IdentResult>,
+}
+
+impl InternalIdentClient {
+    /// Construct an `InternalIdentClient`. Responses to each request will be sent over
+    /// `event_channel` as and when they complete.
+    pub fn new(event_channel: UnboundedSender<IdentResult>) -> Self {
+        Self { event_channel }
+    }
+
+    /// Begin an ident lookup for the connection from `remote` to `local`, which may
+    /// take up to `timeout` from connecting to receiving a response.
+    ///
+    /// `conn_id` is not used internally, but is attached to the response message to allow the result
+    ///  to be associated with the request.
+    pub fn start_lookup(
+        &self,
+        conn_id: ConnectionId,
+        remote: SocketAddr,
+        local: SocketAddr,
+        timeout: Duration,
+    ) {
+        let chan = self.event_channel.clone();
+
+        task::spawn(async move {
+            let username =
+                match tokio::time::timeout(timeout, query(remote, local, IDENT_PORT)).await {
+                    Ok(Ok(response)) => response,
+                    Ok(Err(e)) => {
+                        tracing::debug!(?remote, "Ident lookup failed: {}", e);
+                        None
+                    }
+                    Err(_) => {
+                        tracing::debug!(?remote, "Ident lookup timed out");
+                        None
+                    }
+                };
+            let _res = chan.send(IdentResult {
+                conn: conn_id,
+                username,
+            });
+        });
+    }
+}
+
+/// Ask the ident server on `remote`'s host, listening on `ident_port`, who owns the
+/// connection between `remote` and `local`
+async fn query(
+    remote: SocketAddr,
+    local: SocketAddr,
+    ident_port: u16,
+) -> std::io::Result<Option<Username>> {
+    let socket = if local.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    // Connect from the address the client connected to, so that the ident server
+    // can match the connection
+    socket.bind(SocketAddr::new(local.ip(), 0))?;
+    let mut stream = socket
+        .connect(SocketAddr::new(remote.ip(), ident_port))
+        .await?;
+
+    stream
+        .write_all(format!("{} , {}\r\n", remote.port(), local.port()).as_bytes())
+        .await?;
+
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_RESPONSE_LENGTH))
+        .read_line(&mut line)
+        .await?;
+
+    Ok(parse_response(&line, remote.port(), local.port()))
+}
+
+/// Parse an ident response, checking that it refers to the expected ports, and
+/// return the username if there was one.
+///
+/// A successful response looks like `6193, 23 : USERID : UNIX : stjohns`.
+fn parse_response(line: &str, remote_port: u16, local_port: u16) -> Option<Username> {
+    let mut fields = line.trim_end().splitn(4, ':').map(str::trim);
+
+    let (ports, response_type) = (fields.next()?, fields.next()?);
+    let (_opsys, userid) = (fields.next()?, fields.next()?);
+
+    let (remote, local) = ports.split_once(',')?;
+    if remote.trim().parse::<u16>().ok() != Some(remote_port)
+        || local.trim().parse::<u16>().ok() != Some(local_port)
+    {
+        return None;
+    }
+    if response_type != "USERID" {
+        return None;
+    }
+
+    // The user ID can be almost anything, so only accept what's safe to display
+    if userid.is_empty()
+        || !userid
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return None;
+    }
+    Username::new_coerce(userid).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_responses() {
+        let parse = |line| parse_response(line, 6193, 23).map(|u| u.to_string());
+
+        assert_eq!(
+            parse("6193, 23 : USERID : UNIX : stjohns\r\n"),
+            Some("stjohns".to_string())
+        );
+        assert_eq!(
+            parse("6193,23:USERID:UNIX,US-ASCII:averylongusername"),
+            Some("averylongu".to_string())
+        );
+        assert_eq!(parse("6193, 23 : ERROR : NO-USER"), None);
+        assert_eq!(parse("6194, 23 : USERID : UNIX : stjohns"), None);
+        assert_eq!(parse("6193, 23 : USERID : UNIX : bad name"), None);
+        assert_eq!(parse("6193, 23 : USERID : UNIX : "), None);
+        assert_eq!(parse("garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn queries_ident_server() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ident_port = server.local_addr().unwrap().port();
+
+        let stand_in = tokio::spawn(async move {
+            let (stream, _) = server.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+
+            let mut request = String::new();
+            stream.read_line(&mut request).await.unwrap();
+            stream
+                .get_mut()
+                .write_all(format!("{} : USERID : UNIX : alice\r\n", request.trim()).as_bytes())
+                .await
+                .unwrap();
+            request
+        });
+
+        let remote = "127.0.0.1:40000".parse().unwrap();
+        let local = "127.0.0.1:6667".parse().unwrap();
+        let username = query(remote, local, ident_port).await.unwrap();
+
+        assert_eq!(stand_in.await.unwrap(), "40000 , 6667\r\n");
+        assert_eq!(username.map(|u| u.to_string()), Some("alice".to_string()));
+    }
+}
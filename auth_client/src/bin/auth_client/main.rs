@@ -26,7 +26,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let (dns_event_send, mut dns_event_recv) = unbounded_channel();
 
-    let client = dns_client::InternalDnsClient::new(dns_event_send);
+    let client = dns_client::InternalDnsClient::new(dns_event_send)?;
+
+    let (ident_event_send, mut ident_event_recv) = unbounded_channel();
+
+    let ident_client = ident_client::InternalIdentClient::new(ident_event_send);
+
+    let (dnsbl_event_send, mut dnsbl_event_recv) = unbounded_channel();
+
+    let dnsbl_client = dnsbl_client::InternalDnsblClient::new(dnsbl_event_send)?;
 
     loop {
         select!(
@@ -42,6 +50,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     {
                         client.start_lookup(conn_id, addr);
                     }
+                    ControlMessage::StartIdentLookup(conn_id, remote, local, timeout) =>
+                    {
+                        ident_client.start_lookup(conn_id, remote, local, timeout);
+                    }
+                    ControlMessage::StartDnsblLookup(conn_id, addr, zones) =>
+                    {
+                        dnsbl_client.start_lookup(conn_id, addr, zones);
+                    }
                 }
             },
             event = dns_event_recv.recv() =>
@@ -57,6 +73,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         break;
                     }
                 }
+            },
+            event = ident_event_recv.recv() =>
+            {
+                match event
+                {
+                    Some(evt) =>
+                    {
+                        event_send.send(AuthEvent::IdentResult(evt)).await?;
+                    }
+                    None =>
+                    {
+                        break;
+                    }
+                }
+            },
+            event = dnsbl_event_recv.recv() =>
+            {
+                match event
+                {
+                    Some(evt) =>
+                    {
+                        event_send.send(AuthEvent::DnsblResult(evt)).await?;
+                    }
+                    None =>
+                    {
+                        break;
+                    }
+                }
             }
         );
     }
@@ -65,3 +109,5 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 mod dns_client;
+mod dnsbl_client;
+mod ident_client;
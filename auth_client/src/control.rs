@@ -1,5 +1,6 @@
 use client_listener::ConnectionId;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 
 /// A message sent from the consumer process to the worker
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -9,4 +10,12 @@ pub enum ControlMessage {
 
     /// Start a reverse DNS lookup for the given connection ID and IP address
     StartDnsLookup(ConnectionId, IpAddr),
+
+    /// Start an ident lookup for the given connection ID, with the client's and our
+    /// own ends of the connection, giving up after the given timeout
+    StartIdentLookup(ConnectionId, SocketAddr, SocketAddr, Duration),
+
+    /// Start checking the given IP address against each of the named DNS blocklist
+    /// zones
+    StartDnsblLookup(ConnectionId, IpAddr, Vec<String>),
 }
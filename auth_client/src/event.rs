@@ -1,5 +1,6 @@
 use client_listener::ConnectionId;
-use sable_network::validated::Hostname;
+use sable_network::validated::{Hostname, Username};
+use std::net::{IpAddr, Ipv4Addr};
 
 /// The result of a DNS lookup
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -10,9 +11,44 @@ pub struct DnsResult {
     pub hostname: Option<Hostname>,
 }
 
+/// The result of an ident lookup
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct IdentResult {
+    /// The connection ID provided when initiating the request
+    pub conn: ConnectionId,
+    /// The username returned by the client's ident server, or None if there was no
+    /// usable response
+    pub username: Option<Username>,
+}
+
+/// A DNS blocklist zone in which an address was listed
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DnsblListing {
+    /// The zone, as provided when initiating the request
+    pub zone: String,
+    /// The addresses returned by the zone, which encode the reasons for the listing
+    pub replies: Vec<Ipv4Addr>,
+}
+
+/// The result of checking an address against DNS blocklists
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DnsblResult {
+    /// The connection ID provided when initiating the request
+    pub conn: ConnectionId,
+    /// The address which was checked
+    pub addr: IpAddr,
+    /// The zones in which the address is listed. Zones which didn't answer in time
+    /// are treated as not listing it.
+    pub listings: Vec<DnsblListing>,
+}
+
 /// A notification that something has completed
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub enum AuthEvent {
     /// A reverse DNS lookup has been completed
     DnsResult(DnsResult),
+    /// An ident lookup has been completed
+    IdentResult(IdentResult),
+    /// A DNS blocklist check has been completed
+    DnsblResult(DnsblResult),
 }
@@ -1,4 +1,5 @@
-//! Worker process for DNS and (eventually) identd checks, and library to communicate therewith.
+//! Worker process for DNS, identd and DNS blocklist checks, and library to communicate
+//! therewith.
 //!
 //! The [`AuthClient`] interface supports save and resume across `exec()` boundaries,
 //! via the [`save_state`](AuthClient::save_state) and [`resume`](AuthClient::resume)
@@ -11,20 +11,20 @@ pub struct Connection {
     pub id: ConnectionId,
     pub tls_info: Option<TlsInfo>,
     pub remote_addr: IpAddr,
+    pub websocket: Option<WebSocketInfo>,
+    /// The connection's TCP endpoints, if it wasn't received through a proxy
+    pub socket_info: Option<SocketInfo>,
     send_channel: UnboundedSender<ControlMessage>,
 }
 
 impl Connection {
-    pub(crate) fn new(
-        id: ConnectionId,
-        tls_info: Option<TlsInfo>,
-        remote_addr: IpAddr,
-        send_channel: UnboundedSender<ControlMessage>,
-    ) -> Self {
+    pub(crate) fn new(data: ConnectionData, send_channel: UnboundedSender<ControlMessage>) -> Self {
         Self {
-            id,
-            tls_info,
-            remote_addr,
+            id: data.id,
+            tls_info: data.tls_info,
+            remote_addr: data.remote_addr,
+            websocket: data.websocket,
+            socket_info: data.socket_info,
             send_channel,
         }
     }
@@ -34,6 +34,11 @@ impl Connection {
         self.tls_info.is_some()
     }
 
+    /// Is this a WebSocket connection?
+    pub fn is_websocket(&self) -> bool {
+        self.websocket.is_some()
+    }
+
     fn send_control(&self, msg: ConnectionControlDetail) {
         if let Err(e) = self
             .send_channel
@@ -62,6 +67,8 @@ impl Connection {
             id: self.id,
             remote_addr: self.remote_addr,
             tls_info: self.tls_info,
+            websocket: self.websocket,
+            socket_info: self.socket_info,
         }
     }
 }
@@ -15,6 +15,16 @@ pub enum ConnectionError {
     SendQueueFull,
     #[error("Input line was too long")]
     InputLineTooLong,
+    #[error("WebSocket error: {0}")]
+    WebSocketError(String),
+    #[error("PROXY header received from untrusted source")]
+    UntrustedProxy,
+    #[error("Invalid PROXY header: {0}")]
+    InvalidProxyHeader(String),
+    #[error("Connection limit exceeded: {0}")]
+    ConnectionLimit(String),
+    #[error("Handshake timed out")]
+    HandshakeTimeout,
 }
 
 /// An error that might occur when configuring a listener.
@@ -30,6 +40,8 @@ pub enum ListenerError {
     ControlQueueClosed,
     #[error("Error communicating with listener process")]
     CommunicationError,
+    #[error("Invalid TLS settings: {0}")]
+    InvalidTlsSettings(String),
 }
 
 impl From<std::io::Error> for ListenerError {
@@ -67,3 +79,9 @@ impl<T> From<TrySendError<T>> for ListenerError {
         }
     }
 }
+
+impl From<tokio_tungstenite::tungstenite::Error> for ConnectionError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocketError(e.to_string())
+    }
+}
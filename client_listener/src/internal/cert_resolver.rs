@@ -0,0 +1,314 @@
+use crate::*;
+
+use rustls::{
+    server::{AllowAnyAuthenticatedClient, ClientCertVerifier, ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+use x509_parser::prelude::*;
+
+/// Chooses the certificate to present to each TLS client: the one configured for the
+/// hostname it requested via SNI, if any, and the default otherwise.
+///
+/// Every TLS listener shares a single resolver, so replacing its certificates takes
+/// effect for new connections on all of them without rebuilding the listeners. For
+/// the same reason, it also holds the CA used to validate client certificates.
+pub(crate) struct ReloadableCertResolver {
+    certs: RwLock<CertificateSet>,
+}
+
+struct CertificateSet {
+    default: Arc<CertifiedKey>,
+    by_hostname: HashMap<String, Arc<CertifiedKey>>,
+    client_ca: Option<Arc<dyn ClientCertVerifier>>,
+    root_subjects: rustls::DistinguishedNames,
+}
+
+fn certified_key(
+    cert_chain: Vec<Vec<u8>>,
+    key: Vec<u8>,
+) -> Result<Arc<CertifiedKey>, rustls::Error> {
+    if cert_chain.is_empty() {
+        return Err(rustls::Error::General(
+            "Empty certificate chain".to_string(),
+        ));
+    }
+    let key = rustls::sign::any_supported_type(&rustls::PrivateKey(key))
+        .map_err(|_| rustls::Error::General("Unsupported private key type".to_string()))?;
+    let cert_chain = cert_chain.into_iter().map(rustls::Certificate).collect();
+
+    Ok(Arc::new(CertifiedKey::new(cert_chain, key)))
+}
+
+/// The subject of the DER-encoded certificate `cert`, in the form sent in a certificate
+/// request
+fn subject_name(cert: &[u8]) -> Option<rustls::internal::msgs::base::PayloadU16> {
+    let (_, cert) = X509Certificate::from_der(cert).ok()?;
+    Some(rustls::internal::msgs::base::PayloadU16::new(
+        cert.subject().as_raw().to_vec(),
+    ))
+}
+
+impl CertificateSet {
+    fn build(settings: TlsSettings) -> Result<Self, rustls::Error> {
+        let mut by_hostname = HashMap::new();
+
+        for sni in settings.sni_certificates {
+            let key = certified_key(sni.cert_chain, sni.key)?;
+            for hostname in sni.hostnames {
+                by_hostname.insert(hostname.to_ascii_lowercase(), Arc::clone(&key));
+            }
+        }
+
+        let default = certified_key(settings.cert_chain, settings.key)?;
+
+        // Clients choose which certificate to send by the subjects we list, so name the
+        // client CAs if there are any. Otherwise list something, since an empty list would
+        // mean not asking for a certificate at all.
+        let (client_ca, root_subjects) = if settings.client_ca_certs.is_empty() {
+            (None, subject_name(&default.cert[0].0).into_iter().collect())
+        } else {
+            let mut roots = rustls::RootCertStore::empty();
+            let mut subjects = Vec::new();
+            for cert in settings.client_ca_certs {
+                subjects.extend(subject_name(&cert));
+                roots.add(&rustls::Certificate(cert)).map_err(|e| {
+                    rustls::Error::General(format!("Invalid client CA certificate: {e:?}"))
+                })?;
+            }
+            (Some(AllowAnyAuthenticatedClient::new(roots)), subjects)
+        };
+
+        Ok(Self {
+            default,
+            by_hostname,
+            client_ca,
+            root_subjects,
+        })
+    }
+}
+
+/// Find the entry in `by_hostname` for `hostname`, either exactly or via a wildcard
+/// in place of its first label
+fn find_for_hostname<'a, T>(by_hostname: &'a HashMap<String, T>, hostname: &str) -> Option<&'a T> {
+    let hostname = hostname.to_ascii_lowercase();
+
+    by_hostname.get(&hostname).or_else(|| {
+        let (_, parent) = hostname.split_once('.')?;
+        by_hostname.get(&format!("*.{parent}"))
+    })
+}
+
+impl ReloadableCertResolver {
+    pub fn new(settings: TlsSettings) -> Result<Self, rustls::Error> {
+        Ok(Self {
+            certs: RwLock::new(CertificateSet::build(settings)?),
+        })
+    }
+
+    /// Replace the certificates in use. If any of the new ones are invalid, the old
+    /// ones are left in place.
+    pub fn reload(&self, settings: TlsSettings) -> Result<(), rustls::Error> {
+        let certs = CertificateSet::build(settings)?;
+        *self.certs.write().unwrap() = certs;
+        Ok(())
+    }
+
+    /// Determine whether `chain`, as presented by a client, was issued by the configured
+    /// client CA. Always false if there is none.
+    pub fn validate_client_chain(&self, chain: &[rustls::Certificate]) -> bool {
+        let certs = self.certs.read().unwrap();
+
+        match (&certs.client_ca, chain.split_first()) {
+            (Some(verifier), Some((end_entity, intermediates))) => verifier
+                .verify_client_cert(end_entity, intermediates, SystemTime::now())
+                .is_ok(),
+            _ => false,
+        }
+    }
+
+    /// The subjects to list in certificate requests sent to clients
+    pub fn root_subjects(&self) -> rustls::DistinguishedNames {
+        self.certs.read().unwrap().root_subjects.clone()
+    }
+}
+
+impl ReloadableCertResolver {
+    /// The certificate to present to a client which requested `server_name`
+    fn certificate_for(&self, server_name: Option<&str>) -> Arc<CertifiedKey> {
+        let certs = self.certs.read().unwrap();
+
+        server_name
+            .and_then(|name| find_for_hostname(&certs.by_hostname, name))
+            .unwrap_or(&certs.default)
+            .clone()
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.certificate_for(client_hello.server_name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sable_network::config::TlsConfig;
+
+    fn load(name: &str) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let data = TlsConfig {
+            key_file: format!("../configs/{name}.key").into(),
+            cert_file: format!("../configs/{name}.pem").into(),
+            sni_certificates: Vec::new(),
+            reload_interval: None,
+            client_ca_file: None,
+        }
+        .load_from_disk()
+        .unwrap();
+        (data.cert_chain, data.key)
+    }
+
+    fn settings(default: &str, sni: &[(&str, &[&str])]) -> TlsSettings {
+        let (cert_chain, key) = load(default);
+        TlsSettings {
+            cert_chain,
+            key,
+            sni_certificates: sni
+                .iter()
+                .map(|(name, hostnames)| {
+                    let (cert_chain, key) = load(name);
+                    SniCertificate {
+                        hostnames: hostnames.iter().map(|h| h.to_string()).collect(),
+                        cert_chain,
+                        key,
+                    }
+                })
+                .collect(),
+            client_ca_certs: Vec::new(),
+        }
+    }
+
+    /// The end-entity certificate which `resolver` presents for `server_name`
+    fn presented(resolver: &ReloadableCertResolver, server_name: Option<&str>) -> Vec<u8> {
+        resolver.certificate_for(server_name).cert[0].0.clone()
+    }
+
+    #[test]
+    fn find_hostnames() {
+        let by_hostname = HashMap::from([
+            ("irc.example.com".to_string(), 1),
+            ("*.example.org".to_string(), 2),
+        ]);
+
+        assert_eq!(find_for_hostname(&by_hostname, "irc.example.com"), Some(&1));
+        assert_eq!(find_for_hostname(&by_hostname, "IRC.Example.COM"), Some(&1));
+        assert_eq!(find_for_hostname(&by_hostname, "irc.example.org"), Some(&2));
+        assert_eq!(find_for_hostname(&by_hostname, "Web.EXAMPLE.org"), Some(&2));
+
+        // A wildcard only stands in for one label, and not for none
+        assert_eq!(find_for_hostname(&by_hostname, "a.b.example.org"), None);
+        assert_eq!(find_for_hostname(&by_hostname, "example.org"), None);
+
+        assert_eq!(find_for_hostname(&by_hostname, "other.example.com"), None);
+        assert_eq!(find_for_hostname(&by_hostname, "localhost"), None);
+        assert_eq!(find_for_hostname(&by_hostname, ""), None);
+    }
+
+    #[test]
+    fn resolve_by_sni() {
+        let resolver = ReloadableCertResolver::new(settings(
+            "server1",
+            &[
+                ("server2", &["irc.example.com"]),
+                ("services", &["*.example.org"]),
+            ],
+        ))
+        .unwrap();
+
+        let server1 = load("server1").0.remove(0);
+        let server2 = load("server2").0.remove(0);
+        let services = load("services").0.remove(0);
+
+        assert_eq!(presented(&resolver, None), server1);
+        assert_eq!(presented(&resolver, Some("other.example.com")), server1);
+        assert_eq!(presented(&resolver, Some("irc.example.com")), server2);
+        assert_eq!(presented(&resolver, Some("irc.example.org")), services);
+    }
+
+    #[test]
+    fn reload_replaces_certificates() {
+        let resolver =
+            ReloadableCertResolver::new(settings("server1", &[("server2", &["irc.example.com"])]))
+                .unwrap();
+        let subjects = resolver.root_subjects();
+
+        resolver.reload(settings("services", &[])).unwrap();
+
+        let services = load("services").0.remove(0);
+        assert_eq!(presented(&resolver, None), services);
+        assert_eq!(presented(&resolver, Some("irc.example.com")), services);
+        assert_ne!(resolver.root_subjects(), subjects);
+    }
+
+    #[test]
+    fn failed_reload_keeps_old_certificates() {
+        let resolver = ReloadableCertResolver::new(settings("server1", &[])).unwrap();
+
+        let mut bad_key = settings("server2", &[]);
+        bad_key.key = vec![1, 2, 3];
+        assert!(resolver.reload(bad_key).is_err());
+
+        let mut no_chain = settings("server2", &[]);
+        no_chain.cert_chain.clear();
+        assert!(resolver.reload(no_chain).is_err());
+
+        assert_eq!(presented(&resolver, None), load("server1").0.remove(0));
+    }
+
+    fn subject_of(name: &str) -> Vec<u8> {
+        let cert = load(name).0.remove(0);
+        let (_, cert) = X509Certificate::from_der(&cert).unwrap();
+        cert.subject().as_raw().to_vec()
+    }
+
+    #[test]
+    fn root_subjects_name_client_ca() {
+        let resolver = ReloadableCertResolver::new(settings("server1", &[])).unwrap();
+        let subjects: Vec<_> = resolver.root_subjects().into_iter().map(|s| s.0).collect();
+        assert_eq!(subjects, vec![subject_of("server1")]);
+
+        let mut with_ca = settings("server1", &[]);
+        with_ca.client_ca_certs = load("client_ca").0;
+        resolver.reload(with_ca).unwrap();
+
+        let subjects: Vec<_> = resolver.root_subjects().into_iter().map(|s| s.0).collect();
+        assert_eq!(subjects, vec![subject_of("client_ca")]);
+    }
+
+    #[test]
+    fn validate_client_chains() {
+        let chain = |name| {
+            load(name)
+                .0
+                .into_iter()
+                .map(rustls::Certificate)
+                .collect::<Vec<_>>()
+        };
+
+        let resolver = ReloadableCertResolver::new(settings("server1", &[])).unwrap();
+        assert!(!resolver.validate_client_chain(&chain("client")));
+
+        let mut with_ca = settings("server1", &[]);
+        with_ca.client_ca_certs = load("client_ca").0;
+        resolver.reload(with_ca).unwrap();
+
+        assert!(resolver.validate_client_chain(&chain("client")));
+        assert!(!resolver.validate_client_chain(&chain("server1")));
+        assert!(!resolver.validate_client_chain(&[]));
+    }
+}
@@ -1,19 +1,14 @@
+use super::cert_resolver::ReloadableCertResolver;
+use std::sync::Arc;
 use x509_parser::prelude::*;
 
-pub struct AcceptAnyClientCertVerifier(rustls::DistinguishedNames);
+/// Requests, but does not require or validate, a client certificate. The subjects it
+/// advertises come from the shared certificate resolver, so they follow reloads.
+pub struct AcceptAnyClientCertVerifier(Arc<ReloadableCertResolver>);
 
 impl AcceptAnyClientCertVerifier {
-    pub fn new(cert_for_name: &rustls::Certificate) -> Self {
-        let mut inner = Vec::new();
-
-        if let Ok((_, cert)) = X509Certificate::from_der(&cert_for_name.0) {
-            let name = cert.subject();
-            inner.push(rustls::internal::msgs::base::PayloadU16::new(
-                name.as_raw().to_vec(),
-            ));
-        }
-
-        Self(inner)
+    pub fn new(certs: Arc<ReloadableCertResolver>) -> Self {
+        Self(certs)
     }
 }
 
@@ -21,7 +16,7 @@ impl rustls::server::ClientCertVerifier for AcceptAnyClientCertVerifier {
     fn client_auth_root_subjects(&self) -> Option<rustls::DistinguishedNames> {
         // We have to provide some data here - None will abort the connection,
         // and Some(vec![]) will not send a certificate request
-        Some(self.0.clone())
+        Some(self.0.root_subjects())
     }
 
     fn verify_client_cert(
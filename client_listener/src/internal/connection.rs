@@ -2,12 +2,15 @@ use crate::internal::*;
 use crate::*;
 
 use sha1::{Digest, Sha1};
-use std::net::IpAddr;
+use sha2::{Sha256, Sha512};
+use std::{net::IpAddr, sync::Arc};
 use tokio::{
-    io::AsyncWriteExt,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
     net::TcpStream,
-    sync::mpsc::{channel, Sender},
+    sync::mpsc::{channel, Receiver, Sender},
 };
+use tokio_rustls::server::TlsStream;
+use x509_parser::prelude::{FromDer, X509Certificate};
 
 const SEND_QUEUE_LEN: usize = 100;
 
@@ -16,46 +19,71 @@ pub(crate) struct InternalConnection {
     pub remote_addr: IpAddr,
     pub control_channel: Sender<ConnectionControlDetail>,
     pub tls_info: Option<TlsInfo>,
+    pub websocket: Option<WebSocketInfo>,
+    pub socket_info: Option<SocketInfo>,
 }
 
 impl InternalConnection {
     pub async fn create_and_send(
         id: ConnectionId,
-        stream: TcpStream,
+        mut stream: TcpStream,
         conntype: InternalConnectionType,
+        options: &ListenerOptions,
+        limiter: &Arc<ConnectionLimiter>,
         events: Sender<InternalConnectionEventType>,
     ) -> Result<(), ConnectionError> {
         let (control_send, control_recv) = channel(SEND_QUEUE_LEN);
 
-        let addr = stream.peer_addr()?.ip();
+        let mut addr = stream.peer_addr()?.ip();
+        let mut socket_info = Some(SocketInfo {
+            local: stream.local_addr()?,
+            remote: stream.peer_addr()?,
+        });
+
+        // The PROXY header comes before anything else, including the TLS handshake
+        if let Some(proxy_settings) = &options.proxy_protocol {
+            // The endpoints we can see are the proxy's, not the client's
+            socket_info = None;
+
+            if !proxy_settings.is_trusted(addr) {
+                tracing::warn!(source=%addr, "Rejected connection from untrusted proxy");
+                return Err(ConnectionError::UntrustedProxy);
+            }
+            if let Some(source) = proxy_protocol::read_header(&mut stream).await? {
+                tracing::trace!(proxy=?addr, ?source, "Got PROXY header");
+                addr = source.ip();
+            }
+        }
+
+        // Check limits before doing anything expensive, like a TLS handshake
+        let slot = match limiter.try_acquire(addr) {
+            Ok(slot) => slot,
+            Err(e) => {
+                if matches!(conntype, InternalConnectionType::Clear) {
+                    let _ = stream.write_all(format!("ERROR :{e}\r\n").as_bytes()).await;
+                }
+                return Err(e);
+            }
+        };
+
         let connection_type = conntype.clone();
         let mut tls_info = None;
+        let mut websocket = None;
 
         match connection_type {
-            InternalConnectionType::Tls(tls_config) => {
-                let tls_acceptor: tokio_rustls::TlsAcceptor = tls_config.into();
+            InternalConnectionType::Tls(tls) => {
+                let tls_acceptor: tokio_rustls::TlsAcceptor = tls.config.clone().into();
                 match tls_acceptor.accept(stream).await {
                     Ok(mut tls_stream) => {
                         // We need to flush to make sure the tls handshake has finished, before the client
                         // info will be available
                         tls_stream.flush().await?;
 
-                        let fingerprint = tls_stream
-                            .get_ref()
-                            .1
-                            .peer_certificates()
-                            .and_then(|c| c.first())
-                            .map(|cert| {
-                                let mut hasher = Sha1::new();
-                                hasher.update(&cert.0);
-                                hex::encode(hasher.finalize()).as_str().into()
-                            });
-
-                        tls_info = Some(TlsInfo { fingerprint });
+                        tls_info = Some(Self::tls_info(&tls_stream, &tls));
 
                         let conntask =
                             ConnectionTask::new(id, tls_stream, control_recv, events.clone());
-                        tokio::spawn(conntask.run());
+                        tokio::spawn(slot.hold(conntask.run()));
                     }
                     Err(err) => {
                         let _ = events
@@ -68,7 +96,24 @@ impl InternalConnection {
             }
             InternalConnectionType::Clear => {
                 let conntask = ConnectionTask::new(id, stream, control_recv, events.clone());
-                tokio::spawn(conntask.run());
+                tokio::spawn(slot.hold(conntask.run()));
+            }
+            InternalConnectionType::WebSocket(settings) => {
+                websocket = Some(
+                    Self::start_websocket(id, stream, &settings, slot, control_recv, &events)
+                        .await?,
+                );
+            }
+            InternalConnectionType::SecureWebSocket(tls, settings) => {
+                let tls_acceptor: tokio_rustls::TlsAcceptor = tls.config.clone().into();
+                let mut tls_stream = tls_acceptor.accept(stream).await?;
+                tls_stream.flush().await?;
+
+                tls_info = Some(Self::tls_info(&tls_stream, &tls));
+                websocket = Some(
+                    Self::start_websocket(id, tls_stream, &settings, slot, control_recv, &events)
+                        .await?,
+                );
             }
         }
 
@@ -77,6 +122,8 @@ impl InternalConnection {
             remote_addr: addr,
             control_channel: control_send,
             tls_info,
+            websocket,
+            socket_info,
         };
 
         if events
@@ -90,11 +137,167 @@ impl InternalConnection {
         Ok(())
     }
 
+    fn tls_info(tls_stream: &TlsStream<TcpStream>, tls: &TlsContext) -> TlsInfo {
+        let chain = tls_stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .unwrap_or_default();
+        Self::client_certificate_info(chain, &tls.certs)
+    }
+
+    /// Describe the certificate chain presented by a client
+    fn client_certificate_info(
+        chain: &[rustls::Certificate],
+        certs: &ReloadableCertResolver,
+    ) -> TlsInfo {
+        let Some(cert) = chain.first() else {
+            return TlsInfo::default();
+        };
+
+        let parsed = X509Certificate::from_der(&cert.0)
+            .ok()
+            .map(|(_, parsed)| parsed);
+        let spki = parsed.as_ref().map(|parsed| parsed.public_key().raw);
+
+        let verified_subject = parsed
+            .as_ref()
+            .filter(|_| certs.validate_client_chain(chain))
+            .and_then(|parsed| {
+                let common_name = parsed.subject().iter_common_name().next()?;
+                common_name.as_str().ok().map(str::to_owned)
+            });
+
+        TlsInfo {
+            fingerprint: Some(hex::encode(Sha1::digest(&cert.0))),
+            spki_sha256: spki.map(|spki| hex::encode(Sha256::digest(spki))),
+            spki_sha512: spki.map(|spki| hex::encode(Sha512::digest(spki))),
+            verified_subject,
+        }
+    }
+
+    /// Perform the WebSocket handshake and spawn a task to handle the connection.
+    ///
+    /// If the handshake fails, the client was never a usable connection, so the error
+    /// is returned rather than being notified as an event.
+    async fn start_websocket<S>(
+        id: ConnectionId,
+        stream: S,
+        settings: &WebSocketSettings,
+        slot: ConnectionSlot,
+        control_recv: Receiver<ConnectionControlDetail>,
+        events: &Sender<InternalConnectionEventType>,
+    ) -> Result<WebSocketInfo, ConnectionError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (ws_stream, info) = websocket_task::accept(stream, settings).await?;
+
+        let task = WebSocketTask::new(id, ws_stream, info.protocol, control_recv, events.clone());
+        tokio::spawn(slot.hold(task.run()));
+
+        Ok(info)
+    }
+
     pub fn data(&self) -> ConnectionData {
         ConnectionData {
             id: self.id,
             remote_addr: self.remote_addr,
             tls_info: self.tls_info.clone(),
+            websocket: self.websocket.clone(),
+            socket_info: self.socket_info,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sable_network::config::TlsConfig;
+
+    fn resolver_and_chain(
+        client_ca: bool,
+        name: &str,
+    ) -> (ReloadableCertResolver, Vec<rustls::Certificate>) {
+        let load = |name: &str| {
+            TlsConfig {
+                key_file: format!("../configs/{name}.key").into(),
+                cert_file: format!("../configs/{name}.pem").into(),
+                sni_certificates: Vec::new(),
+                reload_interval: None,
+                client_ca_file: None,
+            }
+            .load_from_disk()
+            .unwrap()
+        };
+
+        let server = load("server1");
+        let settings = TlsSettings {
+            cert_chain: server.cert_chain,
+            key: server.key,
+            sni_certificates: Vec::new(),
+            client_ca_certs: if client_ca {
+                load("client_ca").cert_chain
+            } else {
+                Vec::new()
+            },
+        };
+        let chain = load(name)
+            .cert_chain
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+
+        (ReloadableCertResolver::new(settings).unwrap(), chain)
+    }
+
+    #[test]
+    fn certificate_fingerprints() {
+        let (certs, chain) = resolver_and_chain(false, "server1");
+        let info = InternalConnection::client_certificate_info(&chain, &certs);
+
+        assert_eq!(
+            info.fingerprint.as_deref(),
+            Some("1e73e460163a89fcadfd9acbc8fc115d6e4f3a81")
+        );
+        assert_eq!(
+            info.spki_sha256.as_deref(),
+            Some("a71d3cc4c6f3fad0042d95c1b7cfcc60642c7649a008b6b280ddaa1e0e4168b2")
+        );
+        assert_eq!(
+            info.spki_sha512.as_deref(),
+            Some(
+                "aa56f510bebfc3a6f90f80c0f606e8964dd7b3604fb0b1ba74c8f9590316004b\
+                 9174f39040de305b5c888356bc0c67b6841d41667f1a5b304356ca01d1fb8c5c"
+            )
+        );
+        assert_eq!(info.fingerprints().count(), 3);
+        assert_eq!(info.verified_subject, None);
+    }
+
+    #[test]
+    fn no_client_certificate() {
+        let (certs, _) = resolver_and_chain(true, "client");
+        let info = InternalConnection::client_certificate_info(&[], &certs);
+
+        assert_eq!(info.fingerprints().count(), 0);
+        assert_eq!(info.verified_subject, None);
+    }
+
+    #[test]
+    fn verified_subject_needs_client_ca() {
+        let (certs, chain) = resolver_and_chain(true, "client");
+        let info = InternalConnection::client_certificate_info(&chain, &certs);
+        assert_eq!(info.verified_subject.as_deref(), Some("testuser"));
+
+        let (certs, chain) = resolver_and_chain(false, "client");
+        let info = InternalConnection::client_certificate_info(&chain, &certs);
+        assert_eq!(info.verified_subject, None);
+        assert!(info.fingerprint.is_some());
+
+        // Issued by a different CA
+        let (certs, chain) = resolver_and_chain(true, "server2");
+        let info = InternalConnection::client_certificate_info(&chain, &certs);
+        assert_eq!(info.verified_subject, None);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::*;
+
+use ipnet::IpNet;
+use sable_network::network::config::{ConnectionLimitConfig, ConnectionLimitExceeded};
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Tracks connections across all of the process's listeners, so that the limits in
+/// [`ConnectionLimitConfig`] can be applied as soon as a connection is accepted.
+pub(crate) struct ConnectionLimiter {
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    settings: Option<ConnectionLimitConfig>,
+    per_ip: HashMap<IpAddr, u32>,
+    per_subnet: HashMap<IpNet, u32>,
+    /// Start of the current throttle window and number of connections within it,
+    /// for each recently seen address
+    recent: HashMap<IpAddr, (Instant, u32)>,
+    last_prune: Instant,
+}
+
+/// Held for as long as an accepted connection is open, releasing its place in the
+/// [`ConnectionLimiter`] when dropped
+pub(crate) struct ConnectionSlot {
+    limiter: Arc<ConnectionLimiter>,
+    counted: Option<(IpAddr, Option<IpNet>)>,
+}
+
+fn decrement<K: std::hash::Hash + Eq>(map: &mut HashMap<K, u32>, key: &K) {
+    if let Some(count) = map.get_mut(key) {
+        *count -= 1;
+        if *count == 0 {
+            map.remove(key);
+        }
+    }
+}
+
+impl ConnectionLimiter {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                settings: None,
+                per_ip: HashMap::new(),
+                per_subnet: HashMap::new(),
+                recent: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn set_settings(&self, settings: ConnectionLimitConfig) {
+        self.state.lock().unwrap().settings = Some(settings);
+    }
+
+    /// Take a place for a new connection from `ip`, or return an error if doing so
+    /// would exceed the configured limits
+    pub fn try_acquire(self: &Arc<Self>, ip: IpAddr) -> Result<ConnectionSlot, ConnectionError> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        let Some(settings) = &state.settings else {
+            // No limits have been configured, so there's nothing to track
+            return Ok(ConnectionSlot {
+                limiter: Arc::clone(self),
+                counted: None,
+            });
+        };
+
+        if settings.is_exempt(ip) {
+            return Ok(ConnectionSlot {
+                limiter: Arc::clone(self),
+                counted: None,
+            });
+        }
+
+        let subnet = settings.subnet_of(ip);
+
+        if let Some(max) = settings.max_per_ip {
+            if state.per_ip.get(&ip).copied().unwrap_or(0) >= max {
+                return Err(ConnectionError::ConnectionLimit(
+                    ConnectionLimitExceeded::PerIp(max).to_string(),
+                ));
+            }
+        }
+        if let (Some(max), Some(subnet)) = (settings.max_per_subnet, subnet) {
+            if state.per_subnet.get(&subnet).copied().unwrap_or(0) >= max {
+                return Err(ConnectionError::ConnectionLimit(
+                    ConnectionLimitExceeded::PerSubnet(subnet, max).to_string(),
+                ));
+            }
+        }
+
+        if let Some(max) = settings.throttle_count {
+            let now = Instant::now();
+            let period = Duration::from_secs(settings.throttle_period);
+
+            if now.duration_since(state.last_prune) >= period {
+                state
+                    .recent
+                    .retain(|_, (start, _)| now.duration_since(*start) < period);
+                state.last_prune = now;
+            }
+
+            let (start, count) = state.recent.entry(ip).or_insert((now, 0));
+            if now.duration_since(*start) >= period {
+                *start = now;
+                *count = 0;
+            }
+            if *count >= max {
+                return Err(ConnectionError::ConnectionLimit(
+                    "Connecting too fast; try again later".to_string(),
+                ));
+            }
+            *count += 1;
+        }
+
+        *state.per_ip.entry(ip).or_default() += 1;
+        if let Some(subnet) = subnet {
+            *state.per_subnet.entry(subnet).or_default() += 1;
+        }
+
+        Ok(ConnectionSlot {
+            limiter: Arc::clone(self),
+            counted: Some((ip, subnet)),
+        })
+    }
+}
+
+impl ConnectionSlot {
+    /// Run `task`, holding this slot until it completes
+    pub async fn hold<F: Future>(self, task: F) -> F::Output {
+        // `self` is dropped, releasing the slot, when this future completes
+        task.await
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        if let Some((ip, subnet)) = self.counted.take() {
+            let mut state = self.limiter.state.lock().unwrap();
+            decrement(&mut state.per_ip, &ip);
+            if let Some(subnet) = subnet {
+                decrement(&mut state.per_subnet, &subnet);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(settings: ConnectionLimitConfig) -> Arc<ConnectionLimiter> {
+        let limiter = Arc::new(ConnectionLimiter::new());
+        limiter.set_settings(settings);
+        limiter
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn unlimited_without_settings() {
+        let limiter = Arc::new(ConnectionLimiter::new());
+        let slots: Vec<_> = (0..100)
+            .map(|_| limiter.try_acquire(ip("192.0.2.1")).unwrap())
+            .collect();
+        assert_eq!(slots.len(), 100);
+    }
+
+    #[test]
+    fn per_ip_and_subnet_limits() {
+        let limiter = limiter(ConnectionLimitConfig {
+            max_per_ip: Some(2),
+            max_per_subnet: Some(3),
+            ..Default::default()
+        });
+
+        let _a = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        let _b = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        assert!(limiter.try_acquire(ip("192.0.2.1")).is_err());
+
+        let _c = limiter.try_acquire(ip("192.0.2.2")).unwrap();
+        assert!(limiter.try_acquire(ip("192.0.2.3")).is_err());
+
+        // Other subnets are counted separately
+        let _d = limiter.try_acquire(ip("198.51.100.1")).unwrap();
+        let _e = limiter.try_acquire(ip("2001:db8::1")).unwrap();
+    }
+
+    #[test]
+    fn exempt_sources_are_not_counted() {
+        let limiter = limiter(ConnectionLimitConfig {
+            max_per_ip: Some(1),
+            max_per_subnet: Some(1),
+            exempt: vec!["192.0.2.10/32".parse().unwrap()],
+            ..Default::default()
+        });
+
+        let _a = limiter.try_acquire(ip("192.0.2.10")).unwrap();
+        let _b = limiter.try_acquire(ip("192.0.2.10")).unwrap();
+        let _c = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        assert!(limiter.try_acquire(ip("192.0.2.2")).is_err());
+    }
+
+    #[test]
+    fn dropping_slot_releases_it() {
+        let limiter = limiter(ConnectionLimitConfig {
+            max_per_ip: Some(1),
+            ..Default::default()
+        });
+
+        let slot = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        assert!(limiter.try_acquire(ip("192.0.2.1")).is_err());
+        drop(slot);
+        let slot = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        drop(slot);
+
+        let state = limiter.state.lock().unwrap();
+        assert!(state.per_ip.is_empty());
+        assert!(state.per_subnet.is_empty());
+    }
+
+    #[tokio::test]
+    async fn held_slot_released_when_task_completes() {
+        let limiter = limiter(ConnectionLimitConfig {
+            max_per_ip: Some(1),
+            ..Default::default()
+        });
+
+        let (send, recv) = tokio::sync::oneshot::channel::<()>();
+        let slot = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+        let task = tokio::spawn(slot.hold(recv));
+
+        tokio::task::yield_now().await;
+        assert!(limiter.try_acquire(ip("192.0.2.1")).is_err());
+
+        send.send(()).unwrap();
+        task.await.unwrap().unwrap();
+        let _slot = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+    }
+
+    #[test]
+    fn throttling_counts_closed_connections() {
+        let limiter = limiter(ConnectionLimitConfig {
+            throttle_count: Some(2),
+            throttle_period: 60,
+            ..Default::default()
+        });
+
+        drop(limiter.try_acquire(ip("192.0.2.1")).unwrap());
+        drop(limiter.try_acquire(ip("192.0.2.1")).unwrap());
+
+        let error = limiter.try_acquire(ip("192.0.2.1")).err().unwrap();
+        assert!(matches!(error, ConnectionError::ConnectionLimit(_)));
+
+        // Each address has its own window
+        let _other = limiter.try_acquire(ip("192.0.2.2")).unwrap();
+    }
+
+    #[test]
+    fn throttle_window_expires() {
+        let limiter = limiter(ConnectionLimitConfig {
+            throttle_count: Some(1),
+            throttle_period: 60,
+            ..Default::default()
+        });
+
+        drop(limiter.try_acquire(ip("192.0.2.1")).unwrap());
+        assert!(limiter.try_acquire(ip("192.0.2.1")).is_err());
+
+        // Move the window back, as if it started a period ago
+        {
+            let mut state = limiter.state.lock().unwrap();
+            let window = state.recent.get_mut(&ip("192.0.2.1")).unwrap();
+            window.0 -= Duration::from_secs(60);
+        }
+        let _slot = limiter.try_acquire(ip("192.0.2.1")).unwrap();
+    }
+}
@@ -7,7 +7,7 @@ use tokio::{
     sync::mpsc::{channel, Receiver, Sender},
 };
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 pub(crate) struct Listener {
     //address: SocketAddr,
@@ -22,6 +22,8 @@ impl Listener {
         listener_id: ListenerId,
         address: SocketAddr,
         connection_type: InternalConnectionType,
+        options: ListenerOptions,
+        limiter: Arc<ConnectionLimiter>,
         event_channel: Sender<InternalConnectionEventType>,
     ) -> Self {
         let (control_send, control_receive) = channel(128);
@@ -31,6 +33,8 @@ impl Listener {
             control_receive,
             address,
             connection_type,
+            Arc::new(options),
+            limiter,
             listener_id,
         ));
 
@@ -47,6 +51,8 @@ impl Listener {
         control_channel: Receiver<ListenerControlDetail>,
         address: SocketAddr,
         connection_type: InternalConnectionType,
+        options: Arc<ListenerOptions>,
+        limiter: Arc<ConnectionLimiter>,
         listener_id: ListenerId,
     ) {
         if let Err(e) = match Self::listen_loop(
@@ -54,6 +60,8 @@ impl Listener {
             control_channel,
             address,
             connection_type,
+            options,
+            limiter,
             listener_id,
         )
         .await
@@ -76,6 +84,8 @@ impl Listener {
         mut control_channel: Receiver<ListenerControlDetail>,
         address: SocketAddr,
         connection_type: InternalConnectionType,
+        options: Arc<ListenerOptions>,
+        limiter: Arc<ConnectionLimiter>,
         listener_id: ListenerId,
     ) -> Result<(), std::io::Error> {
         let listener = TcpListener::bind(address).await?;
@@ -89,12 +99,23 @@ impl Listener {
                         {
                             let id = id_gen.next();
                             let connection_type = connection_type.clone();
+                            let options = Arc::clone(&options);
+                            let limiter = Arc::clone(&limiter);
                             let event_channel = event_channel.clone();
 
                             tokio::spawn(async move {
-                                if let Err(e) = InternalConnection::create_and_send(id, stream, connection_type, event_channel).await
+                                match InternalConnection::create_and_send(id, stream, connection_type, &options, &limiter, event_channel).await
                                 {
-                                    tracing::error!("Error creating connection: {}", e);
+                                    Ok(()) => (),
+                                    Err(ConnectionError::ConnectionLimit(reason)) => {
+                                        tracing::debug!("Refused connection: {}", reason);
+                                    }
+                                    Err(e @ (ConnectionError::UntrustedProxy | ConnectionError::InvalidProxyHeader(_))) => {
+                                        tracing::debug!("Refused proxied connection: {}", e);
+                                    }
+                                    Err(e) => {
+                                        tracing::error!("Error creating connection: {}", e);
+                                    }
                                 }
                             });
                             continue
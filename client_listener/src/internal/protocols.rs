@@ -3,14 +3,24 @@ use crate::id::*;
 use crate::protocols::*;
 
 use rustls::ServerConfig;
+use sable_network::network::config::ConnectionLimitConfig;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+/// The TLS configuration shared by all TLS listeners
+#[derive(Clone)]
+pub struct TlsContext {
+    pub config: Arc<ServerConfig>,
+    pub certs: Arc<super::ReloadableCertResolver>,
+}
+
 #[derive(Clone)]
 pub enum InternalConnectionType {
     Clear,
-    Tls(Arc<ServerConfig>),
+    Tls(TlsContext),
+    WebSocket(Arc<WebSocketSettings>),
+    SecureWebSocket(TlsContext, Arc<WebSocketSettings>),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,7 +31,7 @@ pub enum ConnectionControlDetail {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ListenerControlDetail {
-    Add(SocketAddr, ConnectionType),
+    Add(SocketAddr, ConnectionType, ListenerOptions),
     Close,
 }
 
@@ -30,6 +40,7 @@ pub enum ControlMessage {
     Connection(ConnectionId, ConnectionControlDetail),
     Listener(ListenerId, ListenerControlDetail),
     LoadTlsSettings(TlsSettings),
+    SetConnectionLimits(ConnectionLimitConfig),
     Shutdown,
     SaveForUpgrade,
 }
@@ -50,7 +61,7 @@ pub enum InternalConnectionEvent {
     NewListener(ListenerData),
     ListenerError(ListenerId, ListenerError),
     ListenerClosed(ListenerId),
-    BadTlsConfig,
+    TlsSettingsLoaded(Result<(), ListenerError>),
     CommunicationError,
 }
 
@@ -0,0 +1,306 @@
+//! Parsing of HAProxy PROXY protocol headers, as described in
+//! <https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt>
+
+use crate::*;
+
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// How long a proxy has to send the header after connecting
+const HEADER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest possible version 1 header, including the line ending
+const V1_MAX_LENGTH: usize = 107;
+
+/// The longest version 2 address block we'll accept. Addresses take at most 216 bytes;
+/// the rest leaves room for any TLVs the proxy might append.
+const V2_MAX_LENGTH: usize = 1024;
+
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+fn invalid(message: &str) -> ConnectionError {
+    ConnectionError::InvalidProxyHeader(message.to_string())
+}
+
+/// Read a PROXY protocol header (of either version) from `stream`, without consuming
+/// anything which follows it.
+///
+/// Returns the original source address of the connection, or `None` if the proxy
+/// didn't provide one (for health checks, or connections over unsupported protocols),
+/// in which case the proxy's own address should be used.
+pub(crate) async fn read_header<S>(stream: &mut S) -> Result<Option<SocketAddr>, ConnectionError>
+where
+    S: AsyncRead + Unpin,
+{
+    tokio::time::timeout(HEADER_TIMEOUT, read_header_inner(stream))
+        .await
+        .map_err(|_| invalid("timed out"))?
+}
+
+async fn read_header_inner<S>(stream: &mut S) -> Result<Option<SocketAddr>, ConnectionError>
+where
+    S: AsyncRead + Unpin,
+{
+    // Both versions' headers are at least this long, so it's safe to read this much
+    // before knowing which we have
+    let mut prefix = [0u8; 12];
+    stream.read_exact(&mut prefix).await?;
+
+    if prefix == V2_SIGNATURE {
+        read_v2(stream).await
+    } else if prefix.starts_with(b"PROXY ") {
+        read_v1(stream, &prefix).await
+    } else {
+        Err(invalid("missing PROXY header"))
+    }
+}
+
+async fn read_v1<S>(stream: &mut S, prefix: &[u8]) -> Result<Option<SocketAddr>, ConnectionError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut line = prefix.to_vec();
+
+    // Read a byte at a time, to avoid consuming anything after the header
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(invalid("header too long"));
+        }
+        line.push(stream.read_u8().await?);
+    }
+
+    let line = std::str::from_utf8(&line[..line.len() - 2]).map_err(|_| invalid("not ASCII"))?;
+    parse_v1(line)
+}
+
+/// Parse a version 1 header line, without its line ending
+fn parse_v1(line: &str) -> Result<Option<SocketAddr>, ConnectionError> {
+    let fields: Vec<&str> = line.split(' ').collect();
+
+    match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", protocol @ ("TCP4" | "TCP6"), source, _dest, source_port, _dest_port] => {
+            let ip: IpAddr = source.parse().map_err(|_| invalid("bad source address"))?;
+            let port: u16 = source_port
+                .parse()
+                .map_err(|_| invalid("bad source port"))?;
+
+            if ip.is_ipv4() != (*protocol == "TCP4") {
+                return Err(invalid("address doesn't match protocol"));
+            }
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(invalid("malformed header")),
+    }
+}
+
+async fn read_v2<S>(stream: &mut S) -> Result<Option<SocketAddr>, ConnectionError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+
+    let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+    if length > V2_MAX_LENGTH {
+        return Err(invalid("header too long"));
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body).await?;
+
+    parse_v2(header[0], header[1], &body)
+}
+
+/// Parse the version/command and family bytes and the address block of a version 2
+/// header
+fn parse_v2(
+    version_command: u8,
+    family: u8,
+    body: &[u8],
+) -> Result<Option<SocketAddr>, ConnectionError> {
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported version"));
+    }
+
+    match version_command & 0x0f {
+        // LOCAL: the proxy's own connection, e.g. a health check
+        0x0 => return Ok(None),
+        // PROXY
+        0x1 => (),
+        _ => return Err(invalid("unsupported command")),
+    }
+
+    match family {
+        // TCP over IPv4
+        0x11 => {
+            let addrs = body.get(..12).ok_or_else(|| invalid("header too short"))?;
+            let ip = Ipv4Addr::new(addrs[0], addrs[1], addrs[2], addrs[3]);
+            let port = u16::from_be_bytes([addrs[8], addrs[9]]);
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        }
+        // TCP over IPv6
+        0x21 => {
+            let addrs = body.get(..36).ok_or_else(|| invalid("header too short"))?;
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&addrs[..16]);
+            let port = u16::from_be_bytes([addrs[32], addrs[33]]);
+            Ok(Some(SocketAddr::new(Ipv6Addr::from(octets).into(), port)))
+        }
+        // Anything else carries no address we can use
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(mut input: &[u8]) -> Result<Option<SocketAddr>, ConnectionError> {
+        let result = read_header(&mut input).await;
+        // Nothing after the header should be consumed
+        if result.is_ok() {
+            assert_eq!(input, b"rest");
+        }
+        result
+    }
+
+    fn v2(version_command: u8, family: u8, body: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(version_command);
+        header.push(family);
+        header.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        header.extend_from_slice(body);
+        header.extend_from_slice(b"rest");
+        header
+    }
+
+    fn assert_invalid(result: Result<Option<SocketAddr>, ConnectionError>) {
+        assert!(
+            matches!(result, Err(ConnectionError::InvalidProxyHeader(_))),
+            "{:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn v1_tcp4() {
+        let result = read(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 6697\r\nrest").await;
+        assert_eq!(result.unwrap(), Some("192.0.2.1:56324".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn v1_tcp6() {
+        let result = read(b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 6697\r\nrest").await;
+        assert_eq!(
+            result.unwrap(),
+            Some("[2001:db8::1]:56324".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn v1_unknown() {
+        let result = read(b"PROXY UNKNOWN\r\nrest").await;
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn v1_rejects_mismatched_family() {
+        assert_invalid(read(b"PROXY TCP4 2001:db8::1 2001:db8::2 56324 6697\r\nrest").await);
+    }
+
+    #[tokio::test]
+    async fn v1_rejects_malformed_line() {
+        assert_invalid(read(b"PROXY TCP4 192.0.2.1 56324\r\nrest").await);
+        assert_invalid(read(b"PROXY TCP4 192.0.2.1 198.51.100.1 port 6697\r\nrest").await);
+    }
+
+    #[tokio::test]
+    async fn v1_rejects_overlong_line() {
+        let mut line = b"PROXY UNKNOWN ".to_vec();
+        line.resize(200, b'x');
+        line.extend_from_slice(b"\r\nrest");
+        assert_invalid(read(&line).await);
+    }
+
+    #[tokio::test]
+    async fn v2_local() {
+        let result = read(&v2(0x20, 0x00, &[])).await;
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn v2_proxy_tcp4() {
+        let body = [192, 0, 2, 1, 198, 51, 100, 1, 0xdc, 0x04, 0x1a, 0x2b];
+        let result = read(&v2(0x21, 0x11, &body)).await;
+        assert_eq!(result.unwrap(), Some("192.0.2.1:56324".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn v2_proxy_tcp6() {
+        let source: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let dest: Ipv6Addr = "2001:db8::2".parse().unwrap();
+        let mut body = Vec::new();
+        body.extend_from_slice(&source.octets());
+        body.extend_from_slice(&dest.octets());
+        body.extend_from_slice(&56324u16.to_be_bytes());
+        body.extend_from_slice(&6697u16.to_be_bytes());
+
+        let result = read(&v2(0x21, 0x21, &body)).await;
+        assert_eq!(
+            result.unwrap(),
+            Some("[2001:db8::1]:56324".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn v2_proxy_unsupported_family() {
+        // AF_UNIX addresses carry nothing we can use
+        let result = read(&v2(0x21, 0x31, &[0; 216])).await;
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn v2_rejects_short_address_block() {
+        assert_invalid(read(&v2(0x21, 0x11, &[192, 0, 2, 1])).await);
+    }
+
+    #[tokio::test]
+    async fn v2_rejects_bad_version_and_command() {
+        assert_invalid(read(&v2(0x11, 0x11, &[0; 12])).await);
+        assert_invalid(read(&v2(0x22, 0x11, &[0; 12])).await);
+    }
+
+    #[tokio::test]
+    async fn v2_rejects_oversized_length() {
+        assert_invalid(read(&v2(0x21, 0x11, &[0; V2_MAX_LENGTH + 1])).await);
+    }
+
+    #[tokio::test]
+    async fn rejects_truncated_headers() {
+        let truncated = read(b"PROXY TCP4 192.0.2.1").await;
+        assert!(
+            matches!(truncated, Err(ConnectionError::IoError(_))),
+            "{:?}",
+            truncated
+        );
+
+        let mut header = v2(0x21, 0x11, &[0; 12]);
+        header.truncate(20);
+        let truncated = read(&header).await;
+        assert!(
+            matches!(truncated, Err(ConnectionError::IoError(_))),
+            "{:?}",
+            truncated
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_signature() {
+        assert_invalid(read(b"GET / HTTP/1.1\r\n\r\nrest").await);
+        assert_invalid(read(b"\r\n\r\n\0\r\nQUITX\x21\x11\x00\x00rest").await);
+        assert_invalid(read(b"proxy TCP4 192.0.2.1 198.51.100.1 1 2\r\nrest").await);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::internal::*;
+use crate::*;
+
+use futures::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    select,
+    sync::mpsc::{Receiver, Sender},
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        handshake::server::{ErrorResponse, Request, Response},
+        http::{header, HeaderValue, StatusCode},
+        protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig},
+        Message,
+    },
+    WebSocketStream,
+};
+
+/// How long a client has to complete the WebSocket handshake after connecting
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accept a WebSocket handshake on `stream`, checking the client's origin against
+/// `settings` and negotiating one of the IRCv3 subprotocols if the client offers any.
+pub(crate) async fn accept<S>(
+    stream: S,
+    settings: &WebSocketSettings,
+) -> Result<(WebSocketStream<S>, WebSocketInfo), ConnectionError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut info = WebSocketInfo {
+        protocol: None,
+        origin: None,
+    };
+
+    // The error type is dictated by tungstenite
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, mut response: Response| {
+        let origin = request
+            .headers()
+            .get(header::ORIGIN)
+            .and_then(|v| v.to_str().ok());
+
+        if !settings.origin_allowed(origin) {
+            tracing::debug!(
+                ?origin,
+                "Rejecting WebSocket connection from disallowed origin"
+            );
+            let mut error = ErrorResponse::new(Some("Origin not allowed".to_string()));
+            *error.status_mut() = StatusCode::FORBIDDEN;
+            return Err(error);
+        }
+        info.origin = origin.map(ToOwned::to_owned);
+
+        // Use the first of the client's offered subprotocols that we support
+        info.protocol = request
+            .headers()
+            .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .find_map(|name| WebSocketProtocol::from_name(name.trim()));
+
+        if let Some(protocol) = info.protocol {
+            response.headers_mut().insert(
+                header::SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(protocol.name()),
+            );
+        }
+
+        Ok(response)
+    };
+
+    let config = WebSocketConfig {
+        // Leave room for oversized messages to be received and rejected in the same
+        // way as for line-based connections
+        max_message_size: Some(2 * crate::MAX_MSG_SIZE as usize),
+        max_frame_size: Some(2 * crate::MAX_MSG_SIZE as usize),
+        ..Default::default()
+    };
+
+    let handshake = tokio_tungstenite::accept_hdr_async_with_config(stream, callback, Some(config));
+    let ws_stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+        .await
+        .map_err(|_| ConnectionError::HandshakeTimeout)??;
+
+    Ok((ws_stream, info))
+}
+
+/// Equivalent of [`ConnectionTask`] for WebSocket connections, where each message is
+/// carried in its own frame rather than being terminated by a newline.
+pub(crate) struct WebSocketTask<S> {
+    id: ConnectionId,
+    conn: WebSocketStream<S>,
+    protocol: Option<WebSocketProtocol>,
+    control_channel: Receiver<ConnectionControlDetail>,
+    event_channel: Sender<InternalConnectionEventType>,
+}
+
+impl<S> WebSocketTask<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    pub fn new(
+        id: ConnectionId,
+        stream: WebSocketStream<S>,
+        protocol: Option<WebSocketProtocol>,
+        control_channel: Receiver<ConnectionControlDetail>,
+        event_channel: Sender<InternalConnectionEventType>,
+    ) -> Self {
+        Self {
+            id,
+            conn: stream,
+            protocol,
+            control_channel,
+            event_channel,
+        }
+    }
+
+    /// Convert one outgoing line into a frame of the negotiated type
+    fn frame(&self, line: &str) -> Message {
+        match self.protocol {
+            Some(WebSocketProtocol::Binary) => Message::Binary(line.as_bytes().to_vec()),
+            Some(WebSocketProtocol::Text) | None => Message::Text(line.to_owned()),
+        }
+    }
+
+    async fn notify(&mut self, event: InternalConnectionEvent) -> bool {
+        if self
+            .event_channel
+            .send(InternalConnectionEventType::Event(event))
+            .await
+            .is_err()
+        {
+            tracing::error!("Error notifying event on connection {:?}", self.id);
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Handle an incoming message. Returns false if the connection should be closed.
+    async fn received(&mut self, message: String) -> bool {
+        // Clients shouldn't send line endings, but tolerate them if they do
+        let message = message.trim_end_matches(['\r', '\n']);
+
+        if message.len() as u64 > crate::MAX_MSG_SIZE {
+            // Unlike a line-based connection, there's no way to resynchronise with a
+            // client that's sending frames we won't accept
+            self.notify(InternalConnectionEvent::ConnectionError(
+                self.id,
+                ConnectionError::InputLineTooLong,
+            ))
+            .await;
+            let _ = self
+                .conn
+                .close(Some(CloseFrame {
+                    code: CloseCode::Size,
+                    reason: "Message too long".into(),
+                }))
+                .await;
+            return false;
+        }
+
+        self.notify(InternalConnectionEvent::Message(
+            self.id,
+            message.to_owned(),
+        ))
+        .await
+    }
+
+    pub async fn run(mut self) {
+        'outer: loop {
+            select! {
+                control = self.control_channel.recv() => match control
+                {
+                    None => { break; },
+                    Some(ConnectionControlDetail::Close) => {
+                        let _ = self.conn.close(None).await;
+                        break;
+                    },
+                    Some(ConnectionControlDetail::Send(msg)) => {
+                        for line in msg.split_terminator("\r\n") {
+                            let frame = self.frame(line);
+                            if self.conn.feed(frame).await.is_err() {
+                                break 'outer;
+                            }
+                        }
+                        if self.conn.flush().await.is_err() {
+                            break;
+                        }
+                    }
+                },
+                message = self.conn.next() => match message {
+                    None | Some(Ok(Message::Close(_))) => { break; },
+                    Some(Ok(Message::Text(text))) => {
+                        if !self.received(text).await {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Binary(data))) => {
+                        if !self.received(String::from_utf8_lossy(&data).into_owned()).await {
+                            break;
+                        }
+                    }
+                    // Pings are answered by the WebSocket implementation
+                    Some(Ok(_)) => {},
+                    Some(Err(e)) => {
+                        if !self.notify(InternalConnectionEvent::ConnectionError(self.id, e.into())).await {
+                            return;
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.notify(InternalConnectionEvent::ConnectionError(
+            self.id,
+            ConnectionError::Closed,
+        ))
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{io::DuplexStream, sync::mpsc::channel};
+    use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+    type ClientResult = Result<(WebSocketStream<DuplexStream>, Response), tungstenite::Error>;
+
+    /// Run a handshake between a client sending the given headers and [`accept`]
+    async fn handshake(
+        settings: WebSocketSettings,
+        origin: Option<&str>,
+        protocols: Option<&str>,
+    ) -> (
+        Result<(WebSocketStream<DuplexStream>, WebSocketInfo), ConnectionError>,
+        ClientResult,
+    ) {
+        let (client_stream, server_stream) = tokio::io::duplex(64 * 1024);
+
+        let mut request = "ws://irc.example.com/".into_client_request().unwrap();
+        if let Some(origin) = origin {
+            request
+                .headers_mut()
+                .insert(header::ORIGIN, HeaderValue::from_str(origin).unwrap());
+        }
+        if let Some(protocols) = protocols {
+            request.headers_mut().insert(
+                header::SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_str(protocols).unwrap(),
+            );
+        }
+
+        tokio::join!(
+            accept(server_stream, &settings),
+            tokio_tungstenite::client_async(request, client_stream)
+        )
+    }
+
+    fn restricted() -> WebSocketSettings {
+        WebSocketSettings {
+            allowed_origins: vec!["https://web.example.com".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn negotiates_first_supported_protocol() {
+        let (server, client) = handshake(
+            WebSocketSettings::default(),
+            None,
+            Some("chat.example.com, binary.ircv3.net, text.ircv3.net"),
+        )
+        .await;
+
+        let (_, info) = server.unwrap();
+        assert_eq!(info.protocol, Some(WebSocketProtocol::Binary));
+
+        let (_, response) = client.unwrap();
+        assert_eq!(
+            response.headers().get(header::SEC_WEBSOCKET_PROTOCOL),
+            Some(&HeaderValue::from_static("binary.ircv3.net"))
+        );
+    }
+
+    #[tokio::test]
+    async fn no_protocol_unless_offered() {
+        let (server, client) = handshake(WebSocketSettings::default(), None, None).await;
+
+        let (_, info) = server.unwrap();
+        assert_eq!(info.protocol, None);
+        let (_, response) = client.unwrap();
+        assert!(response
+            .headers()
+            .get(header::SEC_WEBSOCKET_PROTOCOL)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_disallowed_origin() {
+        let (server, client) =
+            handshake(restricted(), Some("https://evil.example.com"), None).await;
+
+        assert!(server.is_err());
+        match client {
+            Err(tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), StatusCode::FORBIDDEN)
+            }
+            other => panic!("Unexpected handshake result: {:?}", other.map(|r| r.1)),
+        }
+    }
+
+    #[tokio::test]
+    async fn records_allowed_origin() {
+        let (server, _client) =
+            handshake(restricted(), Some("https://WEB.example.com"), None).await;
+
+        let (_, info) = server.unwrap();
+        assert_eq!(info.origin.as_deref(), Some("https://WEB.example.com"));
+    }
+
+    #[tokio::test]
+    async fn closes_on_oversized_message() {
+        let (server, client) = handshake(WebSocketSettings::default(), None, None).await;
+        let (server, info) = server.unwrap();
+        let (mut client, _) = client.unwrap();
+
+        let id = ConnectionIdGenerator::new(ListenerIdGenerator::new(0).next(), 0).next();
+        let (_control_send, control_recv) = channel(1);
+        let (event_send, mut event_recv) = channel(8);
+        let task = WebSocketTask::new(id, server, info.protocol, control_recv, event_send);
+        tokio::spawn(task.run());
+
+        client
+            .send(Message::Text("a".repeat(crate::MAX_MSG_SIZE as usize + 1)))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            event_recv.recv().await,
+            Some(InternalConnectionEventType::Event(
+                InternalConnectionEvent::ConnectionError(_, ConnectionError::InputLineTooLong)
+            ))
+        ));
+        match client.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => assert_eq!(frame.code, CloseCode::Size),
+            other => panic!("Expected close frame, got {:?}", other),
+        }
+    }
+}
@@ -9,6 +9,10 @@
 //! the child worker process and an asynchronous task to manage communications.
 //! Once a listener has been created, new connections and any events on existing
 //! connections will be sent via the provided event channel.
+//!
+//! As well as line-based TCP and TLS connections, listeners can accept WebSocket
+//! clients speaking the IRCv3 `text.ircv3.net` or `binary.ircv3.net` subprotocols.
+//! These are presented to the application in the same way, one message at a time.
 
 /// Maximum serialised size of a client message
 pub const MAX_MSG_SIZE: u64 = 8191 + 512 + 512; // 8k for tags, 512 for non-tag message, 512 slack
@@ -41,11 +45,18 @@ mod internal {
     pub use protocols::*;
     pub mod connection;
     pub(crate) use connection::*;
+    pub mod connection_limiter;
+    pub(crate) use connection_limiter::*;
     pub mod connection_task;
     pub(crate) use connection_task::*;
+    pub mod websocket_task;
+    pub(crate) use websocket_task::WebSocketTask;
     pub mod listener;
+    pub mod proxy_protocol;
     pub(crate) use listener::*;
+    pub mod cert_resolver;
     pub mod client_verifier;
+    pub(crate) use cert_resolver::*;
 }
 
 pub use internal::ControlMessage;
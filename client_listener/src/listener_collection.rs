@@ -1,19 +1,24 @@
 use crate::internal::*;
 use crate::*;
 
+use sable_network::network::config::ConnectionLimitConfig;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     convert::TryInto,
     env::current_exe,
     io,
     net::SocketAddr,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
 use sable_ipc::{channel as ipc_channel, Receiver as IpcReceiver, Sender as IpcSender};
 use tokio::{
     select,
-    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
     task,
     task::JoinHandle,
 };
@@ -43,6 +48,10 @@ type CommResult = std::io::Result<(
     IpcReceiver<InternalConnectionEvent>,
 )>;
 
+/// Senders waiting for the worker's responses to `LoadTlsSettings`. The worker handles
+/// control messages in order, so each response belongs to the oldest one.
+type PendingTlsLoads = Arc<Mutex<VecDeque<oneshot::Sender<Result<(), ListenerError>>>>>;
+
 /// Errors that could happen when initializing a listener collection
 #[derive(Debug, Error)]
 pub enum ListenerCollectionError {
@@ -70,6 +79,7 @@ pub struct ListenerCollection {
     listener_id_generator: ListenerIdGenerator,
     control_sender: UnboundedSender<ControlMessage>,
     comm_task: JoinHandle<CommResult>,
+    pending_tls_loads: PendingTlsLoads,
     connection_data: HashMap<ConnectionId, ConnectionData>,
     // We can't reconstruct the Child after save/resume, so we have to do without then
     child_process: Option<Child>,
@@ -141,6 +151,7 @@ impl ListenerCollection {
         };
 
         let child_pid = Pid::from_raw(child.id().try_into().unwrap());
+        let pending_tls_loads = PendingTlsLoads::default();
 
         let comm_task = task::spawn(run_communication_task(
             control_send,
@@ -148,6 +159,7 @@ impl ListenerCollection {
             local_control_recv,
             event_recv,
             event_channel,
+            Arc::clone(&pending_tls_loads),
             child_pid,
         ));
 
@@ -155,6 +167,7 @@ impl ListenerCollection {
             listener_id_generator: ListenerIdGenerator::new(0),
             control_sender: local_control_send,
             comm_task,
+            pending_tls_loads,
             connection_data: HashMap::new(),
             child_pid,
             child_process: Some(child),
@@ -234,6 +247,7 @@ impl ListenerCollection {
         let (local_control_send, local_control_recv) = unbounded_channel();
 
         let child_pid = Pid::from_raw(state.child_pid);
+        let pending_tls_loads = PendingTlsLoads::default();
 
         let handle = tokio::spawn(run_communication_task(
             control_sender,
@@ -241,12 +255,14 @@ impl ListenerCollection {
             local_control_recv,
             event_receiver,
             event_channel,
+            Arc::clone(&pending_tls_loads),
             child_pid,
         ));
 
         Ok(Self {
             control_sender: local_control_send,
             comm_task: handle,
+            pending_tls_loads,
             listener_id_generator: state.id_gen,
             connection_data: state.connection_data,
             child_process: None,
@@ -264,35 +280,66 @@ impl ListenerCollection {
         &self,
         address: SocketAddr,
         conn_type: ConnectionType,
+    ) -> Result<ListenerId, ListenerError> {
+        self.add_listener_with_options(address, conn_type, ListenerOptions::default())
+    }
+
+    /// Create a new listener as for [`add_listener`](Self::add_listener), with additional
+    /// options.
+    pub fn add_listener_with_options(
+        &self,
+        address: SocketAddr,
+        conn_type: ConnectionType,
+        options: ListenerOptions,
     ) -> Result<ListenerId, ListenerError> {
         let id = self.listener_id_generator.next();
 
-        let message = ControlMessage::Listener(id, ListenerControlDetail::Add(address, conn_type));
+        let message =
+            ControlMessage::Listener(id, ListenerControlDetail::Add(address, conn_type, options));
         self.control_sender.send(message)?;
         Ok(id)
     }
 
     /// Load the provided TLS settings. This must be done before a TLS listener can be
     /// created.
-    pub fn load_tls_certificates(
+    ///
+    /// It may be called again at any time to replace the certificates; all existing TLS
+    /// listeners will present the new ones to subsequent connections.
+    ///
+    /// This waits for the worker process to load the settings, and returns an error if
+    /// they are invalid, in which case any previously loaded certificates remain in use.
+    pub async fn load_tls_certificates(&self, settings: TlsSettings) -> Result<(), ListenerError> {
+        let (reply_send, reply_recv) = oneshot::channel();
+        {
+            // Hold the lock while sending, so that concurrent loads queue their reply
+            // senders in the same order as their requests
+            let mut pending = self.pending_tls_loads.lock().unwrap();
+            self.control_sender
+                .send(ControlMessage::LoadTlsSettings(settings))?;
+            pending.push_back(reply_send);
+        }
+
+        reply_recv
+            .await
+            .map_err(|_| ListenerError::CommunicationError)?
+    }
+
+    /// Limit the connections accepted from any one source, across all listeners.
+    ///
+    /// Limits only take account of connections accepted after they are first set, so
+    /// this should be called before any listeners are added.
+    pub fn set_connection_limits(
         &self,
-        key: Vec<u8>,
-        cert_chain: Vec<Vec<u8>>,
+        settings: ConnectionLimitConfig,
     ) -> Result<(), ListenerError> {
-        let settings = TlsSettings { key, cert_chain };
         Ok(self
             .control_sender
-            .send(ControlMessage::LoadTlsSettings(settings))?)
+            .send(ControlMessage::SetConnectionLimits(settings))?)
     }
 
     /// Restore a connection belonging to this connection from its saved [`ConnectionData`]
     pub fn restore_connection(&self, data: ConnectionData) -> Connection {
-        Connection::new(
-            data.id,
-            data.tls_info,
-            data.remote_addr,
-            self.control_sender.clone(),
-        )
+        Connection::new(data, self.control_sender.clone())
     }
 
     /// Shut down the worker process and communication task.
@@ -312,6 +359,7 @@ async fn run_communication_task<'a>(
     mut local_control_recv: UnboundedReceiver<ControlMessage>,
     event_receiver: IpcReceiver<InternalConnectionEvent>,
     event_sender: UnboundedSender<ConnectionEvent>,
+    pending_tls_loads: PendingTlsLoads,
     child_pid: Pid,
 ) -> CommResult {
     loop {
@@ -336,7 +384,7 @@ async fn run_communication_task<'a>(
                         NewConnection(data) =>
                         {
                             tracing::debug!(?data, "got new connection");
-                            let new_connection = Connection::new(data.id, data.tls_info, data.remote_addr, local_control_send.clone());
+                            let new_connection = Connection::new(data, local_control_send.clone());
                             ConnectionEvent::new(new_connection.id, new_connection)
                         },
                         ConnectionError(id, err) =>
@@ -348,6 +396,14 @@ async fn run_communication_task<'a>(
                             tracing::trace!(connection=?id, ?msg, "Got message");
                             ConnectionEvent::message(id, msg)
                         },
+                        TlsSettingsLoaded(result) =>
+                        {
+                            if let Some(reply) = pending_tls_loads.lock().unwrap().pop_front()
+                            {
+                                let _ = reply.send(result);
+                            }
+                            continue;
+                        },
                         _ => continue
                     };
                     if let Err(e) = event_sender.send(translated_event) {
@@ -17,10 +17,11 @@ use tokio::sync::mpsc::{channel, unbounded_channel, UnboundedReceiver};
 pub struct ListenerProcess {
     control_receiver: IpcReceiver<ControlMessage>,
     event_sender: Arc<IpcSender<InternalConnectionEvent>>,
-    tls_config: Option<Arc<rustls::ServerConfig>>,
+    tls_context: Option<TlsContext>,
 
     listeners: HashMap<ListenerId, Listener>,
     connections: HashMap<ConnectionId, InternalConnection>,
+    limiter: Arc<ConnectionLimiter>,
 
     shutdown_flag: Arc<AtomicBool>,
 }
@@ -33,22 +34,36 @@ impl ListenerProcess {
         Self {
             control_receiver,
             event_sender: Arc::new(event_sender),
-            tls_config: None,
+            tls_context: None,
             listeners: HashMap::new(),
             connections: HashMap::new(),
+            limiter: Arc::new(ConnectionLimiter::new()),
             shutdown_flag: Arc::new(AtomicBool::new(false)),
         }
     }
 
     fn translate_connection_type(
-        tls_config: &Option<Arc<rustls::ServerConfig>>,
+        tls_context: &Option<TlsContext>,
         ct: ConnectionType,
     ) -> Result<InternalConnectionType, ListenerError> {
         match ct {
             ConnectionType::Clear => Ok(InternalConnectionType::Clear),
             ConnectionType::Tls => {
-                if let Some(conf) = &tls_config {
-                    Ok(InternalConnectionType::Tls(conf.clone()))
+                if let Some(tls) = &tls_context {
+                    Ok(InternalConnectionType::Tls(tls.clone()))
+                } else {
+                    Err(ListenerError::NoTlsConfig)
+                }
+            }
+            ConnectionType::WebSocket(settings) => {
+                Ok(InternalConnectionType::WebSocket(Arc::new(settings)))
+            }
+            ConnectionType::SecureWebSocket(settings) => {
+                if let Some(tls) = &tls_context {
+                    Ok(InternalConnectionType::SecureWebSocket(
+                        tls.clone(),
+                        Arc::new(settings),
+                    ))
                 } else {
                     Err(ListenerError::NoTlsConfig)
                 }
@@ -56,23 +71,32 @@ impl ListenerProcess {
         }
     }
 
-    fn build_tls_config(settings: TlsSettings) -> Result<Arc<rustls::ServerConfig>, rustls::Error> {
-        let key = rustls::PrivateKey(settings.key);
-        let certs: Vec<rustls::Certificate> = settings
-            .cert_chain
-            .into_iter()
-            .map(rustls::Certificate)
-            .collect();
+    fn build_tls_context(settings: TlsSettings) -> Result<TlsContext, rustls::Error> {
+        let certs = Arc::new(ReloadableCertResolver::new(settings)?);
 
         let client_cert_verifier =
-            internal::client_verifier::AcceptAnyClientCertVerifier::new(&certs[0]);
-
-        Ok(Arc::new(
-            rustls::ServerConfig::builder()
-                .with_safe_defaults()
-                .with_client_cert_verifier(Arc::new(client_cert_verifier))
-                .with_single_cert(certs, key)?,
-        ))
+            internal::client_verifier::AcceptAnyClientCertVerifier::new(Arc::clone(&certs));
+
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(Arc::new(client_cert_verifier))
+            .with_cert_resolver(certs.clone());
+
+        Ok(TlsContext {
+            config: Arc::new(config),
+            certs,
+        })
+    }
+
+    /// Load new TLS settings. The first time, this creates the config for TLS listeners
+    /// to use; after that, it replaces the certificates which that config presents.
+    fn load_tls_settings(&mut self, settings: TlsSettings) -> Result<(), rustls::Error> {
+        if let Some(tls) = &self.tls_context {
+            tls.certs.reload(settings)
+        } else {
+            self.tls_context = Some(Self::build_tls_context(settings)?);
+            Ok(())
+        }
     }
 
     /// Sends events to the parent process, spawning the task into the background.
@@ -131,13 +155,13 @@ impl ListenerProcess {
                         {
                             match msg
                             {
-                                ListenerControlDetail::Add(address, conn_type) =>
+                                ListenerControlDetail::Add(address, conn_type, options) =>
                                 {
-                                    match Self::translate_connection_type(&self.tls_config, conn_type)
+                                    match Self::translate_connection_type(&self.tls_context, conn_type)
                                     {
                                         Ok(ct) =>
                                         {
-                                            let listener = Listener::new(id, address, ct, connection_event_send.clone());
+                                            let listener = Listener::new(id, address, ct, options, self.limiter.clone(), connection_event_send.clone());
 
                                             self.listeners.insert(id, listener);
                                         }
@@ -161,14 +185,15 @@ impl ListenerProcess {
                         }
                         Ok(ControlMessage::LoadTlsSettings(settings)) =>
                         {
-                            if let Ok(config) = Self::build_tls_config(settings)
-                            {
-                                self.tls_config = Some(config);
-                            }
-                            else
-                            {
-                                ipc_event_send.send(InternalConnectionEvent::BadTlsConfig).unwrap();
-                            }
+                            let result = self.load_tls_settings(settings).map_err(|e| {
+                                tracing::error!("Couldn't load TLS settings: {}", e);
+                                ListenerError::InvalidTlsSettings(e.to_string())
+                            });
+                            ipc_event_send.send(InternalConnectionEvent::TlsSettingsLoaded(result)).unwrap();
+                        }
+                        Ok(ControlMessage::SetConnectionLimits(settings)) =>
+                        {
+                            self.limiter.set_settings(settings);
                         }
                         Ok(ControlMessage::Shutdown) =>
                         {
@@ -2,19 +2,135 @@ use crate::error::*;
 use crate::id::*;
 use crate::Connection;
 
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// Information about a client connection's TLS status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TlsInfo {
+    /// Hex-encoded SHA-1 hash of the client certificate
     pub fingerprint: Option<String>,
+    /// Hex-encoded SHA-256 hash of the client certificate's public key (SPKI)
+    #[serde(default)]
+    pub spki_sha256: Option<String>,
+    /// Hex-encoded SHA-512 hash of the client certificate's public key (SPKI)
+    #[serde(default)]
+    pub spki_sha512: Option<String>,
+    /// The common name from the client certificate's subject, if the certificate was
+    /// successfully validated against the configured client CA
+    #[serde(default)]
+    pub verified_subject: Option<String>,
+}
+
+impl TlsInfo {
+    /// All of the available fingerprints of the client certificate
+    pub fn fingerprints(&self) -> impl Iterator<Item = &str> {
+        self.fingerprint
+            .iter()
+            .chain(&self.spki_sha256)
+            .chain(&self.spki_sha512)
+            .map(String::as_str)
+    }
+}
+
+/// The TCP endpoints of a connection accepted directly from the client, as needed
+/// for an ident lookup
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SocketInfo {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+}
+
+/// The IRCv3 WebSocket subprotocols
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebSocketProtocol {
+    /// `text.ircv3.net`: messages are sent as UTF-8 text frames
+    Text,
+    /// `binary.ircv3.net`: messages are sent as binary frames
+    Binary,
+}
+
+impl WebSocketProtocol {
+    /// The name used to negotiate this subprotocol
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Text => "text.ircv3.net",
+            Self::Binary => "binary.ircv3.net",
+        }
+    }
+
+    /// Look up a subprotocol by its negotiated name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text.ircv3.net" => Some(Self::Text),
+            "binary.ircv3.net" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Information about a client connection made over WebSocket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketInfo {
+    /// The negotiated subprotocol. Clients which didn't request one are sent text frames.
+    pub protocol: Option<WebSocketProtocol>,
+    /// The `Origin` header sent by the client, if any
+    pub origin: Option<String>,
+}
+
+/// Settings for a WebSocket listener
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WebSocketSettings {
+    /// Origins from which browsers are allowed to connect, e.g. `https://web.example.com`.
+    /// If empty, any origin is accepted. Clients which don't send an `Origin` header
+    /// aren't browsers, and are always accepted.
+    pub allowed_origins: Vec<String>,
+}
+
+impl WebSocketSettings {
+    /// Determine whether a client with the given `Origin` header may connect
+    pub fn origin_allowed(&self, origin: Option<&str>) -> bool {
+        match origin {
+            Some(origin) if !self.allowed_origins.is_empty() => self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin)),
+            _ => true,
+        }
+    }
+}
+
+/// Settings for listeners which accept connections from a proxy or load balancer
+/// using the PROXY protocol
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProxyProtocolSettings {
+    /// Addresses from which PROXY headers are trusted. Connections from anywhere else
+    /// are refused.
+    pub trusted_sources: Vec<IpNet>,
+}
+
+impl ProxyProtocolSettings {
+    /// Determine whether PROXY headers are trusted from the given address
+    pub fn is_trusted(&self, addr: IpAddr) -> bool {
+        self.trusted_sources.iter().any(|net| net.contains(&addr))
+    }
+}
+
+/// Options which apply to a listener regardless of its [`ConnectionType`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ListenerOptions {
+    /// If set, every connection must begin with a version 1 or 2 PROXY protocol
+    /// header, which supplies the client's real address
+    pub proxy_protocol: Option<ProxyProtocolSettings>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ConnectionType {
     Clear,
     Tls,
+    WebSocket(WebSocketSettings),
+    SecureWebSocket(WebSocketSettings),
 }
 
 /// The saved state of a [`Connection`]
@@ -23,6 +139,10 @@ pub struct ConnectionData {
     pub(crate) id: ConnectionId,
     pub(crate) remote_addr: IpAddr,
     pub(crate) tls_info: Option<TlsInfo>,
+    #[serde(default)]
+    pub(crate) websocket: Option<WebSocketInfo>,
+    #[serde(default)]
+    pub(crate) socket_info: Option<SocketInfo>,
 }
 
 /// The certificate chain and private key required to create a TLS listener.
@@ -33,6 +153,24 @@ pub struct ConnectionData {
 pub struct TlsSettings {
     pub cert_chain: Vec<Vec<u8>>,
     pub key: Vec<u8>,
+    /// Certificates to use instead for clients requesting particular hostnames via SNI
+    #[serde(default)]
+    pub sni_certificates: Vec<SniCertificate>,
+    /// CA certificates against which to validate client certificates. If empty, client
+    /// certificates are accepted but never treated as validated.
+    #[serde(default)]
+    pub client_ca_certs: Vec<Vec<u8>>,
+}
+
+/// A certificate chain and private key to be presented to TLS clients which request
+/// one of `hostnames` via SNI.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SniCertificate {
+    /// Hostnames for which to use this certificate. A name beginning with `*.` matches
+    /// any single label in its place.
+    pub hostnames: Vec<String>,
+    pub cert_chain: Vec<Vec<u8>>,
+    pub key: Vec<u8>,
 }
 
 /// Possible types of event that might occur on a given connection.
@@ -75,3 +213,41 @@ impl ConnectionEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_origin_allowed_by_default() {
+        let settings = WebSocketSettings::default();
+
+        assert!(settings.origin_allowed(None));
+        assert!(settings.origin_allowed(Some("https://anywhere.example.com")));
+    }
+
+    #[test]
+    fn origin_must_match_allow_list() {
+        let settings = WebSocketSettings {
+            allowed_origins: vec!["https://web.example.com".to_string()],
+        };
+
+        assert!(settings.origin_allowed(Some("https://web.example.com")));
+        assert!(settings.origin_allowed(Some("HTTPS://Web.Example.com")));
+        assert!(!settings.origin_allowed(Some("https://web.example.com.evil.net")));
+        assert!(!settings.origin_allowed(Some("http://web.example.com")));
+        // Non-browser clients don't send an origin
+        assert!(settings.origin_allowed(None));
+    }
+
+    #[test]
+    fn subprotocol_names_round_trip() {
+        for protocol in [WebSocketProtocol::Text, WebSocketProtocol::Binary] {
+            assert_eq!(
+                WebSocketProtocol::from_name(protocol.name()),
+                Some(protocol)
+            );
+        }
+        assert_eq!(WebSocketProtocol::from_name("irc"), None);
+    }
+}
@@ -35,6 +35,7 @@ impl HistoryServer {
             | NetworkStateChange::ChannelRename(_)
             | NetworkStateChange::NewServer(_)
             | NetworkStateChange::ServerQuit(_)
+            | NetworkStateChange::SyncPeerChange(_)
             | NetworkStateChange::NewAuditLogEntry(_)
             | NetworkStateChange::UserLoginChange(_)
             | NetworkStateChange::HistoryServerUpdate(_)
@@ -26,6 +26,7 @@ fn account_for_tag(update: &NetworkStateChange, net: &Network) -> Option<String>
         NetworkStateChange::UserModeChange(_) => None,
         NetworkStateChange::NewServer(_) => None,
         NetworkStateChange::ServerQuit(_) => None,
+        NetworkStateChange::SyncPeerChange(_) => None,
         NetworkStateChange::NewAuditLogEntry(_) => None,
         NetworkStateChange::HistoryServerUpdate(_) => None,
         NetworkStateChange::ServicesUpdate(_) => None,
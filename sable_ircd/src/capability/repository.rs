@@ -10,6 +10,9 @@ use strum::IntoEnumIterator;
 struct CapabilityEntry {
     cap: ClientCapability,
     values: RwLock<Vec<String>>,
+    /// Values advertised to TLS connections, if they differ from `values`
+    #[serde(default)]
+    tls_values: RwLock<Option<Vec<String>>>,
     available: AtomicBool,
 }
 
@@ -18,6 +21,8 @@ pub struct CapabilityRepository {
     supported_caps: Vec<CapabilityEntry>,
     all_caps_301: ArcSwap<String>,
     all_caps_302: ArcSwap<String>,
+    #[serde(default)]
+    all_caps_302_tls: ArcSwap<String>,
 }
 
 impl CapabilityRepository {
@@ -28,6 +33,7 @@ impl CapabilityRepository {
             supported_caps.push(CapabilityEntry {
                 cap,
                 values: RwLock::new(Vec::new()),
+                tls_values: RwLock::new(None),
                 available: AtomicBool::new(cap.is_default()),
             });
         }
@@ -36,6 +42,7 @@ impl CapabilityRepository {
             supported_caps,
             all_caps_301: ArcSwap::from_pointee(String::new()),
             all_caps_302: ArcSwap::from_pointee(String::new()),
+            all_caps_302_tls: ArcSwap::from_pointee(String::new()),
         };
 
         ret.update_supported_lists();
@@ -55,19 +62,33 @@ impl CapabilityRepository {
             .supported_caps
             .iter()
             .filter(|e| e.available.load(Ordering::Relaxed))
-            .map(CapabilityEntry::token_302)
+            .map(|e| e.token_302(false))
+            .join(" ");
+
+        let all_caps_302_tls = self
+            .supported_caps
+            .iter()
+            .filter(|e| e.available.load(Ordering::Relaxed))
+            .map(|e| e.token_302(true))
             .join(" ");
 
         self.all_caps_301.store(Arc::new(all_caps_301));
         self.all_caps_302.store(Arc::new(all_caps_302));
+        self.all_caps_302_tls.store(Arc::new(all_caps_302_tls));
     }
 
     pub fn supported_caps_301(&self) -> Arc<String> {
         self.all_caps_301.load_full()
     }
 
-    pub fn supported_caps_302(&self) -> Arc<String> {
-        self.all_caps_302.load_full()
+    /// The capability list for `CAP LS 302`, including values. Some capabilities
+    /// advertise different values to TLS connections.
+    pub fn supported_caps_302(&self, tls: bool) -> Arc<String> {
+        if tls {
+            self.all_caps_302_tls.load_full()
+        } else {
+            self.all_caps_302.load_full()
+        }
     }
 
     pub fn find(&self, name: &str) -> Option<ClientCapability> {
@@ -89,25 +110,42 @@ impl CapabilityRepository {
             }
             self.update_supported_lists();
         }
-
-        pub fn disable(&self, cap: ClientCapability)
-        {
-            for entry in &self.supported_caps
-            {
-                if entry.cap == cap
-                {
-                    entry.available.store(false, Ordering::Relaxed);
-                    entry.values.write().clear();
-                }
+    */
+    pub fn disable(&self, cap: ClientCapability) {
+        for entry in &self.supported_caps {
+            if entry.cap == cap {
+                entry.available.store(false, Ordering::Relaxed);
+                entry.values.write().clear();
+                *entry.tls_values.write() = None;
             }
-            self.update_supported_lists();
         }
-    */
+        self.update_supported_lists();
+    }
+
     pub fn enable_with_values(&self, cap: ClientCapability, values: &[String]) {
         for entry in &self.supported_caps {
             if entry.cap == cap {
                 entry.available.store(true, Ordering::Relaxed);
-                *entry.values.write().as_mut() = values.to_owned()
+                *entry.values.write().as_mut() = values.to_owned();
+                *entry.tls_values.write() = None;
+            }
+        }
+        self.update_supported_lists();
+    }
+
+    /// Enable `cap`, advertising `values` to plaintext connections and `tls_values`
+    /// to TLS connections
+    pub fn enable_with_tls_values(
+        &self,
+        cap: ClientCapability,
+        values: &[String],
+        tls_values: &[String],
+    ) {
+        for entry in &self.supported_caps {
+            if entry.cap == cap {
+                entry.available.store(true, Ordering::Relaxed);
+                *entry.values.write() = values.to_owned();
+                *entry.tls_values.write() = Some(tls_values.to_owned());
             }
         }
         self.update_supported_lists();
@@ -119,8 +157,13 @@ impl CapabilityEntry {
         self.cap.name().to_owned()
     }
 
-    fn token_302(&self) -> String {
+    fn token_302(&self, tls: bool) -> String {
+        let tls_values = self.tls_values.read();
         let values = self.values.read();
+        let values = match &*tls_values {
+            Some(tls_values) if tls => tls_values,
+            _ => &*values,
+        };
 
         if values.is_empty() {
             self.cap.name().to_owned()
@@ -133,3 +176,34 @@ impl CapabilityEntry {
         self.cap.name()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn has_token(list: &str, token: &str) -> bool {
+        list.split(' ').any(|t| t == token)
+    }
+
+    #[test]
+    fn sts_values_depend_on_tls() {
+        let caps = CapabilityRepository::new();
+        assert!(!has_token(&caps.supported_caps_301(), "sts"));
+
+        caps.enable_with_tls_values(
+            ClientCapability::Sts,
+            &["port=6697".to_string()],
+            &["duration=300".to_string(), "preload".to_string()],
+        );
+        assert!(has_token(&caps.supported_caps_301(), "sts"));
+        assert!(has_token(&caps.supported_caps_302(false), "sts=port=6697"));
+        assert!(has_token(
+            &caps.supported_caps_302(true),
+            "sts=duration=300,preload"
+        ));
+
+        caps.disable(ClientCapability::Sts);
+        assert!(!has_token(&caps.supported_caps_302(false), "sts=port=6697"));
+        assert!(!has_token(&caps.supported_caps_302(true), "sts"));
+    }
+}
@@ -39,6 +39,18 @@ pub struct ClientConnection {
 
     /// Capability flags
     pub capabilities: AtomicCapabilitySet,
+
+    /// Details supplied by a WEBIRC gateway, if this connection came through one
+    webirc: OnceLock<WebircInfo>,
+}
+
+/// Client details supplied by a trusted gateway using the `WEBIRC` command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebircInfo {
+    /// The configured name of the gateway
+    pub gateway: String,
+    /// The client's real IP address
+    pub ip: IpAddr,
 }
 
 /// Serialised state of a [`ClientConnection`], for later resumption
@@ -49,6 +61,8 @@ pub(super) struct ClientConnectionState {
     pre_client: Option<PreClient>,
     receive_queue: SavedThrottledQueue<String>,
     capabilities: ClientCapabilitySet,
+    #[serde(default)]
+    webirc: Option<WebircInfo>,
 }
 
 /// Operations that, while ongoing, will block a client from registering
@@ -57,6 +71,8 @@ pub(super) struct ClientConnectionState {
 pub enum ProgressFlag {
     CapNegotiation = 0x1,
     SaslAuthentication = 0x2,
+    IdentLookup = 0x4,
+    DnsblCheck = 0x8,
 }
 
 /// Information received from a client connection that has not yet completed registration
@@ -84,6 +100,15 @@ pub struct PreClient {
     pub sasl_session: OnceLock<SaslSessionId>,
     #[serde_as(as = "WrapOption<AccountId>")]
     pub sasl_account: OnceLock<AccountId>,
+    /// The username returned by an ident lookup, if any
+    #[serde_as(as = "WrapOption<Username>")]
+    #[serde(default)]
+    pub ident: OnceLock<Username>,
+    /// If a DNS blocklist requires this client to log in with SASL before registering,
+    /// the reason to give if it doesn't
+    #[serde_as(as = "WrapOption<String>")]
+    #[serde(default)]
+    pub sasl_required: OnceLock<String>,
 
     progress_flags: AtomicU32,
 }
@@ -103,6 +128,7 @@ impl ClientConnection {
             pre_client: ArcSwapOption::new(Some(Arc::new(PreClient::new()))),
             receive_queue: Movable::new(ThrottledQueue::new(throttle_settings, 16)),
             capabilities: AtomicCapabilitySet::new(),
+            webirc: OnceLock::new(),
         }
     }
 
@@ -118,6 +144,7 @@ impl ClientConnection {
             }),
             receive_queue: self.receive_queue.unwrap().save(),
             capabilities: (&self.capabilities).into(),
+            webirc: self.webirc.take(),
         }
     }
 
@@ -136,6 +163,10 @@ impl ClientConnection {
             pre_client: ArcSwapOption::new(state.pre_client.map(Arc::new)),
             receive_queue: Movable::new(ThrottledQueue::restore_from(state.receive_queue)),
             capabilities: state.capabilities.into(),
+            webirc: match state.webirc {
+                Some(v) => OnceLock::from(v),
+                None => OnceLock::new(),
+            },
         }
     }
 
@@ -144,9 +175,37 @@ impl ClientConnection {
         self.connection.id
     }
 
-    /// The remote IP address from which this client connected
+    /// The remote IP address from which this client connected, or the address
+    /// supplied by its WEBIRC gateway
     pub fn remote_addr(&self) -> IpAddr {
-        self.connection.remote_addr
+        match self.webirc.get() {
+            Some(webirc) => webirc.ip,
+            None => self.connection.remote_addr,
+        }
+    }
+
+    /// The WEBIRC gateway details for this connection, if any
+    pub fn webirc(&self) -> Option<&WebircInfo> {
+        self.webirc.get()
+    }
+
+    /// Record that this connection came through a WEBIRC gateway, replacing the
+    /// client's address and hostname.
+    ///
+    /// Returns false, changing nothing, if gateway details were already supplied or
+    /// the connection has completed registration.
+    pub fn set_webirc(&self, info: WebircInfo, hostname: Hostname) -> bool {
+        let Some(pre_client) = self.pre_client() else {
+            return false;
+        };
+        if self.webirc.set(info).is_err() {
+            return false;
+        }
+        // The hostname may already have been set by a DNS lookup on the gateway's
+        // address, so it can't simply be set in place
+        self.pre_client
+            .store(Some(Arc::new(pre_client.with_hostname(hostname))));
+        true
     }
 
     /// The TLS info for this connection, if any
@@ -242,10 +301,31 @@ impl PreClient {
             hostname: OnceLock::new(),
             sasl_session: OnceLock::new(),
             sasl_account: OnceLock::new(),
+            ident: OnceLock::new(),
+            sasl_required: OnceLock::new(),
             progress_flags: AtomicU32::new(0),
         }
     }
 
+    /// Copy this `PreClient`, replacing its hostname. Any ident or DNS blocklist
+    /// results are dropped, as they described the gateway rather than the client.
+    fn with_hostname(&self, hostname: Hostname) -> Self {
+        Self {
+            connected_at: self.connected_at,
+            attach_user_id: self.attach_user_id.clone(),
+            user: self.user.clone(),
+            extra_user_params: self.extra_user_params.clone(),
+            nick: self.nick.clone(),
+            realname: self.realname.clone(),
+            hostname: OnceLock::from(hostname),
+            sasl_session: self.sasl_session.clone(),
+            sasl_account: self.sasl_account.clone(),
+            ident: OnceLock::new(),
+            sasl_required: OnceLock::new(),
+            progress_flags: AtomicU32::new(self.progress_flags.load(Ordering::Relaxed)),
+        }
+    }
+
     /// Determine whether this connection is ready to complete registration.
     ///
     /// This will return true if the connection is ready to either register as a new user,
@@ -263,6 +343,16 @@ impl PreClient {
         can_register_new || can_attach
     }
 
+    /// The username with which to register: the ident response if there was one,
+    /// otherwise the one sent with `USER`, prefixed with `~`
+    pub fn registration_username(&self) -> Option<Username> {
+        if let Some(ident) = self.ident.get() {
+            return Some(*ident);
+        }
+        let user = self.user.get()?;
+        Username::new_coerce(&format!("~{user}")).ok()
+    }
+
     /// Determine whether this connection is ready to register as a new user
     pub fn can_register_new_user(&self) -> bool {
         self.user.get().is_some()
@@ -25,7 +25,10 @@ fn handle_cap(
                     &server,
                     &UnknownTarget,
                     "LS",
-                    server.client_capabilities().supported_caps_302().as_ref(),
+                    server
+                        .client_capabilities()
+                        .supported_caps_302(cmd.connection().tls_info().is_some())
+                        .as_ref(),
                 ));
             } else {
                 response.send(message::Cap::new(
@@ -46,7 +49,10 @@ fn handle_cap(
             let requested_arg =
                 cap_list.ok_or_else(|| make_numeric!(NotEnoughParameters, "CAP"))?;
 
-            if let Some(requested_caps) = translate_caps(server, requested_arg.split_whitespace()) {
+            if let Some(requested_caps) = translate_caps(
+                server.client_capabilities(),
+                requested_arg.split_whitespace(),
+            ) {
                 let mut new_caps = ClientCapabilitySet::new();
                 for cap in requested_caps {
                     new_caps.set(cap);
@@ -108,14 +114,45 @@ fn handle_cap(
 ///
 /// If any string is not a supported capability name, return `None`.
 fn translate_caps<'b>(
-    server: &ClientServer,
+    capabilities: &CapabilityRepository,
     iter: impl Iterator<Item = &'b str>,
 ) -> Option<Vec<ClientCapability>> {
     let mut ret = Vec::new();
 
     for item in iter {
-        ret.push(server.client_capabilities().find(item)?);
+        match capabilities.find(item)? {
+            // sts only advertises the policy, and clients mustn't request it. Treating
+            // it like an unknown capability means the whole REQ is NAKed, as with any
+            // other request that can't be granted in full.
+            ClientCapability::Sts => return None,
+            cap => ret.push(cap),
+        }
     }
 
     Some(ret)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requesting_sts_is_refused() {
+        let caps = CapabilityRepository::new();
+        caps.enable_with_tls_values(
+            ClientCapability::Sts,
+            &["port=6697".to_string()],
+            &["duration=300".to_string()],
+        );
+
+        assert_eq!(
+            translate_caps(&caps, ["message-tags"].into_iter()),
+            Some(vec![ClientCapability::MessageTags])
+        );
+        assert_eq!(translate_caps(&caps, ["sts"].into_iter()), None);
+        assert_eq!(
+            translate_caps(&caps, ["message-tags", "sts"].into_iter()),
+            None
+        );
+    }
+}
@@ -38,6 +38,7 @@ async fn handle_join(
 
                 let channel_id = server.ids().next();
                 cmd.new_event_with_response(channel_id, details).await;
+
                 (channel_id, MembershipFlagFlag::Op.into())
             }
         };
@@ -224,6 +224,13 @@ async fn handle_channel_mode(
             sent_unknown = true;
         }
     }
+    if let Some(registration) = chan.is_registered() {
+        // Changes which contradict the mode lock are silently ignored
+        registration
+            .mode_lock()
+            .restrict(&mut added, &mut removed, &mut key_change);
+    }
+
     if !added.is_empty() || !removed.is_empty() || !key_change.is_no_change() {
         let detail = event::ChannelModeChange {
             changed_by: source.id().into(),
@@ -18,5 +18,10 @@ async fn handle_cs<'a>(
 }
 
 mod access;
+mod akick;
+mod drop;
+mod info;
 mod register;
 mod role;
+mod set;
+mod transfer;
@@ -0,0 +1,160 @@
+use sable_network::{
+    policy::RegistrationPolicyService,
+    rpc::{RemoteServerResponse, RemoteServicesServerRequestType, RemoteServicesServerResponse},
+};
+
+use super::*;
+
+#[command_handler("AKICK", in("CS"))]
+async fn handle_akick(
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    services_target: ServicesTarget<'_>,
+    channel: wrapper::ChannelRegistration<'_>,
+    subcommand: Option<&str>,
+    mut args: ArgList<'_>,
+) -> CommandResult {
+    cmd.server()
+        .node()
+        .policy()
+        .can_manage_auto_kicks(&source.account, &channel)?;
+
+    if let Some(subcommand) = subcommand.map(|s| s.to_ascii_uppercase()) {
+        match subcommand.as_ref() {
+            "ADD" => akick_add(source, cmd, services_target, channel, args.next()?, args).await,
+            "DEL" => akick_del(source, cmd, services_target, channel, args.next()?).await,
+            "LIST" => akick_list(cmd, channel).await,
+            _ => {
+                cmd.notice("Syntax: CS AKICK <#channel> [ADD <mask> [reason] | DEL <mask> | LIST]");
+                Ok(())
+            }
+        }
+    } else {
+        akick_list(cmd, channel).await
+    }
+}
+
+async fn akick_list(cmd: &dyn Command, chan: wrapper::ChannelRegistration<'_>) -> CommandResult {
+    cmd.notice(format_args!("Auto-kick list for {}", chan.name()));
+    cmd.notice(" ");
+
+    for (index, entry) in chan.auto_kicks().enumerate() {
+        let setter = entry
+            .setter()
+            .map(|acc| acc.name().to_string())
+            .unwrap_or_else(|_| "<unknown>".to_string());
+
+        cmd.notice(format_args!(
+            "{}: {} ({}) set by {}",
+            index + 1,
+            entry.mask(),
+            entry.reason().unwrap_or("no reason given"),
+            setter
+        ));
+    }
+
+    Ok(())
+}
+
+async fn akick_add(
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    services_target: ServicesTarget<'_>,
+    chan: wrapper::ChannelRegistration<'_>,
+    mask: &str,
+    args: ArgList<'_>,
+) -> CommandResult {
+    cmd.server().node().policy().validate_auto_kick_mask(mask)?;
+
+    if chan.auto_kicks().any(|entry| entry.mask() == mask) {
+        cmd.notice(format_args!(
+            "{} is already on the auto-kick list for {}",
+            mask,
+            chan.name()
+        ));
+        return Ok(());
+    }
+
+    let reason = (!args.is_empty()).then(|| args.iter().collect::<Vec<_>>().join(" "));
+
+    let request = RemoteServicesServerRequestType::AddAutoKick {
+        source: source.account.id(),
+        channel: chan.id(),
+        mask: Pattern::new(mask.to_owned()),
+        reason,
+    }
+    .into();
+    let response = services_target.send_remote_request(request).await;
+
+    tracing::debug!(?response, "Got auto-kick response");
+    match response {
+        Ok(RemoteServerResponse::Success) => {
+            cmd.notice(format_args!(
+                "{} added to the auto-kick list for {}",
+                mask,
+                chan.name()
+            ));
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied)) => {
+            cmd.notice("Access denied");
+        }
+        Ok(response) => {
+            tracing::error!(?response, "Unexpected response adding auto-kick");
+            cmd.notice("Error updating auto-kick list");
+        }
+        Err(error) => {
+            tracing::error!(?error, "Error adding auto-kick");
+            cmd.notice("Error updating auto-kick list");
+        }
+    }
+
+    Ok(())
+}
+
+async fn akick_del(
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    services_target: ServicesTarget<'_>,
+    chan: wrapper::ChannelRegistration<'_>,
+    mask: &str,
+) -> CommandResult {
+    let Some(entry) = chan.auto_kicks().find(|entry| entry.mask() == mask) else {
+        cmd.notice(format_args!(
+            "{} is not on the auto-kick list for {}",
+            mask,
+            chan.name()
+        ));
+        return Ok(());
+    };
+
+    let request = RemoteServicesServerRequestType::RemoveAutoKick {
+        source: source.account.id(),
+        id: entry.id(),
+    }
+    .into();
+    let response = services_target.send_remote_request(request).await;
+
+    tracing::debug!(?response, "Got auto-kick response");
+    match response {
+        Ok(RemoteServerResponse::Success) => {
+            cmd.notice(format_args!(
+                "{} removed from the auto-kick list for {}",
+                mask,
+                chan.name()
+            ));
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied)) => {
+            cmd.notice("Access denied");
+        }
+        Ok(response) => {
+            tracing::error!(?response, "Unexpected response removing auto-kick");
+            cmd.notice("Error updating auto-kick list");
+        }
+        Err(error) => {
+            tracing::error!(?error, "Error removing auto-kick");
+            cmd.notice("Error updating auto-kick list");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use sable_network::{
+    policy::RegistrationPolicyService,
+    rpc::{RemoteServerResponse, RemoteServicesServerRequestType, RemoteServicesServerResponse},
+};
+
+use super::*;
+
+#[command_handler("DROP", in("CS"))]
+async fn handle_drop(
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    services_target: ServicesTarget<'_>,
+    channel: wrapper::ChannelRegistration<'_>,
+    confirm: Option<&str>,
+) -> CommandResult {
+    cmd.server()
+        .node()
+        .policy()
+        .can_drop_channel(&source.account, &channel)?;
+
+    if confirm.map(|s| s.eq_ignore_ascii_case("CONFIRM")) != Some(true) {
+        cmd.notice(format_args!(
+            "This will remove the registration of {} along with its access list, roles and settings. Use CS DROP {} CONFIRM to proceed.",
+            channel.name(),
+            channel.name()
+        ));
+        return Ok(());
+    }
+
+    let request = RemoteServicesServerRequestType::DropChannel {
+        source: source.account.id(),
+        channel: channel.id(),
+    }
+    .into();
+    let response = services_target.send_remote_request(request).await;
+
+    tracing::debug!(?response, "Got channel drop response");
+    match response {
+        Ok(RemoteServerResponse::Success) => {
+            cmd.notice(format_args!("{} has been dropped", channel.name()));
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied)) => {
+            cmd.notice("Access denied");
+        }
+        Ok(response) => {
+            tracing::error!(?response, "Unexpected response dropping channel");
+            cmd.notice("Error dropping channel");
+        }
+        Err(error) => {
+            tracing::error!(?error, "Error dropping channel");
+            cmd.notice("Error dropping channel");
+        }
+    }
+
+    Ok(())
+}
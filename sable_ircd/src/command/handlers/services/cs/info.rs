@@ -0,0 +1,54 @@
+use sable_network::{network::state::ChannelAccessFlag, policy::RegistrationPolicyService};
+
+use super::*;
+use crate::utils::format_recorded_timestamp;
+
+#[command_handler("INFO", in("CS"))]
+async fn handle_info(
+    source: UserSource<'_>,
+    cmd: &dyn Command,
+    channel: wrapper::ChannelRegistration<'_>,
+) -> CommandResult {
+    cmd.notice(format_args!("Information for {}", channel.name()));
+    cmd.notice(" ");
+
+    // Registrations which predate the registration time being recorded have zero here
+    cmd.notice(format_args!(
+        "Registered: {}",
+        format_recorded_timestamp(channel.registered())
+    ));
+
+    let founders = channel
+        .access_entries()
+        .filter(|access| {
+            access
+                .role()
+                .map(|role| role.flags().is_set(ChannelAccessFlag::Founder))
+                .unwrap_or(false)
+        })
+        .filter_map(|access| access.user().ok().map(|acc| acc.name().to_string()))
+        .collect::<Vec<_>>();
+    cmd.notice(format_args!("Founder(s): {}", founders.join(", ")));
+
+    if cmd
+        .server()
+        .node()
+        .policy()
+        .can_view_channel_settings(&source.user, &channel)
+        .is_ok()
+    {
+        let mode_lock = channel.mode_lock();
+        if !mode_lock.is_empty() {
+            cmd.notice(format_args!("Mode lock: {}", mode_lock));
+        }
+        cmd.notice(format_args!(
+            "Keep topic: {}",
+            if channel.keep_topic() { "on" } else { "off" }
+        ));
+        if let Some(entry_message) = channel.entry_message() {
+            cmd.notice(format_args!("Entry message: {}", entry_message));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use sable_network::{
+    policy::RegistrationPolicyService,
+    rpc::{
+        ChannelSetting, RemoteServerResponse, RemoteServicesServerRequestType,
+        RemoteServicesServerResponse,
+    },
+};
+
+use super::*;
+
+#[command_handler("SET", in("CS"))]
+async fn handle_set(
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    services_target: ServicesTarget<'_>,
+    channel: wrapper::ChannelRegistration<'_>,
+    setting_name: &str,
+    args: ArgList<'_>,
+) -> CommandResult {
+    cmd.server()
+        .node()
+        .policy()
+        .can_change_channel_settings(&source.account, &channel)?;
+
+    let setting = match setting_name.to_ascii_uppercase().as_ref() {
+        "MLOCK" => match parse_mode_lock(args) {
+            Ok(lock) => ChannelSetting::ModeLock(lock),
+            Err(message) => {
+                cmd.notice(message);
+                return Ok(());
+            }
+        },
+        "KEEPTOPIC" => match args
+            .iter()
+            .next()
+            .map(|s| s.to_ascii_uppercase())
+            .as_deref()
+        {
+            Some("ON") => ChannelSetting::KeepTopic(true),
+            Some("OFF") => ChannelSetting::KeepTopic(false),
+            _ => {
+                cmd.notice("Syntax: CS SET <#channel> KEEPTOPIC ON|OFF");
+                return Ok(());
+            }
+        },
+        "ENTRYMSG" => {
+            let message = (!args.is_empty()).then(|| args.iter().collect::<Vec<_>>().join(" "));
+            ChannelSetting::EntryMessage(message)
+        }
+        _ => {
+            cmd.notice("Syntax: CS SET <#channel> [MLOCK [modes] [key] | KEEPTOPIC ON|OFF | ENTRYMSG [message]]");
+            return Ok(());
+        }
+    };
+
+    let request = RemoteServicesServerRequestType::SetChannelSetting {
+        source: source.account.id(),
+        channel: channel.id(),
+        setting,
+    }
+    .into();
+    let response = services_target.send_remote_request(request).await;
+
+    tracing::debug!(?response, "Got channel setting response");
+    match response {
+        Ok(RemoteServerResponse::Success) => {
+            cmd.notice(format_args!(
+                "{} for {} has been updated",
+                setting_name.to_ascii_uppercase(),
+                channel.name()
+            ));
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied)) => {
+            cmd.notice("Access denied");
+        }
+        Ok(response) => {
+            tracing::error!(?response, "Unexpected response changing channel setting");
+            cmd.notice("Error updating channel settings");
+        }
+        Err(error) => {
+            tracing::error!(?error, "Error changing channel setting");
+            cmd.notice("Error updating channel settings");
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a mode lock in the form `+nt-s [key]`. An empty argument list clears the lock.
+fn parse_mode_lock(mut args: ArgList<'_>) -> Result<state::ChannelModeLock, String> {
+    let mut lock = state::ChannelModeLock::default();
+
+    let Ok(modes) = args.next::<&str>() else {
+        return Ok(lock);
+    };
+
+    let mut adding = true;
+    for c in modes.chars() {
+        match c {
+            '+' => adding = true,
+            '-' => adding = false,
+            'k' if adding => {
+                let key = args
+                    .next::<&str>()
+                    .map_err(|_| "A key must be given to lock mode +k".to_string())?;
+                lock.key = Some(
+                    ChannelKey::from_str(key).map_err(|_| format!("Invalid channel key {key}"))?,
+                );
+            }
+            _ => {
+                let Some(flag) = ChannelModeFlag::from_mode_char(c) else {
+                    return Err(format!("{c} is not a lockable mode"));
+                };
+                if adding {
+                    lock.set |= flag;
+                    lock.unset &= !ChannelModeSet::from(flag);
+                } else {
+                    lock.unset |= flag;
+                    lock.set &= !ChannelModeSet::from(flag);
+                }
+            }
+        }
+    }
+
+    Ok(lock)
+}
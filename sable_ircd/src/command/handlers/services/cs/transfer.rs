@@ -0,0 +1,84 @@
+use sable_network::{
+    policy::RegistrationPolicyService,
+    rpc::{RemoteServerResponse, RemoteServicesServerRequestType, RemoteServicesServerResponse},
+};
+
+use super::*;
+
+#[command_handler("TRANSFER", in("CS"))]
+async fn handle_transfer(
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    services_target: ServicesTarget<'_>,
+    channel: wrapper::ChannelRegistration<'_>,
+    target: &str,
+) -> CommandResult {
+    let request = if target.eq_ignore_ascii_case("ACCEPT") {
+        RemoteServicesServerRequestType::AcceptChannelTransfer {
+            source: source.account.id(),
+            channel: channel.id(),
+        }
+    } else {
+        let Ok(target_account) = Nickname::from_str(target)
+            .map_err(|_| ())
+            .and_then(|nick| cmd.network().account_by_name(&nick).map_err(|_| ()))
+        else {
+            cmd.notice(format_args!("{} is not a registered account", target));
+            return Ok(());
+        };
+
+        cmd.server()
+            .node()
+            .policy()
+            .can_transfer_channel(&source.account, &channel)?;
+
+        RemoteServicesServerRequestType::TransferChannel {
+            source: source.account.id(),
+            channel: channel.id(),
+            target: target_account.id(),
+        }
+    };
+    let accepting = matches!(
+        request,
+        RemoteServicesServerRequestType::AcceptChannelTransfer { .. }
+    );
+
+    let response = services_target.send_remote_request(request.into()).await;
+
+    tracing::debug!(?response, "Got channel transfer response");
+    match response {
+        Ok(RemoteServerResponse::Success) if accepting => {
+            cmd.notice(format_args!(
+                "You are now the founder of {}",
+                channel.name()
+            ));
+        }
+        Ok(RemoteServerResponse::Success) => {
+            cmd.notice(format_args!(
+                "Ownership of {} has been offered to {}. They must use CS TRANSFER {} ACCEPT to complete the transfer.",
+                channel.name(),
+                target,
+                channel.name()
+            ));
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::NoPendingTransfer)) => {
+            cmd.notice(format_args!(
+                "Ownership of {} has not been offered to you",
+                channel.name()
+            ));
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied)) => {
+            cmd.notice("Access denied");
+        }
+        Ok(response) => {
+            tracing::error!(?response, "Unexpected response transferring channel");
+            cmd.notice("Error transferring channel");
+        }
+        Err(error) => {
+            tracing::error!(?error, "Error transferring channel");
+            cmd.notice("Error transferring channel");
+        }
+    }
+
+    Ok(())
+}
@@ -147,6 +147,7 @@ impl Command for ServicesCommand<'_> {
                             RegistrationPermissionError::NotLoggedIn => { self.notice("You are not logged in") }
                             RegistrationPermissionError::NoAccess => { self.notice("Access denied") }
                             RegistrationPermissionError::CantEditHigherRole => { self.notice("Access denied - you can't edit a role with permissions you don't have yourself") }
+                            RegistrationPermissionError::InvalidMask => { self.notice("Invalid mask - it can't be empty or match everyone") }
                         }
                     }
                     PermissionError::InternalError(_) => todo!(),
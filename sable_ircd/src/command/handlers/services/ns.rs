@@ -18,4 +18,6 @@ async fn handle_cs<'a>(
 }
 
 mod cert;
+mod info;
 mod login;
+mod set;
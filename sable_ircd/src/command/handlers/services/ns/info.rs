@@ -0,0 +1,68 @@
+use sable_network::policy::RegistrationPolicyService;
+
+use super::*;
+use crate::utils::format_timestamp;
+
+#[command_handler("INFO", in("NS"))]
+async fn handle_info(
+    net: &Network,
+    source: UserSource<'_>,
+    cmd: &dyn Command,
+    target: Option<wrapper::Account<'_>>,
+) -> CommandResult {
+    let account = match target {
+        Some(account) => account,
+        None => match source.user.account()? {
+            Some(account) => account,
+            None => net.account_by_name(&source.nick())?,
+        },
+    };
+
+    cmd.notice(format_args!("Information for account {}", account.name()));
+    cmd.notice(" ");
+
+    if account.registered() != 0 {
+        cmd.notice(format_args!(
+            "Registered: {}",
+            format_timestamp(account.registered())
+        ));
+    }
+
+    if !cmd
+        .server()
+        .node()
+        .policy()
+        .can_view_account_details(&source.user, &account)
+    {
+        cmd.notice("This account's details are private");
+        return Ok(());
+    }
+
+    match account.last_login() {
+        Some(ts) => cmd.notice(format_args!("Last login: {}", format_timestamp(ts))),
+        None => cmd.notice("Last login: never"),
+    }
+
+    if !account.fingerprints().is_empty()
+        && cmd
+            .server()
+            .node()
+            .policy()
+            .can_view_account_fingerprints(&source.user, &account)
+    {
+        cmd.notice(format_args!(
+            "Certificate fingerprints: {}",
+            account.fingerprints().join(", ")
+        ));
+    }
+
+    for (name, value) in account.public_fields() {
+        cmd.notice(format_args!("{}: {}", name, value));
+    }
+
+    if account.is_private() {
+        cmd.notice("This account's details are hidden from other users");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use sable_network::prelude::wrapper::ObjectWrapper;
+use sable_network::rpc::{
+    RemoteServerResponse, RemoteServicesServerRequestType, RemoteServicesServerResponse,
+};
+
+use super::*;
+
+#[command_handler("SET", in("NS"))]
+async fn handle_set(
+    services: ServicesTarget<'_>,
+    source: LoggedInUserSource<'_>,
+    cmd: &dyn Command,
+    setting: &str,
+    mut args: ArgList<'_>,
+) -> CommandResult {
+    let request = match setting.to_ascii_uppercase().as_str() {
+        "PRIVATE" => match args.next::<&str>()?.to_ascii_uppercase().as_str() {
+            "ON" => RemoteServicesServerRequestType::SetAccountPrivate(source.account.id(), true),
+            "OFF" => RemoteServicesServerRequestType::SetAccountPrivate(source.account.id(), false),
+            _ => {
+                cmd.notice("Syntax: NS SET PRIVATE ON|OFF");
+                return Ok(());
+            }
+        },
+        "FIELD" => {
+            let name = args.next::<&str>()?.to_ascii_lowercase();
+            let value = (!args.is_empty()).then(|| args.iter().collect::<Vec<_>>().join(" "));
+
+            if let Err(error) = source
+                .account
+                .raw()
+                .check_public_field(&name, value.as_deref())
+            {
+                cmd.notice(error);
+                return Ok(());
+            }
+
+            RemoteServicesServerRequestType::SetAccountField {
+                account: source.account.id(),
+                name,
+                value,
+            }
+        }
+        _ => {
+            cmd.notice("Syntax: NS SET PRIVATE ON|OFF | NS SET FIELD <name> [value]");
+            return Ok(());
+        }
+    };
+
+    match services.send_remote_request(request.into()).await {
+        Ok(RemoteServerResponse::Success) => {
+            cmd.notice("Your account settings have been updated");
+        }
+        Ok(RemoteServerResponse::Services(RemoteServicesServerResponse::InvalidAccountField(
+            error,
+        ))) => {
+            cmd.notice(error);
+        }
+        Ok(response) => {
+            tracing::warn!(?response, "Unexpected response to account setting change");
+            cmd.notice("Error updating account settings");
+        }
+        Err(e) => {
+            tracing::warn!(?e, "Error response changing account setting");
+            cmd.notice("Error updating account settings");
+        }
+    }
+
+    Ok(())
+}
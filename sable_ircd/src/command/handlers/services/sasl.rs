@@ -5,6 +5,7 @@ use sable_network::{
 
 use super::*;
 use base64::prelude::*;
+use client_listener::TlsInfo;
 
 #[command_handler("AUTHENTICATE")]
 async fn handle_authenticate(
@@ -108,14 +109,128 @@ fn do_sasl_external(
     net: &Network,
     response: &dyn CommandResponse,
 ) -> CommandResult {
-    if let Some(fp) = conn.tls_info().and_then(|ti| ti.fingerprint.as_ref()) {
-        if let Some(account) = net.account_with_fingerprint(fp.as_str()) {
-            source.sasl_account.set(account.id()).ok();
-            response.numeric(make_numeric!(SaslSuccess));
-            return Ok(());
-        }
+    if let Some(account) = conn
+        .tls_info()
+        .and_then(|tls_info| external_account(tls_info, net))
+    {
+        source.sasl_account.set(account.id()).ok();
+        response.numeric(make_numeric!(SaslSuccess));
+        return Ok(());
     }
 
     response.numeric(make_numeric!(SaslFail));
     Ok(())
 }
+
+/// Find the account which a client's certificate authenticates it as, if any
+fn external_account<'a>(tls_info: &TlsInfo, net: &'a Network) -> Option<wrapper::Account<'a>> {
+    tls_info
+        .fingerprints()
+        .find_map(|fp| net.account_with_fingerprint(fp))
+        .or_else(|| {
+            // A certificate issued by the configured client CA names its account
+            let subject = tls_info.verified_subject.as_deref()?;
+            net.account_by_name(&Nickname::try_from(subject).ok()?).ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sable_network::network::{event::details, state};
+
+    struct NoUpdates;
+
+    impl NetworkUpdateReceiver for NoUpdates {
+        fn notify_update(&self, _update: NetworkStateChange, _event: &Event) {}
+    }
+
+    /// A network with one account, which has the given authorised fingerprint
+    fn network_with_account(name: &str, fingerprint: &str) -> (Network, AccountId) {
+        let ids = ObjectIdGenerator::new(ServerId::new(1));
+        let mut net = Network::new(sable_network::network::config::NetworkConfig::new());
+
+        let id: AccountId = ids.next();
+        let event = Event {
+            clock: EventClock::new(),
+            id: ids.next(),
+            target: id.into(),
+            timestamp: 0,
+            details: details::AccountUpdate {
+                data: Some(state::Account {
+                    id,
+                    name: Nickname::try_from(name).unwrap(),
+                    authorised_fingerprints: vec![fingerprint.to_string()],
+                    registered: 0,
+                    last_login: None,
+                    public_fields: Default::default(),
+                    private: false,
+                }),
+            }
+            .into(),
+        };
+        net.apply(&event, &NoUpdates).unwrap();
+
+        (net, id)
+    }
+
+    fn account_id(tls_info: &TlsInfo, net: &Network) -> Option<AccountId> {
+        external_account(tls_info, net).map(|account| account.id())
+    }
+
+    #[test]
+    fn login_by_fingerprint() {
+        let (net, id) = network_with_account("alice", "0123abcd");
+
+        for info in [
+            TlsInfo {
+                fingerprint: Some("0123abcd".to_string()),
+                ..Default::default()
+            },
+            TlsInfo {
+                fingerprint: Some("ffff".to_string()),
+                spki_sha256: Some("0123abcd".to_string()),
+                ..Default::default()
+            },
+            TlsInfo {
+                spki_sha512: Some("0123abcd".to_string()),
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(account_id(&info, &net), Some(id));
+        }
+
+        let unknown = TlsInfo {
+            fingerprint: Some("ffff".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(account_id(&unknown, &net), None);
+    }
+
+    #[test]
+    fn login_by_verified_subject() {
+        let (net, id) = network_with_account("alice", "0123abcd");
+
+        let verified = TlsInfo {
+            fingerprint: Some("ffff".to_string()),
+            verified_subject: Some("alice".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(account_id(&verified, &net), Some(id));
+
+        // Only a validated certificate's subject names an account
+        let unverified = TlsInfo {
+            fingerprint: Some("ffff".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(account_id(&unverified, &net), None);
+
+        for subject in ["bob", "not a nickname"] {
+            let info = TlsInfo {
+                verified_subject: Some(subject.to_string()),
+                ..Default::default()
+            };
+            assert_eq!(account_id(&info, &net), None);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::*;
+use crate::client::WebircInfo;
+use std::net::IpAddr;
+
+/// Whether a gateway-supplied hostname is safe to use as-is
+fn valid_hostname(hostname: &str) -> bool {
+    !hostname.is_empty()
+        && hostname
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+#[allow(clippy::too_many_arguments)]
+#[command_handler("WEBIRC")]
+fn handle_webirc(
+    server: &ClientServer,
+    source: PreClientSource,
+    cmd: &dyn Command,
+    password: &str,
+    _gateway: &str,
+    hostname: &str,
+    ip: &str,
+    _options: Option<&str>,
+) -> CommandResult {
+    let conn = cmd.connection();
+
+    // The address is replaced before registration, so it has to come first
+    if source.nick.get().is_some() || source.user.get().is_some() {
+        conn.error("WEBIRC must be sent before registration");
+        return Ok(());
+    }
+
+    // Match against the gateway's own address, not one previously supplied by WEBIRC
+    let Some(gateway) = server.find_webirc_gateway(conn.connection.remote_addr, password) else {
+        tracing::info!(addr = ?conn.connection.remote_addr, "Rejected WEBIRC from unknown gateway");
+        conn.error("Invalid WEBIRC credentials");
+        return Ok(());
+    };
+
+    let Ok(ip) = ip.parse::<IpAddr>() else {
+        conn.error("Invalid WEBIRC address");
+        return Ok(());
+    };
+
+    // Bans and limits on new connections were checked against the gateway's address,
+    // so need checking again for the client's
+    if let Some(error) = server.check_new_connection(ip, conn.connection.is_tls()) {
+        conn.error(&error);
+        return Ok(());
+    }
+
+    // Fall back to the address if the gateway didn't resolve a usable hostname
+    let hostname = match Hostname::convert(hostname) {
+        Ok(h) if valid_hostname(hostname) => h,
+        _ => match Hostname::convert(ip) {
+            Ok(h) => h,
+            Err(e) => {
+                conn.error(&e.to_string());
+                return Ok(());
+            }
+        },
+    };
+
+    let info = WebircInfo {
+        gateway: gateway.name.clone(),
+        ip,
+    };
+    if !conn.set_webirc(info, hostname) {
+        conn.error("WEBIRC has already been sent");
+        return Ok(());
+    }
+    server.start_dnsbl_check(conn);
+
+    tracing::debug!(?ip, %hostname, gateway = gateway.name, "Accepted WEBIRC");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostnames() {
+        assert!(valid_hostname("gateway.example.com"));
+        assert!(valid_hostname("host-1.example"));
+
+        assert!(!valid_hostname(""));
+        assert!(!valid_hostname("2001:db8::1"));
+        assert!(!valid_hostname(":leading.colon"));
+        assert!(!valid_hostname("user@example.com"));
+        assert!(!valid_hostname("space in.name"));
+    }
+}
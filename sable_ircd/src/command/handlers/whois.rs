@@ -59,6 +59,12 @@ fn whois_handler(
                 conn.hostname(),
                 conn.ip()
             ));
+            if let Some(gateway) = conn.gateway() {
+                response.numeric(make_numeric!(WhoisGateway, &target, gateway));
+            }
+            if let Some(fingerprint) = conn.tls_fingerprint() {
+                response.numeric(make_numeric!(WhoisCertFp, &target, fingerprint));
+            }
         }
     }
 
@@ -68,6 +68,7 @@ mod handlers {
     mod user;
     mod userhost;
     mod version;
+    mod webirc;
     mod who;
     mod whois;
     mod whowas;
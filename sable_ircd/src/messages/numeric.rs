@@ -28,6 +28,10 @@ define_messages! {
     318(EndOfWhois)             => { (user: &str)               => "{user} :End of /WHOIS" },
     319(WhoisChannels)          => { (user: &User.nick(), chanlist: &str)
                                                                 => "{user} :{chanlist}" },
+    276(WhoisCertFp)            => { (user: &User.nick(), fingerprint: &str)
+                                                                => "{user} :has client certificate fingerprint {fingerprint}" },
+    320(WhoisGateway)           => { (user: &User.nick(), gateway: &str)
+                                                                => "{user} :is connecting via WEBIRC gateway {gateway}" },
     378(WhoisHost)              => { (user: &User.nick(), username=user.user(), host: &Hostname, ip: &std::net::IpAddr)
                                                                 => "{user} :is connecting from {username}@{host} {ip}" },
 
@@ -48,6 +48,7 @@ impl SendHistoryItem<NetworkHistoryUpdate> for ClientServer {
             | NetworkStateChange::UserConnectionDisconnected(_)
             | NetworkStateChange::NewServer(_)
             | NetworkStateChange::ServerQuit(_)
+            | NetworkStateChange::SyncPeerChange(_)
             | NetworkStateChange::NewAuditLogEntry(_)
             | NetworkStateChange::UserLoginChange(_)
             | NetworkStateChange::HistoryServerUpdate(_)
@@ -85,6 +86,7 @@ impl SendHistoryItem<HistoryLogEntry> for ClientServer {
             | NetworkStateChange::UserConnectionDisconnected(_)
             | NetworkStateChange::NewServer(_)
             | NetworkStateChange::ServerQuit(_)
+            | NetworkStateChange::SyncPeerChange(_)
             | NetworkStateChange::NewAuditLogEntry(_)
             | NetworkStateChange::UserLoginChange(_)
             | NetworkStateChange::HistoryServerUpdate(_)
@@ -43,6 +43,8 @@ impl ClientServer {
                         hostname: *pre_client.hostname.get().unwrap(),
                         ip: conn.remote_addr(),
                         connection_time: sable_network::utils::now(),
+                        gateway: conn.webirc().map(|w| w.gateway.clone()),
+                        tls_fingerprint: conn.tls_info().and_then(|ti| ti.fingerprint.clone()),
                     };
                     self.node.submit_event(user_connection_id, user_connection);
 
@@ -73,11 +75,13 @@ impl ClientServer {
                         hostname: *pre_client.hostname.get().unwrap(),
                         ip: conn.remote_addr(),
                         connection_time: sable_network::utils::now(),
+                        gateway: conn.webirc().map(|w| w.gateway.clone()),
+                        tls_fingerprint: conn.tls_info().and_then(|ti| ti.fingerprint.clone()),
                     };
 
                     let new_user = event::details::NewUser {
                         nickname: *pre_client.nick.get().unwrap(),
-                        username: *pre_client.user.get().unwrap(),
+                        username: pre_client.registration_username().unwrap(),
                         visible_hostname: *pre_client.hostname.get().unwrap(),
                         realname: *pre_client.realname.get().unwrap(),
                         mode: state::UserMode::new(umodes),
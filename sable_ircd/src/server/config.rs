@@ -1,6 +1,9 @@
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 
+use ipnet::IpNet;
+use sable_network::network::config::ConnectionLimitConfig;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -9,6 +12,17 @@ pub struct ListenerConfig {
     pub address: String,
     #[serde(default)]
     pub tls: bool,
+    /// Accept WebSocket clients rather than line-based connections
+    #[serde(default)]
+    pub websocket: bool,
+    /// For WebSocket listeners, the origins from which browser clients may connect.
+    /// If empty, any origin is allowed.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// If set, connections must come from one of the listed proxies and begin with a
+    /// PROXY protocol header giving the client's real address
+    #[serde(default)]
+    pub proxy_protocol: Option<client_listener::ProxyProtocolSettings>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -24,6 +38,163 @@ pub struct RawClientServerConfig {
     pub info_paths: RawServerInfo,
     #[serde(default)]
     pub monitor: MonitorConfig,
+    #[serde(default)]
+    pub webirc: Vec<WebircGatewayConfig>,
+    /// Limits on connections from a single source, applied by the listener process
+    /// as soon as connections are accepted. WEBIRC gateways are exempt.
+    #[serde(default)]
+    pub connection_limits: Option<ConnectionLimitConfig>,
+    /// DNS blocklists against which connecting clients are checked
+    #[serde(default)]
+    pub dnsbl: Vec<DnsblZoneConfig>,
+    #[serde(default)]
+    pub ident: IdentConfig,
+    /// Strict Transport Security policy to advertise with the `sts` capability
+    #[serde(default)]
+    pub sts: Option<StsConfig>,
+}
+
+/// An IRCv3 Strict Transport Security policy, asking clients to connect with TLS
+/// and to keep doing so
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StsConfig {
+    /// Port to which plaintext clients should reconnect, which must be that of a TLS
+    /// listener. Defaults to that of the first TLS listener.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// How long, in seconds, clients should remember the policy
+    pub duration: u64,
+    /// Whether clients may include this network in preloaded policy lists
+    #[serde(default)]
+    pub preload: bool,
+}
+
+/// An [`StsConfig`] with its port resolved against the configured listeners
+#[derive(Clone, Debug)]
+pub struct StsPolicy {
+    pub port: u16,
+    pub duration: u64,
+    pub preload: bool,
+}
+
+impl StsConfig {
+    /// Check the policy against `listeners`, which must include a TLS listener for
+    /// clients to upgrade to
+    pub fn validate(
+        &self,
+        listeners: &[ListenerConfig],
+    ) -> Result<StsPolicy, ConfigProcessingError> {
+        let tls_ports: Vec<u16> = listeners
+            .iter()
+            .filter(|l| l.tls && !l.websocket)
+            .filter_map(|l| l.address.parse::<SocketAddr>().ok())
+            .map(|addr| addr.port())
+            .collect();
+
+        let port = match (self.port, tls_ports.first()) {
+            (_, None) => {
+                return Err(ConfigProcessingError {
+                    reason: "An STS policy requires a TLS listener".to_string(),
+                })
+            }
+            (Some(port), Some(_)) if !tls_ports.contains(&port) => {
+                return Err(ConfigProcessingError {
+                    reason: format!("STS port {port} is not that of a TLS listener"),
+                })
+            }
+            (Some(port), Some(_)) => port,
+            (None, Some(&first)) => first,
+        };
+
+        Ok(StsPolicy {
+            port,
+            duration: self.duration,
+            preload: self.preload,
+        })
+    }
+}
+
+impl StsPolicy {
+    /// Value of the `sts` capability for plaintext connections, pointing them at
+    /// the TLS port
+    pub fn plaintext_values(&self) -> Vec<String> {
+        vec![format!("port={}", self.port)]
+    }
+
+    /// Value of the `sts` capability for TLS connections, giving the policy's
+    /// lifetime
+    pub fn tls_values(&self) -> Vec<String> {
+        let mut values = vec![format!("duration={}", self.duration)];
+        if self.preload {
+            values.push("preload".to_string());
+        }
+        values
+    }
+}
+
+/// What to do with clients whose address is listed in a DNS blocklist
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsblAction {
+    /// Allow the client to register only once logged in with SASL
+    RequireSasl,
+    /// Refuse the connection
+    Reject,
+}
+
+/// The action to take for one of a blocklist's reply codes
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DnsblReplyConfig {
+    /// The last octet of the reply, e.g. 2 for `127.0.0.2`
+    pub code: u8,
+    pub action: DnsblAction,
+}
+
+/// A DNS blocklist zone, and what to do with clients it lists
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DnsblZoneConfig {
+    /// The zone to query, e.g. `dnsbl.example.org`
+    pub zone: String,
+    /// Actions for specific reply codes
+    #[serde(default)]
+    pub replies: Vec<DnsblReplyConfig>,
+    /// Action for reply codes not listed in `replies`. If unset, they are ignored.
+    #[serde(default)]
+    pub default_action: Option<DnsblAction>,
+    /// Reason shown to clients which are rejected or required to use SASL
+    pub reason: String,
+}
+
+impl DnsblZoneConfig {
+    /// The action to take for a client for which this zone returned `reply`
+    pub fn action_for(&self, reply: Ipv4Addr) -> Option<DnsblAction> {
+        let code = reply.octets()[3];
+        self.replies
+            .iter()
+            .find(|r| r.code == code)
+            .map(|r| r.action)
+            .or(self.default_action)
+    }
+}
+
+/// A web gateway which is trusted to supply its users' real addresses via `WEBIRC`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebircGatewayConfig {
+    /// Name of the gateway, shown to opers in the connection info of its users
+    pub name: String,
+    /// Crypt-style hash of the password the gateway must send
+    pub password_hash: String,
+    /// Addresses from which the gateway connects
+    pub sources: Vec<IpNet>,
+}
+
+impl WebircGatewayConfig {
+    /// Determine whether a gateway connecting from `addr` with `password` matches
+    /// this entry
+    pub fn matches(&self, addr: IpAddr, password: &str) -> bool {
+        self.sources.iter().any(|net| net.contains(&addr))
+            && pwhash::unix::verify(password, &self.password_hash)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -92,11 +263,44 @@ fn default_max_monitors_per_connection() -> u16 {
     MonitorConfig::default().max_per_connection
 }
 
+/// Ident (RFC 1413) lookups for connecting clients
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IdentConfig {
+    /// Whether to look up the usernames of connecting clients. Defaults to true.
+    #[serde(default = "default_ident_enabled")]
+    pub enabled: bool,
+    /// How long, in seconds, a client's ident server has to respond. Defaults to 5.
+    #[serde(default = "default_ident_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for IdentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_ident_enabled(),
+            timeout: default_ident_timeout(),
+        }
+    }
+}
+
+fn default_ident_enabled() -> bool {
+    true
+}
+
+fn default_ident_timeout() -> u64 {
+    5
+}
+
 #[derive(Debug)]
 pub struct ClientServerConfig {
     pub listeners: Vec<ListenerConfig>,
     pub info_strings: ServerInfoStrings,
     pub monitor: MonitorConfig,
+    pub webirc: Vec<WebircGatewayConfig>,
+    pub connection_limits: Option<ConnectionLimitConfig>,
+    pub dnsbl: Vec<DnsblZoneConfig>,
+    pub ident: IdentConfig,
+    pub sts: Option<StsPolicy>,
 }
 
 #[derive(Debug, Error)]
@@ -104,3 +308,106 @@ pub struct ClientServerConfig {
 pub struct ConfigProcessingError {
     reason: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webirc_gateway_matches_source_and_password() {
+        let gateway = WebircGatewayConfig {
+            name: "gateway".to_string(),
+            password_hash: pwhash::sha512_crypt::hash("secret").unwrap(),
+            sources: vec![
+                "192.0.2.0/24".parse().unwrap(),
+                "2001:db8::/32".parse().unwrap(),
+            ],
+        };
+
+        assert!(gateway.matches("192.0.2.10".parse().unwrap(), "secret"));
+        assert!(gateway.matches("2001:db8::1".parse().unwrap(), "secret"));
+        assert!(!gateway.matches("192.0.2.10".parse().unwrap(), "wrong"));
+        assert!(!gateway.matches("198.51.100.1".parse().unwrap(), "secret"));
+    }
+
+    #[test]
+    fn ident_config_defaults() {
+        let config: IdentConfig = serde_json::from_str("{}").unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.timeout, 5);
+
+        let config: IdentConfig = serde_json::from_str(r#"{ "enabled": false }"#).unwrap();
+        assert!(!config.enabled);
+        assert_eq!(config.timeout, 5);
+    }
+
+    fn listener(address: &str, tls: bool, websocket: bool) -> ListenerConfig {
+        ListenerConfig {
+            address: address.to_string(),
+            tls,
+            websocket,
+            allowed_origins: Vec::new(),
+            proxy_protocol: None,
+        }
+    }
+
+    fn sts(port: Option<u16>) -> StsConfig {
+        StsConfig {
+            port,
+            duration: 2592000,
+            preload: true,
+        }
+    }
+
+    #[test]
+    fn sts_port_defaults_to_first_tls_listener() {
+        let listeners = [
+            listener("127.0.0.1:6667", false, false),
+            listener("127.0.0.1:8097", true, true),
+            listener("127.0.0.1:6697", true, false),
+            listener("127.0.0.1:7000", true, false),
+        ];
+        let policy = sts(None).validate(&listeners).unwrap();
+        assert_eq!(policy.port, 6697);
+
+        let policy = sts(Some(7000)).validate(&listeners).unwrap();
+        assert_eq!(policy.port, 7000);
+    }
+
+    #[test]
+    fn sts_port_must_be_a_tls_listener() {
+        let listeners = [
+            listener("127.0.0.1:6667", false, false),
+            listener("127.0.0.1:8097", true, true),
+            listener("127.0.0.1:6697", true, false),
+        ];
+        assert!(sts(Some(6667)).validate(&listeners).is_err());
+        assert!(sts(Some(8097)).validate(&listeners).is_err());
+        assert!(sts(Some(6698)).validate(&listeners).is_err());
+    }
+
+    #[test]
+    fn sts_requires_a_tls_listener() {
+        let listeners = [
+            listener("127.0.0.1:6667", false, false),
+            listener("127.0.0.1:8097", true, true),
+        ];
+        assert!(sts(None).validate(&listeners).is_err());
+        assert!(sts(Some(8097)).validate(&listeners).is_err());
+    }
+
+    #[test]
+    fn sts_cap_values() {
+        let policy = sts(None)
+            .validate(&[listener("127.0.0.1:6697", true, false)])
+            .unwrap();
+        assert_eq!(policy.plaintext_values(), ["port=6697"]);
+        assert_eq!(policy.tls_values(), ["duration=2592000", "preload"]);
+
+        let policy = StsPolicy {
+            preload: false,
+            ..policy
+        };
+        assert_eq!(policy.tls_values(), ["duration=2592000"]);
+    }
+}
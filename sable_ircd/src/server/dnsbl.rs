@@ -0,0 +1,73 @@
+use super::*;
+use config::DnsblAction;
+
+impl ClientServer {
+    /// Begin checking a connection's address against the configured DNS blocklists,
+    /// holding its registration until the result arrives
+    pub(crate) fn start_dnsbl_check(&self, conn: &ClientConnection) {
+        if self.dnsbl_zones.is_empty() {
+            return;
+        }
+        let Some(pre_client) = conn.pre_client() else {
+            return;
+        };
+
+        pre_client.start_progress(ProgressFlag::DnsblCheck);
+
+        let zones = self.dnsbl_zones.iter().map(|z| z.zone.clone()).collect();
+        self.auth_client
+            .start_dnsbl_lookup(conn.id(), conn.remote_addr(), zones);
+    }
+
+    pub(super) fn handle_dnsbl_result(&self, result: DnsblResult) {
+        let Ok(conn) = self.connections.get(result.conn) else {
+            return;
+        };
+        let Some(pre_client) = conn.pre_client() else {
+            return;
+        };
+
+        // If WEBIRC has changed the client's address since this check began, another
+        // check is under way for the new one
+        if result.addr != conn.remote_addr() {
+            return;
+        }
+
+        // Apply the most severe action of any matching zone
+        let action = result
+            .listings
+            .iter()
+            .flat_map(|listing| {
+                self.dnsbl_zones
+                    .iter()
+                    .filter(move |zone| zone.zone == listing.zone)
+                    .flat_map(move |zone| {
+                        listing
+                            .replies
+                            .iter()
+                            .filter_map(move |reply| zone.action_for(*reply).map(|a| (a, zone)))
+                    })
+            })
+            .max_by_key(|(action, _)| *action);
+
+        match action {
+            Some((DnsblAction::Reject, zone)) => {
+                tracing::info!(addr = ?result.addr, zone = zone.zone, "Rejecting client listed in DNSBL");
+                conn.error(&format!(
+                    "*** Your address is listed in {}: {}",
+                    zone.zone, zone.reason
+                ));
+                return;
+            }
+            Some((DnsblAction::RequireSasl, zone)) => {
+                tracing::debug!(addr = ?result.addr, zone = zone.zone, "DNSBL listing requires SASL");
+                pre_client.sasl_required.set(zone.reason.clone()).ok();
+            }
+            None => (),
+        }
+
+        if pre_client.complete_progress(ProgressFlag::DnsblCheck) {
+            self.add_action(CommandAction::RegisterClient(conn.id()));
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use crate::movable::Movable;
 use crate::*;
+use anyhow::Context;
 use capability::*;
 use messages::*;
 
 use event::*;
 use rpc::*;
-use sable_network::{config::TlsData, network::ban::NetworkBanAction, prelude::*};
+use sable_network::{config::TlsData, prelude::*};
 
 use auth_client::*;
 use client_listener::*;
@@ -22,6 +23,7 @@ use tokio::{
 
 use std::{
     collections::VecDeque,
+    net::IpAddr,
     sync::{Arc, Weak},
     time::Duration,
 };
@@ -36,7 +38,10 @@ pub use async_handler_collection::*;
 mod upgrade;
 
 use self::{
-    config::{ClientServerConfig, RawClientServerConfig, ServerInfoStrings},
+    config::{
+        ClientServerConfig, DnsblZoneConfig, IdentConfig, RawClientServerConfig, ServerInfoStrings,
+        WebircGatewayConfig,
+    },
     message_sink_repository::MessageSinkRepository,
 };
 use crate::monitor::MonitorSet;
@@ -44,6 +49,7 @@ use crate::monitor::MonitorSet;
 pub mod config;
 
 mod command_action;
+mod dnsbl;
 mod message_sink_repository;
 mod server_type;
 mod update_handler;
@@ -93,6 +99,10 @@ pub struct ClientServer {
     pub info_strings: ServerInfoStrings,
 
     pub monitors: RwLock<MonitorSet>,
+
+    webirc_gateways: Vec<WebircGatewayConfig>,
+    dnsbl_zones: Vec<DnsblZoneConfig>,
+    ident: IdentConfig,
 }
 
 impl ClientServer {
@@ -127,6 +137,25 @@ impl ClientServer {
         self.node.policy()
     }
 
+    /// Find the configured WEBIRC gateway, if any, which may connect from `addr`
+    /// using `password`
+    pub(crate) fn find_webirc_gateway(
+        &self,
+        addr: IpAddr,
+        password: &str,
+    ) -> Option<&WebircGatewayConfig> {
+        self.webirc_gateways
+            .iter()
+            .find(|gateway| gateway.matches(addr, password))
+    }
+
+    /// Determine whether `addr` belongs to a configured WEBIRC gateway
+    pub(crate) fn is_webirc_gateway(&self, addr: IpAddr) -> bool {
+        self.webirc_gateways
+            .iter()
+            .any(|gateway| gateway.sources.iter().any(|net| net.contains(&addr)))
+    }
+
     /// Find a client connection
     pub fn find_connection(&self, id: ConnectionId) -> Option<Arc<ClientConnection>> {
         let ret = self.connections.get(id).ok();
@@ -236,6 +265,46 @@ impl ClientServer {
         ret
     }
 
+    /// Pass the certificates in `tls_data` to the listener process, for both new and
+    /// existing TLS listeners, and wait for it to accept them
+    async fn load_tls_certificates(
+        listeners: &ListenerCollection,
+        tls_data: &TlsData,
+    ) -> anyhow::Result<()> {
+        let sni_certificates = tls_data
+            .sni_certificates
+            .iter()
+            .map(|sni| SniCertificate {
+                hostnames: sni.hostnames.clone(),
+                cert_chain: sni.cert_chain.clone(),
+                key: sni.key.clone(),
+            })
+            .collect();
+
+        listeners
+            .load_tls_certificates(TlsSettings {
+                key: tls_data.key.clone(),
+                cert_chain: tls_data.cert_chain.clone(),
+                sni_certificates,
+                client_ca_certs: tls_data.client_ca_certs.clone(),
+            })
+            .await
+            .context("Could not load TLS certificates")
+    }
+
+    /// Advertise `policy` with the `sts` capability, or stop advertising it if there
+    /// is none
+    fn apply_sts_policy(caps: &CapabilityRepository, policy: Option<&config::StsPolicy>) {
+        match policy {
+            Some(policy) => caps.enable_with_tls_values(
+                ClientCapability::Sts,
+                &policy.plaintext_values(),
+                &policy.tls_values(),
+            ),
+            None => caps.disable(ClientCapability::Sts),
+        }
+    }
+
     /// Disconnects `PreClient`s that have been connected for too long (ie. connections
     /// which did not complete registration)
     #[tracing::instrument(skip_all)]
@@ -274,20 +343,10 @@ impl ClientServer {
             ConnectionEventDetail::NewConnection(conn) => {
                 tracing::trace!("Got new connection");
 
-                let conn_details = ban::NewConnectionBanSettings {
-                    ip: conn.remote_addr,
-                    tls: conn.is_tls(),
-                };
-                for ban in self
-                    .network()
-                    .network_bans()
-                    .find_new_connection(&conn_details)
-                {
-                    if let NetworkBanAction::RefuseConnection(_) = ban.action {
-                        conn.send(format!("ERROR :*** Banned: {}\r\n", ban.reason));
-                        conn.close();
-                        return;
-                    }
+                if let Some(error) = self.check_new_connection(conn.remote_addr, conn.is_tls()) {
+                    conn.send(format!("ERROR :{error}\r\n"));
+                    conn.close();
+                    return;
                 }
 
                 let conn = ClientConnection::new(conn);
@@ -299,6 +358,27 @@ impl ClientServer {
                 ));
                 self.auth_client
                     .start_dns_lookup(conn.id(), conn.remote_addr());
+
+                // Connections received through a proxy can't be checked
+                if let (true, Some(socket_info), Some(pc)) = (
+                    self.ident.enabled,
+                    conn.connection.socket_info,
+                    conn.pre_client(),
+                ) {
+                    pc.start_progress(ProgressFlag::IdentLookup);
+                    conn.send(message::Notice::new(
+                        self,
+                        &UnknownTarget,
+                        "*** Checking Ident",
+                    ));
+                    self.auth_client.start_ident_lookup(
+                        conn.id(),
+                        socket_info.remote,
+                        socket_info.local,
+                        Duration::from_secs(self.ident.timeout),
+                    );
+                }
+                self.start_dnsbl_check(&conn);
                 let conn = self.connections.write().add(msg.source, conn);
                 self.prereg_connections.lock().await.push_back(conn);
             }
@@ -495,7 +575,8 @@ impl ClientServer {
                                                                                 conn.remote_addr(),
                                                                                 msg.hostname
                                                                                 );
-                                if let Some(pc) = conn.pre_client() {
+                                // If a WEBIRC gateway supplied the client's hostname, the lookup is moot
+                                if let Some(pc) = conn.pre_client().filter(|_| conn.webirc().is_none()) {
                                     if let Some(hostname) = msg.hostname {
                                         conn.send(message::Notice::new(&self, &UnknownTarget,
                                                         &format!("*** Found your hostname: {hostname}")));
@@ -519,6 +600,36 @@ impl ClientServer {
                                 }
                             }
                         },
+                        Some(AuthEvent::DnsblResult(msg)) =>
+                        {
+                            self.handle_dnsbl_result(msg);
+                        },
+                        Some(AuthEvent::IdentResult(msg)) =>
+                        {
+                            if let Ok(conn) = self.connections.get(msg.conn) {
+                                tracing::trace!("Ident lookup finished for {:?}: {:?}", msg.conn, msg.username);
+                                if let Some(pc) = conn.pre_client() {
+                                    // After WEBIRC, the response describes the gateway's connection
+                                    match msg.username.filter(|_| conn.webirc().is_none()) {
+                                        Some(username) => {
+                                            conn.send(message::Notice::new(&self, &UnknownTarget,
+                                                            &format!("*** Got Ident response: {username}")));
+                                            pc.ident.set(username).ok();
+                                        }
+                                        None => {
+                                            conn.send(message::Notice::new(&self, &UnknownTarget,
+                                                            "*** No Ident response"));
+                                        }
+                                    }
+                                    if pc.complete_progress(ProgressFlag::IdentLookup) {
+                                        let res = self.action_submitter.send(CommandAction::RegisterClient(conn.id()));
+                                        if let Err(e) = res {
+                                            conn.error(&e.to_string());
+                                        }
+                                    }
+                                }
+                            }
+                        },
                         None =>
                         {
                             panic!("Lost auth client task");
@@ -32,6 +32,15 @@ impl sable_server::ServerType for ClientServer {
             listeners: config.listeners.clone(),
             info_strings: ServerInfoStrings::load(&config.info_paths)?,
             monitor: config.monitor.clone(),
+            webirc: config.webirc.clone(),
+            connection_limits: config.connection_limits.clone(),
+            dnsbl: config.dnsbl.clone(),
+            ident: config.ident.clone(),
+            sts: config
+                .sts
+                .as_ref()
+                .map(|sts| sts.validate(&config.listeners))
+                .transpose()?,
         })
     }
 
@@ -49,26 +58,49 @@ impl sable_server::ServerType for ClientServer {
         let client_listeners = ListenerCollection::new(client_send)
             .context("Could not initialize listener collection")?;
 
-        client_listeners
-            .load_tls_certificates(tls_data.key.clone(), tls_data.cert_chain.clone())
-            .context("Could not load TLS certificates")?;
+        Self::load_tls_certificates(&client_listeners, tls_data).await?;
+
+        if let Some(mut limits) = config.connection_limits.clone() {
+            // A gateway's users all connect from its address, so are limited by their
+            // own addresses once they send WEBIRC instead
+            limits.exempt.extend(
+                config
+                    .webirc
+                    .iter()
+                    .flat_map(|gateway| gateway.sources.iter().copied()),
+            );
+            client_listeners
+                .set_connection_limits(limits)
+                .context("Could not set connection limits")?;
+        }
 
         for listener in config.listeners.iter() {
-            let conn_type = if listener.tls {
-                ConnectionType::Tls
-            } else {
-                ConnectionType::Clear
+            let websocket_settings = || WebSocketSettings {
+                allowed_origins: listener.allowed_origins.clone(),
+            };
+            let conn_type = match (listener.tls, listener.websocket) {
+                (false, false) => ConnectionType::Clear,
+                (true, false) => ConnectionType::Tls,
+                (false, true) => ConnectionType::WebSocket(websocket_settings()),
+                (true, true) => ConnectionType::SecureWebSocket(websocket_settings()),
+            };
+            let options = ListenerOptions {
+                proxy_protocol: listener.proxy_protocol.clone(),
             };
             client_listeners
-                .add_listener(
+                .add_listener_with_options(
                     listener.address.parse().with_context(|| {
                         format!("Invalid listener address: {}", listener.address)
                     })?,
                     conn_type,
+                    options,
                 )
                 .context("Cannot add listener")?;
         }
 
+        let client_caps = CapabilityRepository::new();
+        Self::apply_sts_policy(&client_caps, config.sts.as_ref());
+
         Ok(Self {
             action_receiver: Mutex::new(action_receiver),
             connection_events: Mutex::new(client_recv),
@@ -86,11 +118,14 @@ impl sable_server::ServerType for ClientServer {
             prereg_connections: Mutex::new(VecDeque::new()),
             myinfo: Self::build_myinfo(),
             isupport: Self::build_basic_isupport(&config),
-            client_caps: CapabilityRepository::new(),
+            client_caps,
             node,
             listeners: Movable::new(client_listeners),
             info_strings: config.info_strings,
             monitors: MonitorSet::new(config.monitor.max_per_connection.into()).into(),
+            webirc_gateways: config.webirc,
+            dnsbl_zones: config.dnsbl,
+            ident: config.ident,
         })
     }
 
@@ -131,6 +166,9 @@ impl sable_server::ServerType for ClientServer {
         let connections = ConnectionCollection::restore_from(state.connections, &listeners);
 
         state.monitors.max_per_connection = config.monitor.max_per_connection.into();
+        // The policy may have changed along with the config
+        Self::apply_sts_policy(&state.client_caps, config.sts.as_ref());
+
         Ok(Self {
             node,
             action_receiver: Mutex::new(action_recv),
@@ -157,9 +195,16 @@ impl sable_server::ServerType for ClientServer {
             listeners: Movable::new(listeners),
             info_strings: config.info_strings.clone(),
             monitors: state.monitors.into(),
+            webirc_gateways: config.webirc.clone(),
+            dnsbl_zones: config.dnsbl.clone(),
+            ident: config.ident.clone(),
         })
     }
 
+    async fn reload_tls(&self, tls_data: &TlsData) -> anyhow::Result<()> {
+        Self::load_tls_certificates(&self.listeners, tls_data).await
+    }
+
     async fn run(self: Arc<Self>, shutdown: broadcast::Receiver<ShutdownAction>) {
         self.do_run(shutdown).await;
     }
@@ -177,4 +222,17 @@ impl sable_server::ServerType for ClientServer {
             _ => RemoteServerResponse::NotSupported,
         }
     }
+
+    fn statistics(&self) -> std::collections::BTreeMap<String, u64> {
+        let connections = self.connections.read();
+        let clients = connections.iter().count() as u64;
+        let registered = connections.iter().filter(|c| c.user_id().is_some()).count() as u64;
+
+        [
+            ("connected_clients".to_string(), clients),
+            ("registered_clients".to_string(), registered),
+        ]
+        .into_iter()
+        .collect()
+    }
 }
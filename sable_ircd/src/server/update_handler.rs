@@ -31,6 +31,13 @@ impl ClientServer {
                 let update = detail.clone();
                 self.handle_services_update(&update)?;
             }
+            NetworkStateChange::ChannelJoin(detail) => {
+                // Only the server which originated the join enforces auto-kicks, so
+                // that each matching join results in exactly one kick
+                if update.event.server() == self.node.id() {
+                    self.enforce_auto_kick(detail)?;
+                }
+            }
             NetworkStateChange::EventComplete(_) => {
                 // All
                 self.stored_response_sinks.write().remove(&update.event);
@@ -41,6 +48,11 @@ impl ClientServer {
             self.notify_user_update(user_id, &update)?;
         }
 
+        // Sent after the join itself so that it appears in the right order
+        if let NetworkStateChange::ChannelJoin(detail) = &update.change {
+            self.send_entry_message(detail)?;
+        }
+
         Ok(())
     }
 
@@ -180,6 +192,61 @@ impl ClientServer {
         Ok(())
     }
 
+    fn enforce_auto_kick(&self, detail: &update::ChannelJoin) -> HandleResult {
+        let net = self.network();
+
+        let Ok(membership) = net.membership(detail.membership) else {
+            // Already gone
+            return Ok(());
+        };
+        let user = membership.user()?;
+        let channel = membership.channel()?;
+
+        let Some(auto_kick_id) = self.policy().auto_kick_for(&user, &channel) else {
+            return Ok(());
+        };
+        let auto_kick = net.channel_auto_kick(auto_kick_id)?;
+
+        self.node.submit_event(
+            membership.id(),
+            event::details::ServerChannelKick {
+                source: self.node.id(),
+                message: auto_kick.reason().unwrap_or("Auto-kicked").to_string(),
+            },
+        );
+
+        Ok(())
+    }
+
+    fn send_entry_message(&self, detail: &update::ChannelJoin) -> HandleResult {
+        let net = self.network();
+
+        let Ok(membership) = net.membership(detail.membership) else {
+            return Ok(());
+        };
+        let user = membership.user()?;
+        let channel = membership.channel()?;
+
+        let Some(entry_message) = channel
+            .is_registered()
+            .and_then(|reg| reg.entry_message().map(ToOwned::to_owned))
+        else {
+            return Ok(());
+        };
+
+        let message = message::Notice::new(
+            self,
+            &user,
+            &format!("[{}] {}", channel.name(), entry_message),
+        );
+
+        for conn in self.connections.read().get_user(user.id()) {
+            conn.send(message.clone());
+        }
+
+        Ok(())
+    }
+
     fn handle_services_update(&self, _detail: &update::ServicesUpdate) -> HandleResult {
         let net = self.network();
         let new_state = net.current_services();
@@ -14,6 +14,43 @@ pub enum AccessError {
 }
 
 impl ClientServer {
+    /// Check whether a new connection from the given address should be refused, by
+    /// either a network ban or the network's connection limits, returning the error
+    /// message to send if so.
+    ///
+    /// Connections from WEBIRC gateways aren't limited until the gateway supplies
+    /// the client's address.
+    pub(crate) fn check_new_connection(&self, ip: IpAddr, tls: bool) -> Option<String> {
+        let network = self.network();
+        let conn_details = NewConnectionBanSettings { ip, tls };
+
+        if let Some(ban) = network
+            .network_bans()
+            .find_new_connection(&conn_details)
+            .find(|ban| matches!(ban.action, NetworkBanAction::RefuseConnection(_)))
+        {
+            return Some(format!("*** Banned: {}", ban.reason));
+        }
+
+        if self.is_webirc_gateway(ip) {
+            return None;
+        }
+
+        // Connections still registering aren't yet known to the rest of the network
+        let unregistered: Vec<_> = self
+            .connections
+            .read()
+            .iter()
+            .filter(|conn| conn.pre_client().is_some())
+            .map(|conn| conn.remote_addr())
+            .collect();
+
+        network
+            .check_connection_limits(ip, unregistered.into_iter())
+            .err()
+            .map(|e| e.to_string())
+    }
+
     #[tracing::instrument(skip(self, net))]
     pub(super) fn check_user_access(
         &self,
@@ -25,7 +62,7 @@ impl ClientServer {
                 tracing::error!("PreClient nickname not set");
                 return Err(AccessError::InternalError);
             };
-            let Some(user) = pre_client.user.get().cloned() else {
+            let Some(user) = pre_client.registration_username() else {
                 tracing::error!("PreClient username not set");
                 return Err(AccessError::InternalError);
             };
@@ -72,6 +109,12 @@ impl ClientServer {
                     }
                 }
             }
+
+            if let Some(reason) = pre_client.sasl_required.get() {
+                if pre_client.sasl_account.get().is_none() {
+                    return Err(AccessError::SaslRequired(reason.clone()));
+                }
+            }
         }
         Ok(())
     }
@@ -6,8 +6,30 @@ pub fn format_timestamp(ts: i64) -> String {
         .to_rfc3339_opts(SecondsFormat::Millis, true)
 }
 
+/// Format a timestamp for which zero means it wasn't recorded
+pub fn format_recorded_timestamp(ts: i64) -> String {
+    match ts {
+        0 => "unknown".to_string(),
+        ts => format_timestamp(ts),
+    }
+}
+
 pub fn parse_timestamp(str: &str) -> Option<i64> {
     NaiveDateTime::parse_from_str(str, "%+")
         .map(|dt| dt.and_utc().timestamp())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_timestamp() {
+        assert_eq!(format_recorded_timestamp(0), "unknown");
+        assert_eq!(
+            format_recorded_timestamp(1_700_000_000),
+            "2023-11-14T22:13:20.000Z"
+        );
+    }
+}
@@ -202,6 +202,16 @@ pub fn define_validated(input: TokenStream) -> TokenStream {
 
         if def.casefolded.is_some() {
             out.extend(quote!(
+                impl #name
+                {
+                    /// The casefolded form of this value. Two values compare equal
+                    /// exactly when their casefolded forms do.
+                    pub fn casefold(&self) -> String
+                    {
+                        self.0.chars().map(|c| c.to_ascii_lowercase()).collect()
+                    }
+                }
+
                 impl PartialEq for #name
                 {
                     fn eq(&self, other: &Self) -> bool
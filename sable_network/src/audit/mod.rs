@@ -88,6 +88,36 @@ impl<'a> AuditLogger<'a> {
     }
 }
 
+impl<'a> AuditLoggerEntry<'a> {
+    /// Begin an entry for an action requested through the management service by the
+    /// named client
+    pub fn management(
+        node: &'a NetworkNode,
+        category: AuditLogCategory,
+        client: &str,
+        addr: Option<IpAddr>,
+        action: String,
+    ) -> Self {
+        let mut source_str = format!("<management:{client}>");
+        if let Some(ip) = addr {
+            write!(source_str, "{{{ip}}}").expect("failed to write to string?");
+        }
+
+        Self {
+            node,
+            category,
+            source_id: None,
+            source_addr: addr,
+            source_str,
+            action,
+            target_id: None,
+            target_str: None,
+            target_duration: None,
+            reason: None,
+        }
+    }
+}
+
 impl AuditLoggerEntry<'_> {
     pub fn source(mut self, id: Option<UserId>, ip: Option<IpAddr>) -> Self {
         self.source_id = id;
@@ -0,0 +1,215 @@
+use sable_network::network::config::NetworkConfig;
+use sable_network::prelude::*;
+use sable_network::sync::replay::{self, OrderingError};
+
+use serde_json::Value;
+use std::{fs::File, io::BufReader, path::Path, path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+struct Input {
+    /// Event list (as returned by `/dump-events`) or snapshot file to read
+    events: PathBuf,
+
+    /// Network state (as returned by `/dump-network`) or snapshot which the events
+    /// follow on from. Without this, events are replayed into an empty network.
+    #[structopt(short, long)]
+    base: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+/// Inspect, replay and compare sable event logs
+enum Opts {
+    /// Check that every event appears after the events it depends on
+    Check {
+        #[structopt(flatten)]
+        input: Input,
+    },
+    /// Replay events and print the resulting network state
+    Replay {
+        #[structopt(flatten)]
+        input: Input,
+
+        /// Write the network state here instead of to stdout
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replay the events which another node had processed, and show where the
+    /// result differs from that node's network state
+    Diff {
+        #[structopt(flatten)]
+        input: Input,
+
+        /// The other node's network state or snapshot
+        against: PathBuf,
+    },
+}
+
+fn read_json(path: &Path) -> Result<Value, anyhow::Error> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Load a list of events, either bare or from a snapshot
+fn load_events(path: &Path) -> Result<Vec<Event>, anyhow::Error> {
+    let json = match read_json(path)? {
+        Value::Object(mut snapshot) => snapshot
+            .remove("events")
+            .ok_or_else(|| anyhow::anyhow!("{} contains no events", path.display()))?,
+        json => json,
+    };
+
+    Ok(serde_json::from_value(json)?)
+}
+
+/// Load a network state, either bare or from a snapshot
+fn load_network(path: &Path) -> Result<Network, anyhow::Error> {
+    let json = match read_json(path)? {
+        Value::Object(mut snapshot) if snapshot.contains_key("network") => {
+            snapshot.remove("network").unwrap()
+        }
+        json => json,
+    };
+
+    Ok(serde_json::from_value(json)?)
+}
+
+impl Input {
+    fn load(&self) -> Result<(Network, Vec<Event>), anyhow::Error> {
+        let base = match &self.base {
+            Some(path) => load_network(path)?,
+            None => Network::new(NetworkConfig::new()),
+        };
+
+        Ok((base, load_events(&self.events)?))
+    }
+}
+
+fn run_check(input: Input) -> Result<bool, anyhow::Error> {
+    let (base, events) = input.load()?;
+    let errors = replay::check_ordering(&events, base.clock());
+
+    for error in &errors {
+        match error {
+            OrderingError::Duplicate(id) => println!("{:?} appears more than once", id),
+            OrderingError::OutOfOrder { event, dependency } => {
+                println!("{:?} appears before its dependency {:?}", event, dependency)
+            }
+            OrderingError::MissingDependency { event, dependency } => {
+                println!("{:?} depends on missing event {:?}", event, dependency)
+            }
+        }
+    }
+
+    println!("Checked {} events: {} problems", events.len(), errors.len());
+
+    Ok(errors.is_empty())
+}
+
+fn print_replay_summary(result: &replay::Replay) {
+    eprintln!("Applied {} events", result.applied.len());
+
+    for event in &result.unapplied {
+        eprintln!("Not applied (missing dependencies): {:?}", event.id);
+    }
+    for (event, error) in &result.failed {
+        eprintln!("Failed to apply {:?}: {}", event.id, error);
+    }
+}
+
+fn run_replay(input: Input, output: Option<PathBuf>) -> Result<bool, anyhow::Error> {
+    let (base, events) = input.load()?;
+    let result = replay::replay(base, events);
+
+    print_replay_summary(&result);
+
+    match output {
+        Some(path) => serde_json::to_writer(File::create(path)?, &result.network)?,
+        None => println!("{}", serde_json::to_string(&result.network)?),
+    }
+
+    Ok(result.unapplied.is_empty() && result.failed.is_empty())
+}
+
+fn run_diff(input: Input, against: PathBuf) -> Result<bool, anyhow::Error> {
+    let (base, events) = input.load()?;
+    let other = load_network(&against)?;
+
+    // Only replay what the other node had seen, so that any remaining
+    // differences come from how the events were applied
+    let events = events
+        .into_iter()
+        .filter(|e| other.clock().contains(e.id))
+        .collect();
+    let result = replay::replay(base, events);
+
+    print_replay_summary(&result);
+
+    for (server, id) in &other.clock().0 {
+        if !result.network.clock().contains(*id) {
+            println!(
+                "Events from {:?} up to {:?} are missing from the log",
+                server, id
+            );
+        }
+    }
+
+    let mut ours = serde_json::to_value(&result.network)?;
+    let mut theirs = serde_json::to_value(&other)?;
+    // Clocks were compared above
+    for json in [&mut ours, &mut theirs] {
+        if let Some(json) = json.as_object_mut() {
+            json.remove("clock");
+        }
+    }
+
+    let differences = replay::diff_json(&ours, &theirs);
+
+    for difference in &differences {
+        println!(
+            "{}: replayed {} != {}",
+            difference.path,
+            describe(&difference.left),
+            describe(&difference.right)
+        );
+
+        if let Some(object) = &difference.object {
+            for event in result
+                .applied
+                .iter()
+                .filter(|e| replay::event_targets(e, object))
+            {
+                println!(
+                    "    touched by {:?} at {}: {:?}",
+                    event.id, event.timestamp, event.details
+                );
+            }
+        }
+    }
+
+    println!("{} differences", differences.len());
+
+    Ok(differences.is_empty())
+}
+
+fn describe(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(absent)".to_string(),
+    }
+}
+
+pub fn main() -> Result<(), anyhow::Error> {
+    let ok = match Opts::from_args() {
+        Opts::Check { input } => run_check(input)?,
+        Opts::Replay { input, output } => run_replay(input, output)?,
+        Opts::Diff { input, against } => run_diff(input, against)?,
+    };
+
+    if !ok {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
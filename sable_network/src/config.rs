@@ -1,49 +1,133 @@
 use anyhow::Context;
 use serde::Deserialize;
-use std::{fs::File, io::BufReader, path::PathBuf};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
 use crate::prelude::ConfigError;
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct TlsConfig {
     pub key_file: PathBuf,
     pub cert_file: PathBuf,
+    /// Additional certificates for client listeners, presented to clients which ask
+    /// for one of the given hostnames via SNI
+    #[serde(default)]
+    pub sni_certificates: Vec<SniCertificateConfig>,
+    /// If set, check every this many seconds whether any of the certificate or key
+    /// files have changed, and reload them if so. Reloads apply to client listeners;
+    /// the management service keeps the certificate it started with until restarted.
+    #[serde(default)]
+    pub reload_interval: Option<u64>,
+    /// If set, client certificates issued by the CA certificates in this file are
+    /// treated as validated, allowing their subjects to log in via SASL EXTERNAL
+    #[serde(default)]
+    pub client_ca_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SniCertificateConfig {
+    /// Hostnames for which to present this certificate. A name beginning with `*.`
+    /// matches any single label in its place.
+    pub hostnames: Vec<String>,
+    pub key_file: PathBuf,
+    pub cert_file: PathBuf,
 }
 
 #[derive(Clone, Debug)]
 pub struct TlsData {
     pub key: Vec<u8>,
     pub cert_chain: Vec<Vec<u8>>,
+    pub sni_certificates: Vec<SniCertificateData>,
+    pub client_ca_certs: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SniCertificateData {
+    pub hostnames: Vec<String>,
+    pub key: Vec<u8>,
+    pub cert_chain: Vec<Vec<u8>>,
 }
 
 impl TlsConfig {
     pub fn load_from_disk(&self) -> Result<TlsData, anyhow::Error> {
-        let cert_file = File::open(&self.cert_file)
-            .with_context(|| format!("Could not open certificate {}", self.cert_file.display()))?;
-        let mut cert_reader = BufReader::new(cert_file);
-        let cert_chain = rustls_pemfile::certs(&mut cert_reader)
-            .with_context(|| format!("Could not parse certificate {}", self.cert_file.display()))?;
-
-        let key_file = File::open(&self.key_file)
-            .with_context(|| format!("Could not open private key {}", self.key_file.display()))?;
-        let mut key_reader = BufReader::new(key_file);
-
-        let server_key = rustls_pemfile::read_one(&mut key_reader)
-            .with_context(|| format!("Could not parse private key {}", self.key_file.display()))?;
+        let (key, cert_chain) = load_key_pair(&self.key_file, &self.cert_file)?;
 
-        use rustls_pemfile::Item;
+        let sni_certificates = self
+            .sni_certificates
+            .iter()
+            .map(|sni| {
+                let (key, cert_chain) = load_key_pair(&sni.key_file, &sni.cert_file)?;
+                Ok(SniCertificateData {
+                    hostnames: sni.hostnames.clone(),
+                    key,
+                    cert_chain,
+                })
+            })
+            .collect::<Result<_, anyhow::Error>>()?;
 
-        let server_key = match server_key {
-            Some(Item::RSAKey(key)) | Some(Item::PKCS8Key(key)) => Ok(key),
-            Some(Item::X509Certificate(_)) | None => Err(ConfigError::FormatError(
-                "No private key in file".to_string(),
-                self.key_file.clone(),
-            )),
-        }?;
+        let client_ca_certs = match &self.client_ca_file {
+            Some(path) => load_certs(path)?,
+            None => Vec::new(),
+        };
 
         Ok(TlsData {
-            key: server_key,
+            key,
             cert_chain,
+            sni_certificates,
+            client_ca_certs,
         })
     }
+
+    /// The most recent modification time of any of the configured files, used to
+    /// detect when they have been replaced
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        std::iter::once((&self.key_file, &self.cert_file))
+            .chain(
+                self.sni_certificates
+                    .iter()
+                    .map(|sni| (&sni.key_file, &sni.cert_file)),
+            )
+            .flat_map(|(key_file, cert_file)| [key_file, cert_file])
+            .chain(&self.client_ca_file)
+            .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .max()
+    }
+}
+
+fn load_certs(cert_path: &Path) -> Result<Vec<Vec<u8>>, anyhow::Error> {
+    let cert_file = File::open(cert_path)
+        .with_context(|| format!("Could not open certificate {}", cert_path.display()))?;
+    let mut cert_reader = BufReader::new(cert_file);
+    rustls_pemfile::certs(&mut cert_reader)
+        .with_context(|| format!("Could not parse certificate {}", cert_path.display()))
+}
+
+fn load_key_pair(
+    key_path: &Path,
+    cert_path: &Path,
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), anyhow::Error> {
+    let cert_chain = load_certs(cert_path)?;
+
+    let key_file = File::open(key_path)
+        .with_context(|| format!("Could not open private key {}", key_path.display()))?;
+    let mut key_reader = BufReader::new(key_file);
+
+    let server_key = rustls_pemfile::read_one(&mut key_reader)
+        .with_context(|| format!("Could not parse private key {}", key_path.display()))?;
+
+    use rustls_pemfile::Item;
+
+    let server_key = match server_key {
+        Some(Item::RSAKey(key)) | Some(Item::PKCS8Key(key)) => Ok(key),
+        Some(Item::X509Certificate(_)) | None => Err(ConfigError::FormatError(
+            "No private key in file".to_string(),
+            key_path.to_owned(),
+        )),
+    }?;
+
+    Ok((server_key, cert_chain))
 }
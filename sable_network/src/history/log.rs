@@ -162,6 +162,7 @@ impl NetworkHistoryLog {
             | UserConnectionDisconnected(_)
             | NewServer(_)
             | ServerQuit(_)
+            | SyncPeerChange(_)
             | NewAuditLogEntry(_)
             | UserLoginChange(_)
             | ServicesUpdate(_)
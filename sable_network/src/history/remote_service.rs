@@ -1,11 +1,16 @@
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::time::Duration;
 
 use tracing::instrument;
 
 use crate::prelude::*;
 use crate::rpc::*;
 
+/// History queries may have to search a large database, so allow them longer than
+/// other remote requests
+const HISTORY_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
 /// Implementation of [`HistoryService`] that forwards requests to a `HistoryServer`
 /// through the RPC.
 pub struct RemoteHistoryService<'a, NetworkPolicy: policy::PolicyService> {
@@ -36,7 +41,7 @@ impl<NetworkPolicy: policy::PolicyService> HistoryService
         let res = self
             .node
             .sync_log()
-            .send_remote_request(
+            .send_remote_request_with_timeout(
                 self.remote_server_name,
                 RemoteHistoryServerRequestType::ListTargets {
                     user,
@@ -45,6 +50,7 @@ impl<NetworkPolicy: policy::PolicyService> HistoryService
                     limit,
                 }
                 .into(),
+                HISTORY_REQUEST_TIMEOUT,
             )
             .await;
         tracing::trace!("list_targets RPC response: {res:?}");
@@ -78,7 +84,7 @@ impl<NetworkPolicy: policy::PolicyService> HistoryService
         let res = self
             .node
             .sync_log()
-            .send_remote_request(
+            .send_remote_request_with_timeout(
                 self.remote_server_name,
                 rpc::RemoteHistoryServerRequestType::GetEntries {
                     user,
@@ -86,6 +92,7 @@ impl<NetworkPolicy: policy::PolicyService> HistoryService
                     request,
                 }
                 .into(),
+                HISTORY_REQUEST_TIMEOUT,
             )
             .await;
         match res {
@@ -45,6 +45,7 @@ pub type EpochId = u64;
 
 object_ids!(ObjectId (ObjectIdGenerator) {
     Server: (u16,);
+    SyncPeer: (ServerName,);
     Event: snowflake;
     User: snowflake;
     HistoricUser: (UserId, u32);
@@ -72,6 +73,7 @@ object_ids!(ObjectId (ObjectIdGenerator) {
 
     ChannelAccess: (AccountId, ChannelRegistrationId);
     ChannelRole: snowflake;
+    ChannelAutoKick: snowflake;
 
     SaslSession: snowflake;
 });
@@ -86,6 +88,12 @@ impl HistoricUserId {
     }
 }
 
+impl SyncPeerId {
+    pub fn name(&self) -> &ServerName {
+        &self.0
+    }
+}
+
 impl NicknameId {
     pub fn nick(&self) -> &Nickname {
         &self.0
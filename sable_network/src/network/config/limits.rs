@@ -0,0 +1,120 @@
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use thiserror::Error;
+
+/// Limits on concurrent connections from a single source.
+///
+/// The network config's limits apply to all the network's users, and are enforced
+/// when a connection is received alongside `NewConnection` network bans. A server can
+/// also give its listeners limits of its own, which are applied to connections as soon
+/// as they are accepted; connection throttling only applies there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionLimitConfig {
+    /// Maximum connections from one address
+    #[serde(default)]
+    pub max_per_ip: Option<u32>,
+    /// Maximum connections from one subnet, of the size given by `ipv4_subnet_prefix`
+    /// or `ipv6_subnet_prefix`
+    #[serde(default)]
+    pub max_per_subnet: Option<u32>,
+    #[serde(default = "default_ipv4_subnet_prefix")]
+    pub ipv4_subnet_prefix: u8,
+    #[serde(default = "default_ipv6_subnet_prefix")]
+    pub ipv6_subnet_prefix: u8,
+    /// Maximum new connections from one address in each `throttle_period`
+    #[serde(default)]
+    pub throttle_count: Option<u32>,
+    /// Length of the connection throttle window, in seconds
+    #[serde(default = "default_throttle_period")]
+    pub throttle_period: u64,
+    /// Sources, such as known bouncers, to which no limits apply
+    #[serde(default)]
+    pub exempt: Vec<IpNet>,
+}
+
+fn default_ipv4_subnet_prefix() -> u8 {
+    24
+}
+
+fn default_ipv6_subnet_prefix() -> u8 {
+    64
+}
+
+fn default_throttle_period() -> u64 {
+    60
+}
+
+impl Default for ConnectionLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_per_ip: None,
+            max_per_subnet: None,
+            ipv4_subnet_prefix: default_ipv4_subnet_prefix(),
+            ipv6_subnet_prefix: default_ipv6_subnet_prefix(),
+            throttle_count: None,
+            throttle_period: default_throttle_period(),
+            exempt: Vec::new(),
+        }
+    }
+}
+
+/// Reasons a new connection may exceed a [`ConnectionLimitConfig`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConnectionLimitExceeded {
+    #[error("Too many connections from your address (max {0})")]
+    PerIp(u32),
+    #[error("Too many connections from your subnet {0} (max {1})")]
+    PerSubnet(IpNet, u32),
+}
+
+impl ConnectionLimitConfig {
+    /// The subnet, of the configured size, which contains `ip`
+    pub fn subnet_of(&self, ip: IpAddr) -> Option<IpNet> {
+        let prefix = match ip {
+            IpAddr::V4(_) => self.ipv4_subnet_prefix,
+            IpAddr::V6(_) => self.ipv6_subnet_prefix,
+        };
+        IpNet::new(ip, prefix).ok().map(|net| net.trunc())
+    }
+
+    /// Determine whether `ip` is exempt from these limits
+    pub fn is_exempt(&self, ip: IpAddr) -> bool {
+        self.exempt.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Check whether a new connection from `ip` would exceed these limits, given the
+    /// addresses of the existing connections
+    pub fn check(
+        &self,
+        ip: IpAddr,
+        existing: impl Iterator<Item = IpAddr>,
+    ) -> Result<(), ConnectionLimitExceeded> {
+        if self.is_exempt(ip) || (self.max_per_ip.is_none() && self.max_per_subnet.is_none()) {
+            return Ok(());
+        }
+
+        let subnet = self.subnet_of(ip);
+        let (mut same_ip, mut same_subnet) = (0, 0);
+        for other in existing {
+            if other == ip {
+                same_ip += 1;
+            }
+            if subnet.is_some_and(|net| net.contains(&other)) {
+                same_subnet += 1;
+            }
+        }
+
+        if let Some(max) = self.max_per_ip {
+            if same_ip >= max {
+                return Err(ConnectionLimitExceeded::PerIp(max));
+            }
+        }
+        if let (Some(max), Some(subnet)) = (self.max_per_subnet, subnet) {
+            if same_subnet >= max {
+                return Err(ConnectionLimitExceeded::PerSubnet(subnet, max));
+            }
+        }
+        Ok(())
+    }
+}
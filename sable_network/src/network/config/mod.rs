@@ -4,6 +4,12 @@ use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use std::collections::HashMap;
 
+mod limits;
+pub use limits::{ConnectionLimitConfig, ConnectionLimitExceeded};
+
+mod validate;
+pub use validate::{ConfigChange, NetworkConfigError};
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
@@ -16,6 +22,9 @@ pub struct NetworkConfig {
     pub alias_users: Vec<AliasUser>,
 
     pub object_expiry: i64,
+
+    #[serde(default)]
+    pub connection_limits: ConnectionLimitConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +51,7 @@ impl NetworkConfig {
             default_roles: HashMap::new(),
             alias_users: Vec::new(),
             object_expiry: 0,
+            connection_limits: ConnectionLimitConfig::default(),
         }
     }
 }
@@ -0,0 +1,143 @@
+use super::NetworkConfig;
+use crate::sync::replay::diff_json;
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Problems which prevent a [`NetworkConfig`] from being loaded
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NetworkConfigError {
+    #[error("Oper block {0} has an empty name")]
+    EmptyOperName(usize),
+    #[error("Oper {0} has no password hash")]
+    EmptyOperHash(String),
+    #[error("Oper {0} is defined more than once")]
+    DuplicateOper(String),
+    #[error("Alias user {0} has an empty command alias")]
+    EmptyCommandAlias(String),
+    #[error("Nickname {0} is used by more than one alias user")]
+    DuplicateAliasNick(String),
+    #[error("Command alias {0} is used by more than one alias user")]
+    DuplicateCommandAlias(String),
+    #[error("object_expiry must not be negative")]
+    NegativeObjectExpiry,
+    #[error("debug_mode must be enabled while servers with debug functionality are linked")]
+    DebugModeRequired,
+    #[error("Connection limit subnet prefix /{0} is too long")]
+    InvalidSubnetPrefix(u8),
+}
+
+/// Settings whose values are secret, and are never shown in a [`ConfigChange`]
+const SECRET_FIELDS: &[&str] = &["hash"];
+
+/// Shown in place of a secret value which has changed
+const REDACTED: &str = "<redacted>";
+
+/// A single setting which differs between two network configs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    /// Location of the setting, e.g. `.opers[0].hash`
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl NetworkConfig {
+    /// Check that this config is internally consistent, returning every problem found
+    pub fn validate(&self) -> Result<(), Vec<NetworkConfigError>> {
+        let mut errors = Vec::new();
+
+        let mut oper_names = HashSet::new();
+        for (i, oper) in self.opers.iter().enumerate() {
+            if oper.name.is_empty() {
+                errors.push(NetworkConfigError::EmptyOperName(i));
+            } else if !oper_names.insert(oper.name.as_str()) {
+                errors.push(NetworkConfigError::DuplicateOper(oper.name.clone()));
+            }
+            if oper.hash.is_empty() {
+                errors.push(NetworkConfigError::EmptyOperHash(oper.name.clone()));
+            }
+        }
+
+        let mut nicks = HashSet::new();
+        let mut aliases = HashSet::new();
+        for alias in &self.alias_users {
+            if alias.command_alias.is_empty() {
+                errors.push(NetworkConfigError::EmptyCommandAlias(
+                    alias.nick.to_string(),
+                ));
+            } else if !aliases.insert(alias.command_alias.to_ascii_lowercase()) {
+                errors.push(NetworkConfigError::DuplicateCommandAlias(
+                    alias.command_alias.clone(),
+                ));
+            }
+            if !nicks.insert(alias.nick) {
+                errors.push(NetworkConfigError::DuplicateAliasNick(
+                    alias.nick.to_string(),
+                ));
+            }
+        }
+
+        if self.object_expiry < 0 {
+            errors.push(NetworkConfigError::NegativeObjectExpiry);
+        }
+
+        let limits = &self.connection_limits;
+        if limits.ipv4_subnet_prefix > 32 {
+            errors.push(NetworkConfigError::InvalidSubnetPrefix(
+                limits.ipv4_subnet_prefix,
+            ));
+        }
+        if limits.ipv6_subnet_prefix > 128 {
+            errors.push(NetworkConfigError::InvalidSubnetPrefix(
+                limits.ipv6_subnet_prefix,
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// List the settings which would change if `new` replaced this config
+    pub fn diff(&self, new: &NetworkConfig) -> Vec<ConfigChange> {
+        let old = serde_json::to_value(self).expect("Failed to serialise network config");
+        let new = serde_json::to_value(new).expect("Failed to serialise network config");
+
+        diff_json(&old, &new)
+            .into_iter()
+            .map(|d| {
+                let secret = SECRET_FIELDS
+                    .iter()
+                    .any(|field| d.path.ends_with(&format!(".{field}")));
+                ConfigChange {
+                    old: d.left.map(|v| redact(v, secret)),
+                    new: d.right.map(|v| redact(v, secret)),
+                    path: d.path,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Hide `value` if it's `secret`, or any secret fields within it
+fn redact(value: Value, secret: bool) -> Value {
+    match value {
+        _ if secret => Value::String(REDACTED.to_string()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| {
+                    let secret = SECRET_FIELDS.contains(&k.as_str());
+                    (k, redact(v, secret))
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| redact(v, false)).collect()),
+        other => other,
+    }
+}
@@ -58,6 +58,8 @@ pub enum LookupError {
     NoSuchChannelAccess(ChannelAccessId),
     #[error("No such channel role {0:?}")]
     NoSuchChannelRole(ChannelRoleId),
+    #[error("No such channel auto-kick {0:?}")]
+    NoSuchChannelAutoKick(ChannelAutoKickId),
 }
 
 /// Convenience definition of a Result type used to look up network objects.
@@ -31,6 +31,12 @@ EventDetails => {
         pub hostname: Hostname,
         pub ip: std::net::IpAddr,
         pub connection_time: i64,
+        /// The name of the WEBIRC gateway through which the user connected, if any
+        #[serde(default)]
+        pub gateway: Option<String>,
+        /// The fingerprint of the client certificate presented on this connection, if any
+        #[serde(default)]
+        pub tls_fingerprint: Option<String>,
     }
 
     #[target_type(UserConnectionId)]
@@ -163,6 +169,9 @@ EventDetails => {
     #[target_type(ServerId)]
     struct ServerPing {
         pub ts: i64,
+        /// The sending server's view of the network state, for divergence detection
+        #[serde(default)]
+        pub digest: Option<StateDigest>,
     }
 
     #[target_type(ServerId)]
@@ -170,6 +179,11 @@ EventDetails => {
         pub epoch: EpochId,
     }
 
+    #[target_type(SyncPeerId)]
+    struct SyncPeerUpdate {
+        pub data: Option<state::SyncPeer>,
+    }
+
     #[target_type(ConfigId)]
     struct LoadConfig {
         pub config: config::NetworkConfig,
@@ -223,6 +237,11 @@ EventDetails => {
         pub data: Option<state::ChannelRole>,
     }
 
+    #[target_type(ChannelAutoKickId)]
+    struct ChannelAutoKickUpdate {
+        pub data: Option<state::ChannelAutoKick>,
+    }
+
     #[target_type(UserId)]
     struct UserAway {
         /// None iff the user is back from being away
@@ -234,4 +253,12 @@ EventDetails => {
         /// None means logout
         pub account: Option<AccountId>
     }
+
+    /// A kick issued by a server rather than a user, such as when enforcing an
+    /// auto-kick list
+    #[target_type(MembershipId)]
+    struct ServerChannelKick {
+        pub source: ServerId,
+        pub message: String,
+    }
 });
@@ -54,6 +54,10 @@ mod state_utils;
 
 #[cfg(test)]
 pub mod tests {
+    mod account_details;
+    mod auto_kick;
+    mod channel_settings;
+    mod config;
     mod event_application;
     pub mod fixtures;
     mod serialize;
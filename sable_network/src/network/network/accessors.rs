@@ -278,6 +278,11 @@ impl Network {
         self.servers.values().wrap(self)
     }
 
+    /// Iterate over sync network peers that were added at runtime
+    pub fn sync_peers(&self) -> impl std::iter::Iterator<Item = &state::SyncPeer> {
+        self.sync_peers.values()
+    }
+
     /// Look up a message by ID
     pub fn message(&self, id: MessageId) -> LookupResult<wrapper::Message<'_>> {
         self.messages.get(&id).ok_or(NoSuchMessage(id)).wrap(self)
@@ -312,6 +317,22 @@ impl Network {
         &self.config
     }
 
+    /// Check whether a new connection from `ip` would exceed the network's configured
+    /// connection limits.
+    ///
+    /// `unregistered` gives the addresses of connections which are still registering,
+    /// and so are counted alongside the network's user connections.
+    pub fn check_connection_limits(
+        &self,
+        ip: std::net::IpAddr,
+        unregistered: impl Iterator<Item = std::net::IpAddr>,
+    ) -> Result<(), config::ConnectionLimitExceeded> {
+        let existing = self.raw_user_connections().map(|conn| conn.ip);
+        self.config
+            .connection_limits
+            .check(ip, existing.chain(unregistered))
+    }
+
     /// Retrieve an audit log entry
     pub fn audit_entry(&self, id: AuditLogEntryId) -> LookupResult<&state::AuditLogEntry> {
         self.audit_log.get(&id).ok_or(NoSuchAuditLogEntry(id))
@@ -413,4 +434,20 @@ impl Network {
     pub fn channel_roles(&self) -> impl Iterator<Item = wrapper::ChannelRole<'_>> {
         self.channel_roles.values().wrap(self)
     }
+
+    /// Retrieve a channel auto-kick entry
+    pub fn channel_auto_kick(
+        &self,
+        id: ChannelAutoKickId,
+    ) -> LookupResult<wrapper::ChannelAutoKick<'_>> {
+        self.channel_auto_kicks
+            .get(&id)
+            .ok_or(NoSuchChannelAutoKick(id))
+            .wrap(self)
+    }
+
+    /// Iterate over all channel auto-kick entries
+    pub fn channel_auto_kicks(&self) -> impl Iterator<Item = wrapper::ChannelAutoKick<'_>> {
+        self.channel_auto_kicks.values().wrap(self)
+    }
 }
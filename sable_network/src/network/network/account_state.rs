@@ -94,6 +94,21 @@ impl Network {
         }
     }
 
+    pub(super) fn update_channel_auto_kick(
+        &mut self,
+        target: ChannelAutoKickId,
+        _event: &Event,
+        update: &ChannelAutoKickUpdate,
+        _updates: &dyn NetworkUpdateReceiver,
+    ) {
+        if let Some(data) = &update.data {
+            self.channel_auto_kicks.insert(target, data.clone());
+        } else {
+            // None here means deletion
+            self.channel_auto_kicks.remove(&target);
+        }
+    }
+
     pub(super) fn user_login(
         &mut self,
         target: UserId,
@@ -13,4 +13,14 @@ impl Network {
         let update = update::NewAuditLogEntry { entry: target };
         updates.notify(update, event);
     }
+
+    /// Replace the category of every audit log entry
+    pub(crate) fn map_audit_categories(
+        &mut self,
+        f: impl Fn(state::AuditLogCategory) -> state::AuditLogCategory,
+    ) {
+        for entry in self.audit_log.values_mut() {
+            entry.category = f(entry.category);
+        }
+    }
 }
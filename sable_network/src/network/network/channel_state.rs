@@ -90,8 +90,49 @@ impl Network {
                 details.name = state_utils::hashed_channel_name_for(target);
             }
         }
-        let channel = state::Channel::new(target, details.name, details.mode);
+        let mut channel = state::Channel::new(target, details.name, details.mode);
+        let mut retained_topic = None;
+
+        if let Some(registration) = self
+            .channel_registrations
+            .values()
+            .find(|reg| reg.channelname == channel.name)
+        {
+            // Apply the mode lock and retained topic for a registered channel. These are
+            // derived entirely from the registration data, so every server reaches the
+            // same result however the channel came to be created.
+            let lock = &registration.mode_lock;
+            channel.mode.modes |= lock.set;
+            channel.mode.modes &= !lock.unset;
+            if lock.key.is_some() {
+                channel.mode.key = lock.key;
+            }
+
+            if registration.keep_topic {
+                retained_topic = registration.topic.clone();
+            }
+        }
+
         self.channels.insert(channel.id, channel);
+
+        if let Some(retained) = retained_topic {
+            // Keep the original setter and time, so that any topic set since wins. The
+            // channel ID is unique, so its snowflake makes an equally unique topic ID.
+            let topic = state::ChannelTopic::new(
+                ChannelTopicId::new(*target),
+                target,
+                retained.text,
+                retained.setter_info,
+                retained.timestamp,
+            );
+
+            self.set_channel_topic(
+                topic,
+                HistoricMessageSourceId::Server(target.server()),
+                event,
+                updates,
+            );
+        }
     }
 
     pub(super) fn channel_mode_change(
@@ -150,16 +191,35 @@ impl Network {
         event: &Event,
         details: &details::NewChannelTopic,
         updates: &dyn NetworkUpdateReceiver,
+    ) {
+        let new_topic = state::ChannelTopic::new(
+            target,
+            details.channel,
+            details.text.clone(),
+            self.translate_setter_info(details.setter),
+            event.timestamp,
+        );
+        let setter = self.translate_state_change_source(details.setter);
+
+        self.set_channel_topic(new_topic, setter, event, updates);
+    }
+
+    fn set_channel_topic(
+        &mut self,
+        new_topic: state::ChannelTopic,
+        setter: HistoricMessageSourceId,
+        event: &Event,
+        updates: &dyn NetworkUpdateReceiver,
     ) {
         if let Some(existing) = self
             .channel_topics
             .values()
-            .find(|t| t.channel == details.channel)
+            .find(|t| t.channel == new_topic.channel)
         {
             // This is a conflict - we can't have two topics for one channel. Keep the newer, drop the older.
             // As usual, use ID comparison as a tiebreaker if the timestamps are equal
-            if existing.timestamp > event.timestamp
-                || (existing.timestamp == event.timestamp && existing.id < target)
+            if existing.timestamp > new_topic.timestamp
+                || (existing.timestamp == new_topic.timestamp && existing.id < new_topic.id)
             {
                 // The existing one is newer and wins. Do nothing.
                 return;
@@ -171,26 +231,16 @@ impl Network {
 
         // If there was an existing topic for this channel, there isn't any more. Carry on.
 
-        let setter_info = self.translate_setter_info(details.setter);
-
-        let new_topic = state::ChannelTopic::new(
-            target,
-            details.channel,
-            details.text.clone(),
-            setter_info,
-            event.timestamp,
-        );
-
-        if let Some(channel) = self.channels.get(&details.channel) {
+        if let Some(channel) = self.channels.get(&new_topic.channel) {
             let update = update::ChannelTopicChange {
                 channel: channel.id,
-                topic: target,
-                new_text: details.text.clone(),
-                setter: self.translate_state_change_source(details.setter),
-                timestamp: event.timestamp,
+                topic: new_topic.id,
+                new_text: new_topic.text.clone(),
+                setter,
+                timestamp: new_topic.timestamp,
             };
 
-            self.channel_topics.insert(target, new_topic);
+            self.channel_topics.insert(new_topic.id, new_topic);
             updates.notify(update, event);
         }
     }
@@ -300,6 +350,39 @@ impl Network {
         event: &Event,
         details: &details::ChannelKick,
         updates: &dyn NetworkUpdateReceiver,
+    ) {
+        self.remove_kicked_member(
+            target,
+            event,
+            details.source.into(),
+            &details.message,
+            updates,
+        );
+    }
+
+    pub(super) fn server_kicked_from_channel(
+        &mut self,
+        target: MembershipId,
+        event: &Event,
+        details: &details::ServerChannelKick,
+        updates: &dyn NetworkUpdateReceiver,
+    ) {
+        self.remove_kicked_member(
+            target,
+            event,
+            details.source.into(),
+            &details.message,
+            updates,
+        );
+    }
+
+    fn remove_kicked_member(
+        &mut self,
+        target: MembershipId,
+        event: &Event,
+        source: ObjectId,
+        message: &str,
+        updates: &dyn NetworkUpdateReceiver,
     ) {
         if let Some(removed_membership) = self.memberships.remove(&target) {
             let empty = !self
@@ -313,9 +396,9 @@ impl Network {
             if let Some(user) = self.users.get(&target.user()) {
                 let update = update::ChannelKick {
                     membership: removed_membership,
-                    source: self.translate_state_change_source(details.source.into()),
+                    source: self.translate_state_change_source(source),
                     user: self.translate_historic_user_id(user),
-                    message: details.message.clone(),
+                    message: message.to_string(),
                 };
                 updates.notify(update, event);
             }
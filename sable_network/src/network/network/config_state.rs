@@ -3,6 +3,30 @@ use crate::network::update::*;
 use crate::prelude::*;
 
 impl Network {
+    /// Check whether `config` can be loaded into this network. As well as the config's
+    /// own consistency checks, this rejects configs which [`load_config`](Self::load_config)
+    /// would ignore.
+    pub fn validate_config(
+        &self,
+        config: &config::NetworkConfig,
+    ) -> Result<(), Vec<config::NetworkConfigError>> {
+        let mut errors = config.validate().err().unwrap_or_default();
+
+        if !config.debug_mode
+            && self
+                .servers()
+                .any(|s| s.flags().contains(state::ServerFlags::DEBUG))
+        {
+            errors.push(config::NetworkConfigError::DebugModeRequired);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     pub(super) fn load_config(
         &mut self,
         _target: ConfigId,
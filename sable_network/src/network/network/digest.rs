@@ -0,0 +1,104 @@
+use super::Network;
+use crate::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+
+/// Parts of the network state which are expired by each server on its own schedule,
+/// rather than in response to events, and so can legitimately differ between servers.
+const LOCAL_CATEGORIES: &[&str] = &["messages", "historic_users", "historic_nick_users"];
+
+/// A summary of the network state at a given event clock, used to detect whether
+/// two servers which have processed the same events agree on the result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDigest {
+    /// The network clock at which the digest was computed
+    pub clock: EventClock,
+    /// A hash for each category of state object, keyed by category name
+    pub categories: BTreeMap<String, String>,
+}
+
+impl StateDigest {
+    /// List the categories whose hashes differ between `self` and `other`
+    pub fn differences<'a>(&'a self, other: &'a StateDigest) -> Vec<&'a str> {
+        let mut ret: Vec<&str> = self
+            .categories
+            .iter()
+            .filter(|(name, hash)| other.categories.get(*name) != Some(hash))
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        ret.extend(
+            other
+                .categories
+                .keys()
+                .filter(|name| !self.categories.contains_key(*name))
+                .map(String::as_str),
+        );
+
+        ret
+    }
+}
+
+impl Network {
+    /// Compute a digest of the current state.
+    ///
+    /// Any two `Network`s which have applied the same set of events should produce
+    /// the same digest, regardless of the order in which they were applied.
+    pub fn state_digest(&self) -> StateDigest {
+        let json = serde_json::to_value(self).expect("Failed to serialise network");
+
+        let categories = match json {
+            Value::Object(fields) => fields
+                .into_iter()
+                .filter(|(name, _)| name != "clock" && !LOCAL_CATEGORIES.contains(&name.as_str()))
+                .map(|(name, value)| (name, hex::encode(hash_value(&value))))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+
+        StateDigest {
+            clock: self.clock.clone(),
+            categories,
+        }
+    }
+}
+
+/// Hash a JSON value in a way that doesn't depend on the order of array elements,
+/// since most collections in the network state are serialised from hash maps.
+fn hash_value(value: &Value) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+
+    match value {
+        Value::Array(items) => {
+            let mut hashes: Vec<_> = items.iter().map(hash_value).collect();
+            hashes.sort_unstable();
+
+            hasher.update(b"a");
+            for hash in hashes {
+                hasher.update(hash);
+            }
+        }
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_unstable_by_key(|(key, _)| *key);
+
+            hasher.update(b"o");
+            for (key, value) in fields {
+                hasher.update(key.as_bytes());
+                hasher.update([0]);
+                hasher.update(hash_value(value));
+            }
+        }
+        scalar => {
+            hasher.update(b"s");
+            hasher.update(scalar.to_string().as_bytes());
+        }
+    }
+
+    let mut out = [0; 20];
+    out.copy_from_slice(&hasher.finalize());
+    out
+}
@@ -67,6 +67,8 @@ pub struct Network {
 
     #[serde_as(as = "Vec<(_,_)>")]
     servers: HashMap<ServerId, state::Server>,
+    #[serde_as(as = "Vec<(_,_)>")]
+    sync_peers: HashMap<SyncPeerId, state::SyncPeer>,
 
     network_bans: ban::BanRepository,
 
@@ -88,6 +90,9 @@ pub struct Network {
     #[serde_as(as = "Vec<(_,_)>")]
     channel_roles: HashMap<ChannelRoleId, state::ChannelRole>,
 
+    #[serde_as(as = "Vec<(_,_)>")]
+    channel_auto_kicks: HashMap<ChannelAutoKickId, state::ChannelAutoKick>,
+
     current_services: Option<state::ServicesData>,
     current_history_server_id: Option<ServerId>,
     config: config::NetworkConfig,
@@ -120,6 +125,7 @@ impl Network {
 
             messages: HashMap::new(),
             servers: HashMap::new(),
+            sync_peers: HashMap::new(),
             network_bans: ban::BanRepository::new(),
 
             audit_log: HashMap::new(),
@@ -129,6 +135,7 @@ impl Network {
             channel_registrations: HashMap::new(),
             channel_accesses: HashMap::new(),
             channel_roles: HashMap::new(),
+            channel_auto_kicks: HashMap::new(),
 
             current_services: None,
             current_history_server_id: None,
@@ -205,6 +212,7 @@ impl Network {
             NewServer => self.new_server,
             ServerPing => self.server_ping,
             ServerQuit => self.server_quit,
+            SyncPeerUpdate => self.update_sync_peer,
             LoadConfig => self.load_config,
             NewAuditLogEntry => self.new_audit_log,
             EnablePersistentSession => self.enable_persistent_session,
@@ -216,8 +224,10 @@ impl Network {
             ChannelRegistrationUpdate => self.update_channel_registration,
             ChannelAccessUpdate => self.update_channel_access,
             ChannelRoleUpdate => self.update_channel_role,
+            ChannelAutoKickUpdate => self.update_channel_auto_kick,
             UserAway => self.user_away,
             UserLogin => self.user_login,
+            ServerChannelKick => self.server_kicked_from_channel,
         })?;
 
         self.clock.update_with_id(event.id);
@@ -280,6 +290,8 @@ impl Network {
 mod accessors;
 mod alias_users;
 mod default_roles;
+mod digest;
+pub use digest::StateDigest;
 
 mod user_history;
 use user_history::*;
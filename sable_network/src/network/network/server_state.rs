@@ -55,6 +55,28 @@ impl Network {
         self.delete_server(target, event, updates);
     }
 
+    pub(super) fn update_sync_peer(
+        &mut self,
+        target: SyncPeerId,
+        event: &Event,
+        detail: &details::SyncPeerUpdate,
+        updates: &dyn NetworkUpdateReceiver,
+    ) {
+        if let Some(data) = &detail.data {
+            self.sync_peers.insert(target, data.clone());
+        } else {
+            self.sync_peers.remove(&target);
+        }
+
+        updates.notify(
+            update::SyncPeerChange {
+                peer: target,
+                data: detail.data.clone(),
+            },
+            event,
+        );
+    }
+
     fn delete_server(
         &mut self,
         target: ServerId,
@@ -375,6 +375,8 @@ impl Network {
                 hostname: detail.hostname,
                 ip: detail.ip,
                 connection_time: detail.connection_time,
+                gateway: detail.gateway.clone(),
+                tls_fingerprint: detail.tls_fingerprint.clone(),
             },
         );
 
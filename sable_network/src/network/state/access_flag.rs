@@ -16,6 +16,8 @@ pub enum ChannelAccessFlag {
     AccessEdit = 0x0200_0000_0000,
     RoleView = 0x0400_0000_0000,
     RoleEdit = 0x0800_0000_0000,
+    Akick = 0x1000_0000_0000,
+    Set = 0x2000_0000_0000,
 
     OpSelf = 0x0010_0000_0000,
     OpGrant = 0x0020_0000_0000,
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
@@ -8,6 +9,79 @@ pub struct Account {
     pub name: Nickname,
 
     pub authorised_fingerprints: Vec<String>,
+
+    /// Time of registration; zero if unknown
+    #[serde(default)]
+    pub registered: i64,
+    /// Time of the most recent successful login through services
+    #[serde(default)]
+    pub last_login: Option<i64>,
+    /// Free-form information published by the account holder, e.g. a URL
+    #[serde(default)]
+    pub public_fields: BTreeMap<String, String>,
+    /// If set, `last_login` and `public_fields` are only shown to the account
+    /// holder and to opers
+    #[serde(default)]
+    pub private: bool,
+}
+
+impl Account {
+    /// The maximum number of public fields an account may have
+    pub const MAX_PUBLIC_FIELDS: usize = 16;
+    /// The maximum length of a public field's name
+    pub const MAX_PUBLIC_FIELD_NAME_LEN: usize = 32;
+    /// The maximum length of a public field's value
+    pub const MAX_PUBLIC_FIELD_VALUE_LEN: usize = 300;
+
+    /// Check whether the public field `name` may be set to `value`, or removed if
+    /// `value` is `None`
+    pub fn check_public_field(
+        &self,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), PublicFieldError> {
+        if name.is_empty()
+            || name.len() > Self::MAX_PUBLIC_FIELD_NAME_LEN
+            || !name.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return Err(PublicFieldError::InvalidName);
+        }
+
+        let Some(value) = value else {
+            return Ok(());
+        };
+
+        if value.len() > Self::MAX_PUBLIC_FIELD_VALUE_LEN {
+            return Err(PublicFieldError::ValueTooLong);
+        }
+        if !self.public_fields.contains_key(name)
+            && self.public_fields.len() >= Self::MAX_PUBLIC_FIELDS
+        {
+            return Err(PublicFieldError::TooManyFields);
+        }
+
+        Ok(())
+    }
+}
+
+/// Reasons for which a public account field can't be set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+pub enum PublicFieldError {
+    #[error(
+        "Field names must be alphanumeric and at most {} characters long",
+        Account::MAX_PUBLIC_FIELD_NAME_LEN
+    )]
+    InvalidName,
+    #[error(
+        "Field values may be at most {} characters long",
+        Account::MAX_PUBLIC_FIELD_VALUE_LEN
+    )]
+    ValueTooLong,
+    #[error(
+        "Accounts may have at most {} public fields",
+        Account::MAX_PUBLIC_FIELDS
+    )]
+    TooManyFields,
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +95,79 @@ pub struct NickRegistration {
 pub struct ChannelRegistration {
     pub id: ChannelRegistrationId,
     pub channelname: ChannelName,
+    /// Time of registration; zero if unknown
+    #[serde(default)]
+    pub registered: i64,
+
+    /// Modes which are applied when the channel is created, and can't be changed while it exists
+    #[serde(default)]
+    pub mode_lock: ChannelModeLock,
+    /// Whether the most recent topic should be restored when the channel is recreated
+    #[serde(default)]
+    pub keep_topic: bool,
+    /// The most recent topic, maintained by services while `keep_topic` is set
+    #[serde(default)]
+    pub topic: Option<RetainedTopic>,
+    /// A notice sent to users when they join the channel
+    #[serde(default)]
+    pub entry_message: Option<String>,
+}
+
+/// Modes which are forced on or off for a registered channel
+#[derive(PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelModeLock {
+    pub set: ChannelModeSet,
+    pub unset: ChannelModeSet,
+    pub key: Option<ChannelKey>,
+}
+
+/// A channel topic retained in a channel registration
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct RetainedTopic {
+    pub text: String,
+    pub setter_info: String,
+    pub timestamp: i64,
+}
+
+impl ChannelModeLock {
+    /// Whether this lock has any effect
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty() && self.key.is_none()
+    }
+
+    /// Drop any part of a requested mode change which this lock forbids
+    pub fn restrict(
+        &self,
+        added: &mut ChannelModeSet,
+        removed: &mut ChannelModeSet,
+        key_change: &mut OptionChange<ChannelKey>,
+    ) {
+        *added &= !self.unset;
+        *removed &= !self.set;
+        if self.key.is_some() {
+            *key_change = OptionChange::NoChange;
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelModeLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut set = self.set.to_chars();
+        if self.key.is_some() {
+            set.push('k');
+        }
+
+        if !set.is_empty() {
+            write!(f, "+{}", set)?;
+        }
+        if !self.unset.is_empty() {
+            write!(f, "-{}", self.unset.to_chars())?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, " {}", key)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +176,18 @@ pub struct ChannelAccess {
     pub role: ChannelRoleId,
 }
 
+/// An entry on a registered channel's auto-kick list. Users matching `mask` are
+/// prevented from joining the channel, and kicked if they manage to join anyway.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelAutoKick {
+    pub id: ChannelAutoKickId,
+    pub channel: ChannelRegistrationId,
+    pub mask: Pattern,
+    pub reason: Option<String>,
+    pub setter: AccountId,
+    pub timestamp: i64,
+}
+
 #[derive(
     PartialEq, Eq, Hash, Debug, Clone, serde_with::SerializeDisplay, serde_with::DeserializeFromStr,
 )]
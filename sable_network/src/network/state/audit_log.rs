@@ -8,6 +8,7 @@ pub enum AuditLogCategory {
     General,
     NetworkBan,
     ServerKill,
+    NetworkConfig,
 }
 
 /// An audit log entry
@@ -22,3 +22,18 @@ pub struct Server {
     pub flags: ServerFlags,
     pub version: String,
 }
+
+/// A peer in the sync network, added at runtime rather than in the static
+/// sync configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPeer {
+    pub id: SyncPeerId,
+    pub address: String,
+    pub fingerprint: String,
+}
+
+impl SyncPeer {
+    pub fn name(&self) -> &ServerName {
+        self.id.name()
+    }
+}
@@ -31,6 +31,12 @@ pub struct UserConnection {
     pub hostname: Hostname,
     pub ip: IpAddr,
     pub connection_time: i64,
+    /// The name of the WEBIRC gateway through which the user connected, if any
+    #[serde(default)]
+    pub gateway: Option<String>,
+    /// The fingerprint of the client certificate presented on this connection, if any
+    #[serde(default)]
+    pub tls_fingerprint: Option<String>,
 }
 
 /// A user.
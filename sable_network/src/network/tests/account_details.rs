@@ -0,0 +1,115 @@
+use super::fixtures::*;
+use crate::policy::{RegistrationPolicyService, StandardRegistrationPolicy};
+use crate::prelude::*;
+use state::PublicFieldError;
+use std::str::FromStr;
+use wrapper::ObjectWrapper;
+
+fn nick(s: &str) -> Nickname {
+    Nickname::from_str(s).unwrap()
+}
+
+fn set_private(builder: &mut NetworkBuilder, account: AccountId) {
+    let mut data = builder.net.account(account).unwrap().raw().clone();
+    data.private = true;
+    builder.apply(account, event::AccountUpdate { data: Some(data) });
+}
+
+#[test]
+fn public_field_limits() {
+    let mut account = state::Account {
+        id: AccountId::new(Snowflake::from_parts(ServerId::new(1), 0, 1)),
+        name: nick("someone"),
+        authorised_fingerprints: Vec::new(),
+        registered: 0,
+        last_login: None,
+        public_fields: Default::default(),
+        private: false,
+    };
+
+    assert_eq!(
+        account.check_public_field("url", Some("https://example.com/")),
+        Ok(())
+    );
+    assert_eq!(
+        account.check_public_field("no spaces", Some("x")),
+        Err(PublicFieldError::InvalidName)
+    );
+    assert_eq!(
+        account.check_public_field(&"a".repeat(33), None),
+        Err(PublicFieldError::InvalidName)
+    );
+    assert_eq!(
+        account.check_public_field("url", Some(&"x".repeat(301))),
+        Err(PublicFieldError::ValueTooLong)
+    );
+
+    for i in 0..state::Account::MAX_PUBLIC_FIELDS {
+        account
+            .public_fields
+            .insert(format!("field{i}"), "x".to_string());
+    }
+    assert_eq!(
+        account.check_public_field("another", Some("x")),
+        Err(PublicFieldError::TooManyFields)
+    );
+    // Existing fields can still be changed or removed when at the limit
+    assert_eq!(account.check_public_field("field0", Some("y")), Ok(()));
+    assert_eq!(account.check_public_field("another", None), Ok(()));
+}
+
+#[test]
+fn account_details_visibility() {
+    let mut builder = NetworkBuilder::new();
+    let account = builder.add_account(nick("owner"));
+    let owner = builder.add_user_with(nick("owner"), "host.name", Some(account));
+    let other = builder.add_user(nick("other"));
+    let oper = builder.add_user(nick("oper"));
+    builder.apply(
+        oper,
+        event::OperUp {
+            oper_name: "oper".to_string(),
+        },
+    );
+
+    let policy = StandardRegistrationPolicy::new();
+
+    let net = &builder.net;
+    let acc = net.account(account).unwrap();
+    for user in [owner, other, oper] {
+        assert!(policy.can_view_account_details(&net.user(user).unwrap(), &acc));
+    }
+
+    set_private(&mut builder, account);
+
+    let net = &builder.net;
+    let acc = net.account(account).unwrap();
+    let visible = |user| policy.can_view_account_details(&net.user(user).unwrap(), &acc);
+    assert!(visible(owner));
+    assert!(visible(oper));
+    assert!(!visible(other));
+}
+
+#[test]
+fn fingerprints_visible_to_owner_and_opers() {
+    let mut builder = NetworkBuilder::new();
+    let account = builder.add_account(nick("owner"));
+    let owner = builder.add_user_with(nick("owner"), "host.name", Some(account));
+    let other = builder.add_user(nick("other"));
+    let oper = builder.add_user(nick("oper"));
+    builder.apply(
+        oper,
+        event::OperUp {
+            oper_name: "oper".to_string(),
+        },
+    );
+
+    let policy = StandardRegistrationPolicy::new();
+    let net = &builder.net;
+    let acc = net.account(account).unwrap();
+    let visible = |user| policy.can_view_account_fingerprints(&net.user(user).unwrap(), &acc);
+
+    assert!(visible(owner));
+    assert!(visible(oper));
+    assert!(!visible(other));
+}
@@ -0,0 +1,148 @@
+use super::fixtures::*;
+use crate::policy::{
+    ChannelPermissionError, ChannelPolicyService, PermissionError, RegistrationPermissionError,
+    RegistrationPolicyService, StandardChannelPolicy, StandardRegistrationPolicy,
+};
+use crate::prelude::*;
+use state::ChannelAccessFlag;
+use std::str::FromStr;
+
+struct AutoKickFixture {
+    builder: NetworkBuilder,
+    channel: ChannelId,
+    founder: AccountId,
+    auto_kick: ChannelAutoKickId,
+}
+
+fn fixture() -> AutoKickFixture {
+    let mut builder = NetworkBuilder::new();
+    let name = ChannelName::from_str("#chan").unwrap();
+
+    let founder = builder.add_account(Nickname::from_str("founder").unwrap());
+    let registration = builder.register_channel(name);
+    let channel = builder.add_channel(name);
+    let auto_kick = builder.add_auto_kick(registration, "*!*@*.bad.example", founder);
+
+    AutoKickFixture {
+        builder,
+        channel,
+        founder,
+        auto_kick,
+    }
+}
+
+fn nick(s: &str) -> Nickname {
+    Nickname::from_str(s).unwrap()
+}
+
+#[test]
+fn auto_kick_matches_hostmask() {
+    let mut f = fixture();
+    let bad = f
+        .builder
+        .add_user_with(nick("bad"), "host.bad.example", None);
+    let good = f
+        .builder
+        .add_user_with(nick("good"), "host.good.example", None);
+
+    let policy = StandardChannelPolicy::new();
+    let net = &f.builder.net;
+    let channel = net.channel(f.channel).unwrap();
+
+    let bad = net.user(bad).unwrap();
+    assert_eq!(policy.auto_kick_for(&bad, &channel), Some(f.auto_kick));
+    assert!(matches!(
+        policy.can_join(&bad, &channel, None),
+        Err(PermissionError::Channel(
+            _,
+            ChannelPermissionError::UserIsBanned
+        ))
+    ));
+
+    let good = net.user(good).unwrap();
+    assert_eq!(policy.auto_kick_for(&good, &channel), None);
+    assert!(policy.can_join(&good, &channel, None).is_ok());
+}
+
+#[test]
+fn invite_self_access_exempts_from_auto_kick() {
+    let mut f = fixture();
+    let registration = f
+        .builder
+        .net
+        .channel_registration_by_name(ChannelName::from_str("#chan").unwrap())
+        .unwrap()
+        .id();
+    f.builder
+        .grant_access(f.founder, registration, vec![ChannelAccessFlag::InviteSelf]);
+    let user = f
+        .builder
+        .add_user_with(nick("founder"), "host.bad.example", Some(f.founder));
+    let membership = f.builder.join(user, f.channel);
+
+    let policy = StandardChannelPolicy::new();
+    let net = &f.builder.net;
+    let channel = net.channel(f.channel).unwrap();
+    let user = net.user(user).unwrap();
+
+    // Exempt both when joining and once they're in the channel
+    assert!(policy.can_join(&user, &channel, None).is_ok());
+    assert!(net.membership(membership).is_ok());
+    assert_eq!(policy.auto_kick_for(&user, &channel), None);
+}
+
+#[test]
+fn server_kick_removes_member() {
+    let mut f = fixture();
+    let user = f
+        .builder
+        .add_user_with(nick("bad"), "host.bad.example", None);
+    let membership = f.builder.join(user, f.channel);
+
+    let updates = RecordingUpdateReceiver::default();
+    f.builder.apply_with(
+        membership,
+        event::details::ServerChannelKick {
+            source: ServerId::new(1),
+            message: "go away".to_string(),
+        },
+        &updates,
+    );
+
+    assert!(f.builder.net.membership(membership).is_err());
+
+    let updates = updates.updates.into_inner();
+    let kick = updates
+        .iter()
+        .find_map(|update| match update {
+            NetworkStateChange::ChannelKick(kick) => Some(kick),
+            _ => None,
+        })
+        .expect("no kick update");
+    assert!(matches!(
+        kick.source,
+        state::HistoricMessageSourceId::Server(id) if id == ServerId::new(1)
+    ));
+    assert_eq!(kick.message, "go away");
+}
+
+#[test]
+fn masks_matching_everyone_are_rejected() {
+    let policy = StandardRegistrationPolicy::new();
+
+    for mask in ["", "*", "*!*@*", "*@*", "?*!*@*"] {
+        assert!(
+            matches!(
+                policy.validate_auto_kick_mask(mask),
+                Err(PermissionError::Registration(
+                    RegistrationPermissionError::InvalidMask
+                ))
+            ),
+            "{mask:?} was accepted"
+        );
+    }
+
+    for mask in ["*!*@*.bad.example", "bad!*@*", "*!~bad@*"] {
+        assert!(policy.validate_auto_kick_mask(mask).is_ok(), "{mask:?}");
+    }
+}
@@ -0,0 +1,100 @@
+use super::fixtures::*;
+use crate::prelude::*;
+use std::str::FromStr;
+use wrapper::ObjectWrapper;
+
+fn chan_name() -> ChannelName {
+    ChannelName::from_str("#chan").unwrap()
+}
+
+fn mode_lock() -> state::ChannelModeLock {
+    state::ChannelModeLock {
+        set: ChannelModeSet::new() | ChannelModeFlag::NoExternal | ChannelModeFlag::TopicLock,
+        unset: ChannelModeSet::new() | ChannelModeFlag::InviteOnly,
+        key: Some(ChannelKey::from_str("secret").unwrap()),
+    }
+}
+
+/// Register `#chan`, then apply `f` to its registration
+fn register(
+    builder: &mut NetworkBuilder,
+    f: impl FnOnce(&mut state::ChannelRegistration),
+) -> ChannelRegistrationId {
+    let id = builder.register_channel(chan_name());
+    let mut data = builder.net.channel_registration(id).unwrap().raw().clone();
+    f(&mut data);
+    builder.apply(id, event::ChannelRegistrationUpdate { data: Some(data) });
+    id
+}
+
+fn retained(text: &str, timestamp: i64) -> state::RetainedTopic {
+    state::RetainedTopic {
+        text: text.to_string(),
+        setter_info: "nick!user@host".to_string(),
+        timestamp,
+    }
+}
+
+#[test]
+fn mode_lock_applied_on_creation() {
+    let mut builder = NetworkBuilder::new();
+    register(&mut builder, |reg| reg.mode_lock = mode_lock());
+
+    let channel = builder.add_channel(chan_name());
+    let channel = builder.net.channel(channel).unwrap();
+    let mode = channel.mode();
+
+    assert!(mode.has_mode(ChannelModeFlag::NoExternal));
+    assert!(mode.has_mode(ChannelModeFlag::TopicLock));
+    assert!(!mode.has_mode(ChannelModeFlag::InviteOnly));
+    assert_eq!(mode.key(), Some(ChannelKey::from_str("secret").unwrap()));
+}
+
+#[test]
+fn mode_lock_restricts_changes() {
+    let lock = mode_lock();
+
+    let mut added = ChannelModeSet::new() | ChannelModeFlag::InviteOnly | ChannelModeFlag::Secret;
+    let mut removed =
+        ChannelModeSet::new() | ChannelModeFlag::NoExternal | ChannelModeFlag::Moderated;
+    let mut key_change = OptionChange::Unset;
+    lock.restrict(&mut added, &mut removed, &mut key_change);
+
+    assert_eq!(added, ChannelModeSet::new() | ChannelModeFlag::Secret);
+    assert_eq!(removed, ChannelModeSet::new() | ChannelModeFlag::Moderated);
+    assert!(key_change.is_no_change());
+
+    // Without a locked key, key changes are allowed through
+    let lock = state::ChannelModeLock::default();
+    let mut key_change = OptionChange::Unset;
+    lock.restrict(&mut added, &mut removed, &mut key_change);
+    assert!(matches!(key_change, OptionChange::Unset));
+}
+
+#[test]
+fn retained_topic_restored_on_creation() {
+    let mut builder = NetworkBuilder::new();
+    register(&mut builder, |reg| {
+        reg.keep_topic = true;
+        reg.topic = Some(retained("a topic", 4000));
+    });
+
+    // However the channel is created, the topic comes back with its original setter and time
+    let channel = builder.add_channel(chan_name());
+
+    let topic = builder.net.topic_for_channel(channel).unwrap();
+    assert_eq!(topic.text(), "a topic");
+    assert_eq!(topic.setter(), "nick!user@host");
+    assert_eq!(topic.timestamp(), 4000);
+}
+
+#[test]
+fn retained_topic_needs_keep_topic() {
+    let mut builder = NetworkBuilder::new();
+    register(&mut builder, |reg| {
+        reg.topic = Some(retained("a topic", 4000))
+    });
+
+    let channel = builder.add_channel(chan_name());
+    assert!(builder.net.topic_for_channel(channel).is_err());
+}
@@ -0,0 +1,146 @@
+use crate::network::config::*;
+
+fn oper(name: &str, hash: &str) -> OperConfig {
+    OperConfig {
+        name: name.to_string(),
+        hash: hash.to_string(),
+    }
+}
+
+#[test]
+fn validate_reports_every_problem() {
+    let mut config = NetworkConfig::new();
+    config.opers = vec![
+        oper("admin", "$6$abc"),
+        oper("admin", ""),
+        oper("", "$6$def"),
+    ];
+    config.object_expiry = -1;
+
+    let errors = config.validate().unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            NetworkConfigError::DuplicateOper("admin".to_string()),
+            NetworkConfigError::EmptyOperHash("admin".to_string()),
+            NetworkConfigError::EmptyOperName(2),
+            NetworkConfigError::NegativeObjectExpiry,
+        ]
+    );
+
+    assert!(NetworkConfig::new().validate().is_ok());
+}
+
+#[test]
+fn diff_lists_changed_settings() {
+    let old = NetworkConfig::new();
+    let mut new = NetworkConfig::new();
+    new.object_expiry = 3600;
+
+    let changes = old.diff(&new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, ".object_expiry");
+    assert_eq!(changes[0].old, Some(serde_json::json!(0)));
+    assert_eq!(changes[0].new, Some(serde_json::json!(3600)));
+
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn diff_redacts_password_hashes() {
+    let mut old = NetworkConfig::new();
+    old.opers = vec![oper("admin", "$6$abc")];
+    let mut new = NetworkConfig::new();
+    new.opers = vec![oper("admin", "$6$def")];
+
+    let changes = old.diff(&new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, ".opers[0].hash");
+    assert_eq!(changes[0].old, Some(serde_json::json!("<redacted>")));
+    assert_eq!(changes[0].new, Some(serde_json::json!("<redacted>")));
+
+    // Hashes nested in a larger change are hidden too
+    new.opers.push(oper("other", "$6$ghi"));
+    let changes = old.diff(&new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, ".opers");
+    let json = serde_json::to_string(&changes).unwrap();
+    assert!(!json.contains("$6$"), "{}", json);
+    assert!(json.contains("other"), "{}", json);
+}
+
+#[test]
+fn connection_limits_count_matching_addresses() {
+    let limits = ConnectionLimitConfig {
+        max_per_ip: Some(2),
+        max_per_subnet: Some(3),
+        exempt: vec!["192.0.2.10/32".parse().unwrap()],
+        ..Default::default()
+    };
+    let ip = |s: &str| -> std::net::IpAddr { s.parse().unwrap() };
+
+    let existing = [ip("192.0.2.1"), ip("192.0.2.1"), ip("192.0.2.2")];
+
+    assert_eq!(
+        limits.check(ip("192.0.2.1"), existing.into_iter()),
+        Err(ConnectionLimitExceeded::PerIp(2))
+    );
+    assert_eq!(
+        limits.check(ip("192.0.2.3"), existing.into_iter()),
+        Err(ConnectionLimitExceeded::PerSubnet(
+            "192.0.2.0/24".parse().unwrap(),
+            3
+        ))
+    );
+    assert!(limits
+        .check(ip("198.51.100.1"), existing.into_iter())
+        .is_ok());
+    assert!(limits.check(ip("192.0.2.10"), existing.into_iter()).is_ok());
+
+    assert!(ConnectionLimitConfig::default()
+        .check(ip("192.0.2.1"), existing.into_iter())
+        .is_ok());
+}
+
+#[test]
+fn connection_limits_count_unregistered_connections() {
+    use crate::network::tests::fixtures::*;
+    use crate::prelude::*;
+    use std::str::FromStr;
+
+    let ip = |s: &str| -> std::net::IpAddr { s.parse().unwrap() };
+
+    let mut config = NetworkConfig::new();
+    config.connection_limits.max_per_ip = Some(2);
+    let mut builder = NetworkBuilder::new();
+    builder.net = Network::new(config);
+
+    let user = builder.add_user(Nickname::from_str("someone").unwrap());
+    let connection: UserConnectionId = builder.next_id();
+    builder.apply(
+        connection,
+        event::NewUserConnection {
+            user,
+            hostname: Hostname::from_str("host.name").unwrap(),
+            ip: ip("192.0.2.1"),
+            connection_time: 0,
+            gateway: None,
+            tls_fingerprint: None,
+        },
+    );
+
+    assert!(builder
+        .net
+        .check_connection_limits(ip("192.0.2.1"), std::iter::empty())
+        .is_ok());
+    assert_eq!(
+        builder
+            .net
+            .check_connection_limits(ip("192.0.2.1"), [ip("192.0.2.1")].into_iter()),
+        Err(ConnectionLimitExceeded::PerIp(2))
+    );
+    assert!(builder
+        .net
+        .check_connection_limits(ip("192.0.2.2"), [ip("192.0.2.1")].into_iter())
+        .is_ok());
+}
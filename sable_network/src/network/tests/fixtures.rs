@@ -13,6 +13,18 @@ impl NetworkUpdateReceiver for NopUpdateReceiver {
     fn notify_update(&self, _update: NetworkStateChange, _event: &Event) {}
 }
 
+/// Collects the updates emitted while applying events, for inspection by tests
+#[derive(Default)]
+pub struct RecordingUpdateReceiver {
+    pub updates: std::cell::RefCell<Vec<NetworkStateChange>>,
+}
+
+impl NetworkUpdateReceiver for RecordingUpdateReceiver {
+    fn notify_update(&self, update: NetworkStateChange, _event: &Event) {
+        self.updates.borrow_mut().push(update);
+    }
+}
+
 impl NetworkBuilder {
     pub fn new() -> Self {
         Self {
@@ -27,7 +39,20 @@ impl NetworkBuilder {
         json
     }
 
-    fn apply(&mut self, target: impl Into<ObjectId>, details: impl Into<EventDetails>) {
+    pub fn next_id<T: From<Snowflake>>(&self) -> T {
+        self.id_gen.next()
+    }
+
+    pub fn apply(&mut self, target: impl Into<ObjectId>, details: impl Into<EventDetails>) {
+        self.apply_with(target, details, &NopUpdateReceiver);
+    }
+
+    pub fn apply_with(
+        &mut self,
+        target: impl Into<ObjectId>,
+        details: impl Into<EventDetails>,
+        updates: &dyn NetworkUpdateReceiver,
+    ) {
         let evt = Event {
             clock: EventClock::new(),
             id: self.id_gen.next(),
@@ -35,33 +60,46 @@ impl NetworkBuilder {
             timestamp: 0,
             details: details.into(),
         };
-        self.net.apply(&evt, &NopUpdateReceiver).unwrap();
+        self.net.apply(&evt, updates).unwrap();
     }
 
-    pub fn add_channel(&mut self, name: ChannelName) {
+    pub fn add_channel(&mut self, name: ChannelName) -> ChannelId {
+        let id = self.id_gen.next::<ChannelId>();
         self.apply(
-            self.id_gen.next::<ChannelId>(),
+            id,
             details::NewChannel {
                 mode: state::ChannelMode::new(ChannelModeSet::default()),
                 name,
             },
         );
+        id
+    }
+
+    pub fn add_user(&mut self, nick: Nickname) -> UserId {
+        self.add_user_with(nick, "host.name", None)
     }
 
-    pub fn add_user(&mut self, nick: Nickname) {
+    pub fn add_user_with(
+        &mut self,
+        nick: Nickname,
+        host: &str,
+        account: Option<AccountId>,
+    ) -> UserId {
+        let id = self.id_gen.next::<UserId>();
         self.apply(
-            self.id_gen.next::<UserId>(),
+            id,
             details::NewUser {
                 mode: state::UserMode::new(UserModeSet::default()),
                 nickname: nick,
                 username: Username::from_str("a").unwrap(),
                 realname: Realname::from_str("user").unwrap(),
-                visible_hostname: Hostname::from_str("host.name").unwrap(),
+                visible_hostname: Hostname::from_str(host).unwrap(),
                 server: ServerId::new(1),
-                account: None,
+                account,
                 initial_connection: None,
             },
         );
+        id
     }
 
     pub fn remove_user(&mut self, id: UserId) {
@@ -72,4 +110,107 @@ impl NetworkBuilder {
             },
         )
     }
+
+    pub fn join(&mut self, user: UserId, channel: ChannelId) -> MembershipId {
+        let id = MembershipId::new(user, channel);
+        self.apply(
+            id,
+            details::ChannelJoin {
+                channel,
+                user,
+                permissions: MembershipFlagSet::default(),
+            },
+        );
+        id
+    }
+
+    pub fn add_account(&mut self, name: Nickname) -> AccountId {
+        let id = self.id_gen.next::<AccountId>();
+        self.apply(
+            id,
+            details::AccountUpdate {
+                data: Some(state::Account {
+                    id,
+                    name,
+                    authorised_fingerprints: Vec::new(),
+                    registered: 0,
+                    last_login: None,
+                    public_fields: Default::default(),
+                    private: false,
+                }),
+            },
+        );
+        id
+    }
+
+    pub fn register_channel(&mut self, name: ChannelName) -> ChannelRegistrationId {
+        let id = self.id_gen.next::<ChannelRegistrationId>();
+        self.apply(
+            id,
+            details::ChannelRegistrationUpdate {
+                data: Some(state::ChannelRegistration {
+                    id,
+                    channelname: name,
+                    registered: 0,
+                    mode_lock: Default::default(),
+                    keep_topic: false,
+                    topic: None,
+                    entry_message: None,
+                }),
+            },
+        );
+        id
+    }
+
+    /// Give `account` a role on `channel` carrying the given access flags
+    pub fn grant_access(
+        &mut self,
+        account: AccountId,
+        channel: ChannelRegistrationId,
+        flags: Vec<state::ChannelAccessFlag>,
+    ) {
+        let role = self.id_gen.next::<ChannelRoleId>();
+        self.apply(
+            role,
+            details::ChannelRoleUpdate {
+                data: Some(state::ChannelRole {
+                    id: role,
+                    channel: Some(channel),
+                    name: state::ChannelRoleName::Custom(CustomRoleName::from_str("test").unwrap()),
+                    flags: flags.into(),
+                }),
+            },
+        );
+
+        let id = ChannelAccessId::new(account, channel);
+        self.apply(
+            id,
+            details::ChannelAccessUpdate {
+                data: Some(state::ChannelAccess { id, role }),
+            },
+        );
+    }
+
+    pub fn add_auto_kick(
+        &mut self,
+        channel: ChannelRegistrationId,
+        mask: &str,
+        setter: AccountId,
+    ) -> ChannelAutoKickId {
+        let id = self.id_gen.next::<ChannelAutoKickId>();
+        self.apply(
+            id,
+            details::ChannelAutoKickUpdate {
+                data: Some(state::ChannelAutoKick {
+                    id,
+                    channel,
+                    mask: Pattern::new(mask.to_string()),
+                    reason: Some("go away".to_string()),
+                    setter,
+                    timestamp: 0,
+                }),
+            },
+        );
+        id
+    }
 }
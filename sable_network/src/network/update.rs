@@ -168,6 +168,12 @@ NetworkStateChange => {
         pub server: state::Server,
     }
 
+    /// A sync network peer has been added, changed or (if `data` is `None`) removed
+    struct SyncPeerChange {
+        pub peer: SyncPeerId,
+        pub data: Option<state::SyncPeer>,
+    }
+
     /// An entry has been added to the network audit log
     struct NewAuditLogEntry {
         pub entry: AuditLogEntryId,
@@ -15,6 +15,22 @@ impl Account<'_> {
         self.data.name
     }
 
+    pub fn registered(&self) -> i64 {
+        self.data.registered
+    }
+
+    pub fn last_login(&self) -> Option<i64> {
+        self.data.last_login
+    }
+
+    pub fn public_fields(&self) -> &std::collections::BTreeMap<String, String> {
+        &self.data.public_fields
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.data.private
+    }
+
     pub fn users(&self) -> impl Iterator<Item = wrapper::User<'_>> {
         let my_id = self.data.id;
         self.network
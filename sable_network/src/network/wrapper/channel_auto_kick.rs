@@ -0,0 +1,44 @@
+use crate::prelude::*;
+
+pub struct ChannelAutoKick<'a> {
+    network: &'a Network,
+    data: &'a state::ChannelAutoKick,
+}
+
+impl ChannelAutoKick<'_> {
+    pub fn id(&self) -> ChannelAutoKickId {
+        self.data.id
+    }
+
+    pub fn channel(&self) -> LookupResult<wrapper::ChannelRegistration<'_>> {
+        self.network.channel_registration(self.data.channel)
+    }
+
+    pub fn mask(&self) -> &Pattern {
+        &self.data.mask
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.data.reason.as_deref()
+    }
+
+    pub fn setter(&self) -> LookupResult<wrapper::Account<'_>> {
+        self.network.account(self.data.setter)
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        self.data.timestamp
+    }
+}
+
+impl<'a> super::ObjectWrapper<'a> for ChannelAutoKick<'a> {
+    type Underlying = state::ChannelAutoKick;
+
+    fn wrap(network: &'a Network, data: &'a Self::Underlying) -> Self {
+        Self { network, data }
+    }
+
+    fn raw(&self) -> &'a Self::Underlying {
+        self.data
+    }
+}
@@ -15,6 +15,26 @@ impl ChannelRegistration<'_> {
         &self.data.channelname
     }
 
+    pub fn registered(&self) -> i64 {
+        self.data.registered
+    }
+
+    pub fn mode_lock(&self) -> &state::ChannelModeLock {
+        &self.data.mode_lock
+    }
+
+    pub fn keep_topic(&self) -> bool {
+        self.data.keep_topic
+    }
+
+    pub fn retained_topic(&self) -> Option<&state::RetainedTopic> {
+        self.data.topic.as_ref()
+    }
+
+    pub fn entry_message(&self) -> Option<&str> {
+        self.data.entry_message.as_deref()
+    }
+
     pub fn access_entries(&self) -> impl Iterator<Item = ChannelAccess<'_>> {
         let my_id = self.data.id;
         self.network
@@ -30,6 +50,14 @@ impl ChannelRegistration<'_> {
             .filter(move |r| r.raw().channel == Some(my_id))
     }
 
+    /// Access the auto-kick list for this channel
+    pub fn auto_kicks(&self) -> impl Iterator<Item = ChannelAutoKick<'_>> {
+        let my_id = self.data.id;
+        self.network
+            .channel_auto_kicks()
+            .filter(move |a| a.raw().channel == my_id)
+    }
+
     /// Look up a role by name
     pub fn role_named(&self, name: &state::ChannelRoleName) -> Option<ChannelRole<'_>> {
         self.roles().find(|r| r.name() == name)
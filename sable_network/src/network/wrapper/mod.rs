@@ -2,6 +2,7 @@ mod account;
 mod bans;
 mod channel;
 mod channel_access;
+mod channel_auto_kick;
 mod channel_invite;
 mod channel_mode;
 mod channel_registration;
@@ -31,6 +32,7 @@ pub use account::*;
 pub use bans::*;
 pub use channel::Channel;
 pub use channel_access::*;
+pub use channel_auto_kick::*;
 pub use channel_invite::ChannelInvite;
 pub use channel_mode::ChannelMode;
 pub use channel_registration::*;
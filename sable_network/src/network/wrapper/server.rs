@@ -27,6 +27,11 @@ impl Server<'_> {
     pub fn last_ping(&self) -> i64 {
         self.data.last_ping
     }
+
+    /// Flags describing the server's build and role
+    pub fn flags(&self) -> state::ServerFlags {
+        self.data.flags
+    }
 }
 
 impl<'a> super::ObjectWrapper<'a> for Server<'a> {
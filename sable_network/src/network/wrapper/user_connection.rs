@@ -26,6 +26,16 @@ impl UserConnection<'_> {
         &self.data.hostname
     }
 
+    /// The WEBIRC gateway through which this connection was made, if any
+    pub fn gateway(&self) -> Option<&str> {
+        self.data.gateway.as_deref()
+    }
+
+    /// The fingerprint of the client certificate used for this connection, if any
+    pub fn tls_fingerprint(&self) -> Option<&str> {
+        self.data.tls_fingerprint.as_deref()
+    }
+
     pub fn server(&self) -> LookupResult<Server<'_>> {
         self.network.server(self.data.id.server())
     }
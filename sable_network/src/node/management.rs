@@ -3,47 +3,216 @@ use super::*;
 impl NetworkNode {
     pub async fn handle_management_command(&self, cmd: ServerManagementCommand) {
         use ServerManagementCommandType::*;
-        let resp = match cmd.cmd {
-            ServerStatistics => self.export_server_statistics(),
+        let ServerManagementCommand { cmd, response } = cmd;
+        tracing::debug!(?cmd, "Handling management command");
+        let resp = match cmd {
+            ServerStatistics(format) => Ok(self.server_statistics().render(format)),
             DumpNetwork => self.dump_network_state(),
             DumpEvents => self.dump_events(),
+            ListPeers => self.list_peers(),
+            AddPeer(peer) => self.add_peer(peer),
+            RemovePeer(name) => self.remove_peer(name),
+            StateDivergence => self.list_state_divergence(),
+            RoutingTable => self.routing_table(),
+            ShowNetworkConfig => self.show_network_config(),
+            ValidateNetworkConfig(config) => self.validate_network_config(&config),
+            DiffNetworkConfig(config) => self.diff_network_config(&config),
+            LoadNetworkConfig(request) => self.load_network_config(request),
+            // The certificates belong to the server application rather than the node
+            ReloadTls => management_error(
+                ManagementErrorKind::Unsupported,
+                "TLS reload is handled by the server",
+            ),
         };
-        tracing::debug!(?cmd.cmd, ?resp, "Handled management command");
-        let _ = cmd.response.send(resp);
+        tracing::debug!(?resp, "Handled management command");
+        let _ = response.send(resp);
     }
 
-    fn export_server_statistics(&self) -> String {
-        unimplemented!("server stats");
-        /*
-                let stats = ServerStatistics {
-                    connected_clients: self.connections.len(),
-                    event_stats: self.server.event_log().get_stats(),
-                };
+    fn list_peers(&self) -> ManagementResponse {
+        Ok(serde_json::to_string(&self.sync_log().peers()).expect("Failed to serialise peers"))
+    }
+
+    fn add_peer(&self, peer: PeerConfig) -> ManagementResponse {
+        if peer.name() == self.name() {
+            return management_error(
+                ManagementErrorKind::BadRequest,
+                "Can't add this server as its own peer",
+            );
+        }
+        if peer.tls_server_name().is_none() {
+            return management_error(
+                ManagementErrorKind::BadRequest,
+                "Peer name must be usable as a TLS server name",
+            );
+        }
+        if peer.address().is_empty() {
+            return management_error(
+                ManagementErrorKind::BadRequest,
+                "Peer address must not be empty",
+            );
+        }
+        if peer.fingerprint().len() != 40
+            || !peer
+                .fingerprint()
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+        {
+            return management_error(
+                ManagementErrorKind::BadRequest,
+                "Fingerprint must be a lower-case hex SHA-1 digest",
+            );
+        }
+
+        let id = SyncPeerId::new(*peer.name());
+        self.submit_event(
+            id,
+            details::SyncPeerUpdate {
+                data: Some(state::SyncPeer {
+                    id,
+                    address: peer.address().to_owned(),
+                    fingerprint: peer.fingerprint().to_owned(),
+                }),
+            },
+        );
+
+        Ok("{}".to_string())
+    }
+
+    fn remove_peer(&self, name: ServerName) -> ManagementResponse {
+        if &name == self.name() {
+            return management_error(
+                ManagementErrorKind::BadRequest,
+                "Can't remove this server from the network",
+            );
+        }
+        if !self.sync_log().peers().iter().any(|p| p.name() == &name) {
+            return management_error(ManagementErrorKind::NotFound, "No such peer");
+        }
+
+        self.submit_event(
+            SyncPeerId::new(name),
+            details::SyncPeerUpdate { data: None },
+        );
+
+        Ok("{}".to_string())
+    }
+
+    fn routing_table(&self) -> ManagementResponse {
+        Ok(serde_json::to_string(&self.sync_log().routing_table())
+            .expect("Failed to serialise routing table"))
+    }
+
+    fn show_network_config(&self) -> ManagementResponse {
+        Ok(serde_json::to_string(self.network().config())
+            .expect("Failed to serialise network config"))
+    }
+
+    fn validate_network_config(
+        &self,
+        config: &crate::network::config::NetworkConfig,
+    ) -> ManagementResponse {
+        // An invalid config is a successful answer to the question of whether it's valid
+        Ok(match self.network().validate_config(config) {
+            Ok(()) => serde_json::json!({ "valid": true }).to_string(),
+            Err(errors) => serde_json::json!({
+                "valid": false,
+                "errors": errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            })
+            .to_string(),
+        })
+    }
 
-                serde_json::to_string(&stats).expect("Failed to serialise statistics")
-        */
+    fn diff_network_config(
+        &self,
+        config: &crate::network::config::NetworkConfig,
+    ) -> ManagementResponse {
+        let changes = self.network().config().diff(config);
+        Ok(serde_json::json!({ "changes": changes }).to_string())
     }
+
+    fn load_network_config(&self, request: LoadNetworkConfig) -> ManagementResponse {
+        let changes = {
+            let net = self.network();
+
+            if let Err(errors) = net.validate_config(&request.config) {
+                return Err(ManagementError {
+                    kind: ManagementErrorKind::BadRequest,
+                    body: serde_json::json!({
+                        "error": "Invalid network config",
+                        "errors": errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                    }),
+                });
+            }
+
+            net.config().diff(&request.config)
+        };
+
+        if changes.is_empty() {
+            return Ok(serde_json::json!({ "changes": changes }).to_string());
+        }
+
+        let summary = changes
+            .iter()
+            .map(|c| c.path.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        AuditLoggerEntry::management(
+            self,
+            state::AuditLogCategory::NetworkConfig,
+            &request.client,
+            request.client_addr,
+            "LOADCONFIG".to_string(),
+        )
+        .target_str(summary)
+        .log();
+
+        // There is only one network config, so the target ID doesn't identify anything
+        self.submit_event(
+            ConfigId::new(0),
+            details::LoadConfig {
+                config: *request.config,
+            },
+        );
+
+        Ok(serde_json::json!({ "changes": changes }).to_string())
+    }
+
+    fn list_state_divergence(&self) -> ManagementResponse {
+        Ok(serde_json::to_string(&self.state_divergence())
+            .expect("Failed to serialise divergence reports"))
+    }
+}
+
+fn management_error(kind: ManagementErrorKind, message: &str) -> ManagementResponse {
+    Err(ManagementError::new(kind, message))
 }
 
 #[cfg(feature = "debug")]
 impl NetworkNode {
-    fn dump_network_state(&self) -> String {
-        serde_json::to_string(&*self.network()).expect("Failed to serialise network")
+    fn dump_network_state(&self) -> ManagementResponse {
+        Ok(serde_json::to_string(&*self.network()).expect("Failed to serialise network"))
     }
 
-    fn dump_events(&self) -> String {
+    fn dump_events(&self) -> ManagementResponse {
         let log = self.event_log();
         let events = log.all_events().collect::<Vec<_>>();
-        serde_json::to_string(&events).expect("Failed to serialise events")
+        Ok(serde_json::to_string(&events).expect("Failed to serialise events"))
     }
 }
 
 #[cfg(not(feature = "debug"))]
 impl NetworkNode {
-    fn dump_network_state(&self) -> String {
-        "{\"error\": \"Debug functionality not enabled\"}".to_string()
+    fn dump_network_state(&self) -> ManagementResponse {
+        management_error(
+            ManagementErrorKind::Unsupported,
+            "Debug functionality not enabled",
+        )
     }
-    fn dump_events(&self) -> String {
-        "{\"error\": \"Debug functionality not enabled\"}".to_string()
+    fn dump_events(&self) -> ManagementResponse {
+        management_error(
+            ManagementErrorKind::Unsupported,
+            "Debug functionality not enabled",
+        )
     }
 }
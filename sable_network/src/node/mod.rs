@@ -20,11 +20,16 @@ use tokio::{
     time,
 };
 
-use std::{sync::Arc, time::Duration};
+use std::{collections::VecDeque, sync::Arc, time::Duration};
 
 use parking_lot::RwLock;
 
 mod pings;
+mod state_digest;
+pub use state_digest::StateDivergence;
+use state_digest::{DigestPurpose, DigestTasks};
+mod statistics;
+pub use statistics::{NetworkStatistics, ServerStatistics};
 mod update_receiver;
 
 mod upgrade;
@@ -51,6 +56,8 @@ where
     subscriber: UnboundedSender<NetworkHistoryUpdate>,
     remote_server_commands: Option<UnboundedSender<RemoteServerRequest>>,
     policy_service: Policy,
+    state_digest: parking_lot::Mutex<Option<StateDigest>>,
+    divergence_reports: RwLock<VecDeque<StateDivergence>>,
 }
 
 impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
@@ -100,6 +107,8 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
             subscriber,
             remote_server_commands,
             policy_service,
+            state_digest: parking_lot::Mutex::new(None),
+            divergence_reports: RwLock::new(VecDeque::new()),
         }
     }
 
@@ -239,14 +248,40 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
         self.history_log.write().expire_entries(min_ts);
     }
 
+    /// Write a snapshot of the current network state and event log to disk, if enabled
+    pub fn write_snapshot(&self) {
+        let Some(config) = self.event_log.snapshot_config().cloned() else {
+            return;
+        };
+
+        let network = Arc::clone(&*self.net.read());
+        let events = self.event_log.snapshot_events();
+        let name = self.name;
+
+        tokio::task::spawn_blocking(move || {
+            match config.write(name, crate::utils::now(), &network, &events) {
+                Ok(path) => tracing::debug!("Wrote snapshot {}", path.display()),
+                Err(e) => tracing::error!("Error writing snapshot: {}", e),
+            }
+        });
+    }
+
     #[tracing::instrument(skip(self))]
-    fn apply_event(&self, event: Event) {
+    fn apply_event(&self, event: Event, digests: &mut DigestTasks) {
         tracing::trace!("Applying inbound event");
 
         // We need to queue up the emitted updates and process them after `apply()` returns and we've released
         // the write lock on `net`. The handlers for various network updates require read access to `net`.
         let mut update_queue = crate::network::SavedUpdateReceiver::new();
 
+        if let EventDetails::ServerPing(details::ServerPing {
+            digest: Some(digest),
+            ..
+        }) = &event.details
+        {
+            self.check_state_digest(&event, digest, digests);
+        }
+
         Arc::make_mut(&mut *self.net.write())
             .apply(&event, &update_queue)
             .unwrap_or_else(|_| panic!("Event {event:?} failed to apply"));
@@ -272,8 +307,17 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
 
         let mut check_ping_timer = time::interval(Duration::from_secs(60));
         let mut expire_objects_timer = time::interval(Duration::from_secs(60));
+        let snapshot_interval = self
+            .event_log
+            .snapshot_config()
+            .map(|config| config.interval.max(1))
+            .unwrap_or(3600);
+        let mut snapshot_timer = time::interval(Duration::from_secs(snapshot_interval));
+        // Don't write a snapshot immediately on startup, before we've caught up
+        snapshot_timer.tick().await;
 
         let mut rpc_receiver = self.rpc_receiver.lock().await;
+        let mut digests = DigestTasks::new();
 
         let shutdown_action = loop {
             tracing::trace!("server run loop");
@@ -285,7 +329,7 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
                     match res {
                         Some(NetworkMessage::NewEvent(event)) =>
                         {
-                            self.apply_event(event);
+                            self.apply_event(event, &mut digests);
                         },
                         Some(NetworkMessage::ImportNetworkState(new_net)) =>
                         {
@@ -320,13 +364,26 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
                 _ = check_ping_timer.tick() =>
                 {
                     tracing::trace!("...from check_ping_timer");
-                    self.check_pings();
+                    self.check_pings(&mut digests);
                 },
                 _ = expire_objects_timer.tick() =>
                 {
                     tracing::trace!("...from expire_objects_timer");
                     self.expire_old_entries();
                 }
+                Some(res) = digests.join_next(), if !digests.is_empty() =>
+                {
+                    tracing::trace!("...from digests");
+                    match res {
+                        Ok((purpose, digest)) => self.state_digest_ready(purpose, digest),
+                        Err(e) => tracing::error!("Error computing state digest: {}", e),
+                    }
+                }
+                _ = snapshot_timer.tick() =>
+                {
+                    tracing::trace!("...from snapshot_timer");
+                    self.write_snapshot();
+                }
                 shutdown = shutdown_channel.recv() =>
                 {
                     match shutdown
@@ -347,7 +404,8 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
                         {
                             // If we're upgrading, then don't signal to the network that we're shutting down.
                             // The actual state save/restore will be called by main() after everything's stopped
-                            // processing.
+                            // processing, which includes releasing the snapshots held by digest tasks.
+                            while digests.join_next().await.is_some() {}
                             return ShutdownAction::Upgrade;
                         }
                     }
@@ -5,11 +5,11 @@ use super::*;
 const PINGOUT_DURATION: i64 = 240;
 
 impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
-    pub(super) fn check_pings(&self) {
+    pub(super) fn check_pings(&self, digests: &mut DigestTasks) {
         let now = utils::now();
 
-        let ping_detail = details::ServerPing { ts: now };
-        self.submit_event(self.my_id, ping_detail);
+        // The ping is sent once its digest is ready
+        self.start_state_digest(DigestPurpose::Ping(now), digests);
 
         for server in self.net.read().servers() {
             let last_ping = server.last_ping();
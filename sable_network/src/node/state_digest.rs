@@ -0,0 +1,130 @@
+use super::*;
+
+/// The number of divergence reports kept for the management service
+const MAX_DIVERGENCE_REPORTS: usize = 100;
+
+/// A report that another server's network state differed from ours at the same
+/// event clock
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StateDivergence {
+    pub server: ServerId,
+    pub server_name: Option<ServerName>,
+    pub detected_at: i64,
+    pub clock: EventClock,
+    /// The categories of state object which differed
+    pub categories: Vec<String>,
+}
+
+/// Why a state digest is being computed
+pub(super) enum DigestPurpose {
+    /// To send in a server ping with the given timestamp
+    Ping(i64),
+    /// To compare with the digest sent in a ping by the given server
+    Compare(ServerId, StateDigest),
+}
+
+/// Digests being computed in the background, to be handled by the run loop as they
+/// complete
+pub(super) type DigestTasks = tokio::task::JoinSet<(DigestPurpose, StateDigest)>;
+
+impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
+    /// Compute a digest of the current network state, reusing the previous one if
+    /// no events have been applied since.
+    ///
+    /// Hashing the whole state is slow, so a new digest is computed from a snapshot in
+    /// a blocking task, and passed to [`state_digest_ready`](Self::state_digest_ready)
+    /// by the run loop once it completes.
+    pub(super) fn start_state_digest(&self, purpose: DigestPurpose, tasks: &mut DigestTasks) {
+        let net = Arc::clone(&*self.net.read());
+
+        let cached = self
+            .state_digest
+            .lock()
+            .as_ref()
+            .filter(|d| &d.clock == net.clock())
+            .cloned();
+
+        match cached {
+            Some(digest) => self.state_digest_ready(purpose, digest),
+            None => {
+                tasks.spawn_blocking(move || (purpose, net.state_digest()));
+            }
+        }
+    }
+
+    /// Handle a digest started by [`start_state_digest`](Self::start_state_digest)
+    pub(super) fn state_digest_ready(&self, purpose: DigestPurpose, digest: StateDigest) {
+        *self.state_digest.lock() = Some(digest.clone());
+
+        match purpose {
+            DigestPurpose::Ping(ts) => {
+                self.submit_event(
+                    self.my_id,
+                    details::ServerPing {
+                        ts,
+                        digest: Some(digest),
+                    },
+                );
+            }
+            DigestPurpose::Compare(source, remote) => {
+                self.compare_state_digest(source, &digest, &remote)
+            }
+        }
+    }
+
+    /// Compare the digest sent in another server's ping with our own state. This can
+    /// only be done if we're at exactly the same point in the event stream.
+    pub(super) fn check_state_digest(
+        &self,
+        event: &Event,
+        remote: &StateDigest,
+        tasks: &mut DigestTasks,
+    ) {
+        let source = event.id.server();
+
+        if source == self.my_id || &remote.clock != self.network().clock() {
+            return;
+        }
+
+        self.start_state_digest(DigestPurpose::Compare(source, remote.clone()), tasks);
+    }
+
+    fn compare_state_digest(&self, source: ServerId, local: &StateDigest, remote: &StateDigest) {
+        let categories: Vec<String> = local
+            .differences(remote)
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect();
+
+        if categories.is_empty() {
+            return;
+        }
+
+        let server_name = self.network().server(source).ok().map(|s| *s.name());
+
+        tracing::error!(
+            ?source,
+            ?server_name,
+            clock = ?remote.clock,
+            ?categories,
+            "Network state has diverged from another server"
+        );
+
+        let mut reports = self.divergence_reports.write();
+        if reports.len() >= MAX_DIVERGENCE_REPORTS {
+            reports.pop_front();
+        }
+        reports.push_back(StateDivergence {
+            server: source,
+            server_name,
+            detected_at: crate::utils::now(),
+            clock: remote.clock.clone(),
+            categories,
+        });
+    }
+
+    /// Recent reports of other servers whose state differs from ours, oldest first
+    pub fn state_divergence(&self) -> Vec<StateDivergence> {
+        self.divergence_reports.read().iter().cloned().collect()
+    }
+}
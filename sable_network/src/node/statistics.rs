@@ -0,0 +1,212 @@
+use super::*;
+use crate::rpc::StatisticsFormat;
+use crate::sync::{EventLogStats, PeerStatistics, RemoteRequestStats};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Counts of objects in the network state
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NetworkStatistics {
+    pub users: usize,
+    pub channels: usize,
+    pub servers: usize,
+    pub accounts: usize,
+}
+
+/// Statistics about a running server, as reported by the management service
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ServerStatistics {
+    pub server: ServerName,
+    pub version: String,
+    pub event_log: EventLogStats,
+    pub network: NetworkStatistics,
+    pub peers: Vec<PeerStatistics>,
+    pub remote_requests: BTreeMap<ServerName, RemoteRequestStats>,
+    /// Statistics specific to the type of server, such as the number of connected clients
+    pub server_type: BTreeMap<String, u64>,
+}
+
+impl ServerStatistics {
+    /// Render the statistics in the requested format
+    pub fn render(&self, format: StatisticsFormat) -> String {
+        match format {
+            StatisticsFormat::Json => {
+                serde_json::to_string(self).expect("Failed to serialise statistics")
+            }
+            StatisticsFormat::Prometheus => self.to_prometheus(),
+        }
+    }
+
+    /// Render the statistics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, f64)]| {
+            let _ = writeln!(out, "# HELP sable_{name} {help}");
+            let _ = writeln!(out, "# TYPE sable_{name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "sable_{name}{labels} {value}");
+            }
+        };
+
+        metric(
+            "info",
+            "gauge",
+            "Server name and version",
+            &[(
+                format!(
+                    "{{server=\"{}\",version=\"{}\"}}",
+                    self.server,
+                    escape_label(&self.version)
+                ),
+                1.0,
+            )],
+        );
+
+        metric(
+            "event_log_events",
+            "gauge",
+            "Events held in the event log",
+            &[(String::new(), self.event_log.events as f64)],
+        );
+        metric(
+            "event_log_pending_events",
+            "gauge",
+            "Events waiting for their dependencies",
+            &[(String::new(), self.event_log.pending_events as f64)],
+        );
+
+        metric(
+            "network_users",
+            "gauge",
+            "Users on the network",
+            &[(String::new(), self.network.users as f64)],
+        );
+        metric(
+            "network_channels",
+            "gauge",
+            "Channels on the network",
+            &[(String::new(), self.network.channels as f64)],
+        );
+        metric(
+            "network_servers",
+            "gauge",
+            "Servers on the network",
+            &[(String::new(), self.network.servers as f64)],
+        );
+        metric(
+            "network_accounts",
+            "gauge",
+            "Registered accounts",
+            &[(String::new(), self.network.accounts as f64)],
+        );
+
+        let peer_samples = |f: &dyn Fn(&PeerStatistics) -> f64| -> Vec<(String, f64)> {
+            self.peers
+                .iter()
+                .map(|p| (format!("{{peer=\"{}\"}}", p.name), f(p)))
+                .collect()
+        };
+        metric(
+            "sync_peer_enabled",
+            "gauge",
+            "Whether the sync peer is enabled",
+            &peer_samples(&|p| p.enabled as u8 as f64),
+        );
+        metric(
+            "sync_peer_connected",
+            "gauge",
+            "Whether a connection to the sync peer is established",
+            &peer_samples(&|p| p.connected as u8 as f64),
+        );
+        metric(
+            "sync_peer_messages_sent_total",
+            "counter",
+            "Messages sent to the sync peer",
+            &peer_samples(&|p| p.messages_sent as f64),
+        );
+        metric(
+            "sync_peer_send_failures_total",
+            "counter",
+            "Messages which couldn't be sent to the sync peer",
+            &peer_samples(&|p| p.send_failures as f64),
+        );
+
+        let request_samples = |f: &dyn Fn(&RemoteRequestStats) -> f64| -> Vec<(String, f64)> {
+            self.remote_requests
+                .iter()
+                .map(|(target, stats)| (format!("{{target=\"{}\"}}", target), f(stats)))
+                .collect()
+        };
+        metric(
+            "remote_request_failures_total",
+            "counter",
+            "Remote server requests which failed",
+            &request_samples(&|s| s.failures as f64),
+        );
+        metric(
+            "remote_request_timeouts_total",
+            "counter",
+            "Remote server requests which timed out",
+            &request_samples(&|s| s.timeouts as f64),
+        );
+        metric(
+            "remote_request_duration_seconds_max",
+            "gauge",
+            "Longest time taken by a remote server request",
+            &request_samples(&|s| s.latency_seconds_max),
+        );
+
+        let mut durations = request_samples(&|s| s.latency_seconds_sum)
+            .into_iter()
+            .map(|(labels, value)| (format!("_sum{labels}"), value))
+            .collect::<Vec<_>>();
+        durations.extend(
+            request_samples(&|s| s.requests as f64)
+                .into_iter()
+                .map(|(labels, value)| (format!("_count{labels}"), value)),
+        );
+        metric(
+            "remote_request_duration_seconds",
+            "summary",
+            "Time taken by remote server requests",
+            &durations,
+        );
+
+        for (name, value) in &self.server_type {
+            metric(name, "gauge", name, &[(String::new(), *value as f64)]);
+        }
+
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl NetworkNode {
+    /// Collect statistics about this node. Statistics specific to the server type
+    /// are left empty, to be filled in by the caller.
+    pub fn server_statistics(&self) -> ServerStatistics {
+        let net = self.network();
+
+        ServerStatistics {
+            server: self.name,
+            version: self.version.clone(),
+            event_log: self.event_log().get_stats(),
+            network: NetworkStatistics {
+                users: net.users().count(),
+                channels: net.channels().count(),
+                servers: net.servers().count(),
+                accounts: net.accounts().count(),
+            },
+            peers: self.event_log.peer_statistics(),
+            remote_requests: self.event_log.remote_request_statistics(),
+            server_type: BTreeMap::new(),
+        }
+    }
+}
@@ -225,6 +225,21 @@ impl<Policy: crate::policy::PolicyService> NetworkNode<Policy> {
         Ok(Vec::new())
     }
 
+    #[tracing::instrument(skip(self))]
+    fn handle_sync_peer_change(&self, detail: &update::SyncPeerChange) -> HandleResult {
+        tracing::trace!("Got sync peer change");
+
+        let name = detail.peer.name();
+        // If the server is already part of the network, there won't be a NewServer
+        // to enable it
+        let enabled = self.network().servers().any(|s| s.name() == name);
+
+        self.sync_log()
+            .update_peer(name, detail.data.as_ref().map(Into::into), enabled);
+
+        Ok(Vec::new())
+    }
+
     fn report_audit_entry(&self, _detail: &update::NewAuditLogEntry) -> HandleResult {
         Ok(Vec::new())
     }
@@ -286,6 +301,7 @@ impl<Policy: crate::policy::PolicyService> NetworkUpdateReceiver for NetworkNode
             NewMessage(detail) => self.handle_new_message(detail),
             NewServer(detail) => self.handle_new_server(detail),
             ServerQuit(detail) => self.handle_server_quit(detail),
+            SyncPeerChange(detail) => self.handle_sync_peer_change(detail),
             NewAuditLogEntry(detail) => self.report_audit_entry(detail),
             UserLoginChange(detail) => self.handle_user_login(detail),
             HistoryServerUpdate(detail) => self.handle_history_server_update(detail),
@@ -54,6 +54,8 @@ impl<Policy: PolicyService + Saveable> NetworkNode<Policy> {
             subscriber,
             policy_service: Policy::restore(state.policy_state),
             remote_server_commands,
+            state_digest: parking_lot::Mutex::new(None),
+            divergence_reports: RwLock::new(VecDeque::new()),
         })
     }
 }
@@ -7,6 +7,10 @@ pub trait ChannelPolicyService {
     fn can_join(&self, user: &User, channel: &Channel, key: Option<ChannelKey>)
         -> PermissionResult;
 
+    /// Find an auto-kick entry on the given channel's registration which matches the given
+    /// user. Users with `InviteSelf` access to the channel are exempt.
+    fn auto_kick_for(&self, user: &User, channel: &Channel) -> Option<ChannelAutoKickId>;
+
     /// Determine whether the given user can kick the other given user to the given channel
     fn can_kick(
         &self,
@@ -45,6 +45,8 @@ pub enum RegistrationPermissionError {
     NoAccess,
     /// Attempted to grant or edit a role with more access the user doesn't have
     CantEditHigherRole,
+    /// The given mask is empty or would match every user
+    InvalidMask,
 }
 
 #[derive(Debug)]
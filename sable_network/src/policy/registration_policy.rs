@@ -43,6 +43,56 @@ pub trait RegistrationPolicyService {
         role: &wrapper::ChannelRole,
     ) -> PermissionResult;
 
+    /// Determine whether the given user can see the private details (last login time and
+    /// public fields) of an account
+    fn can_view_account_details(&self, source: &wrapper::User, account: &wrapper::Account) -> bool;
+
+    /// Determine whether the given user can see the certificate fingerprints authorised
+    /// to log in to an account
+    fn can_view_account_fingerprints(
+        &self,
+        source: &wrapper::User,
+        account: &wrapper::Account,
+    ) -> bool;
+
+    /// Determine whether the given user can see the settings of a registered channel
+    fn can_view_channel_settings(
+        &self,
+        source: &wrapper::User,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult;
+
+    /// Determine whether the given user can offer ownership of a channel to another account
+    fn can_transfer_channel(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult;
+
+    /// Determine whether the given user can drop a channel registration
+    fn can_drop_channel(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult;
+
+    /// Determine whether the given user can change the settings of a registered channel
+    fn can_change_channel_settings(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult;
+
+    /// Determine whether the given user can view or edit the auto-kick list for a channel
+    fn can_manage_auto_kicks(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult;
+
+    /// Determine whether the given mask is acceptable for an auto-kick entry
+    fn validate_auto_kick_mask(&self, mask: &str) -> PermissionResult;
+
     /// Determine whether the given user can create/edit a role with the given flags
     fn can_create_role(
         &self,
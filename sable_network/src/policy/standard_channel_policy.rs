@@ -71,6 +71,10 @@ impl ChannelPolicyService for StandardChannelPolicy {
         channel: &Channel,
         key: Option<ChannelKey>,
     ) -> PermissionResult {
+        if self.auto_kick_for(user, channel).is_some() {
+            return Err(PermissionError::Channel(*channel.name(), UserIsBanned));
+        }
+
         if has_access(user, channel, ChannelAccessFlag::InviteSelf).is_ok() {
             return Ok(());
         }
@@ -105,6 +109,27 @@ impl ChannelPolicyService for StandardChannelPolicy {
         Ok(())
     }
 
+    fn auto_kick_for(&self, user: &User, channel: &Channel) -> Option<ChannelAutoKickId> {
+        let registration = channel.is_registered()?;
+
+        // Users who may invite themselves are exempt. Ephemeral (+o/+v) access is
+        // deliberately not considered: it isn't held when joining, and whoever
+        // recreates an empty channel is opped, so counting it here would let
+        // enforcement disagree with `can_join`.
+        let exempt = |flags: Option<state::ChannelAccessSet>| {
+            flags.is_some_and(|f| f.is_set(ChannelAccessFlag::InviteSelf))
+        };
+        if exempt(has_assigned_access(user, channel)) || exempt(has_default_access(channel)) {
+            return None;
+        }
+
+        let found = registration
+            .auto_kicks()
+            .find(|entry| self.ban_resolver.user_matches_auto_kick(user, entry))
+            .map(|entry| entry.id());
+        found
+    }
+
     fn can_kick(
         &self,
         user: &User,
@@ -141,6 +141,119 @@ impl RegistrationPolicyService for StandardRegistrationPolicy {
         Ok(())
     }
 
+    fn can_view_account_details(&self, source: &wrapper::User, account: &wrapper::Account) -> bool {
+        if !account.is_private() || source.is_oper() {
+            return true;
+        }
+
+        matches!(source.account(), Ok(Some(source_account)) if source_account.id() == account.id())
+    }
+
+    fn can_view_account_fingerprints(
+        &self,
+        source: &wrapper::User,
+        account: &wrapper::Account,
+    ) -> bool {
+        source.is_oper()
+            || matches!(source.account(), Ok(Some(source_account)) if source_account.id() == account.id())
+    }
+
+    fn can_view_channel_settings(
+        &self,
+        source: &wrapper::User,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult {
+        let source_account = source
+            .account()?
+            .ok_or(RegistrationPermissionError::NotLoggedIn)?;
+
+        let source_access = source_account
+            .has_access_in(channel.id())
+            .ok_or(RegistrationPermissionError::NoAccess)?;
+
+        let flags = source_access.role()?.flags();
+        if !flags.is_set(ChannelAccessFlag::Set) && !flags.is_set(ChannelAccessFlag::AccessView) {
+            return Err(RegistrationPermissionError::NoAccess.into());
+        }
+
+        Ok(())
+    }
+
+    fn can_transfer_channel(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult {
+        let source_access = source
+            .has_access_in(channel.id())
+            .ok_or(RegistrationPermissionError::NoAccess)?;
+
+        if !source_access
+            .role()?
+            .flags()
+            .is_set(ChannelAccessFlag::Founder)
+        {
+            return Err(RegistrationPermissionError::NoAccess.into());
+        }
+
+        Ok(())
+    }
+
+    fn can_drop_channel(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult {
+        // Only founders may drop a channel, the same as for transferring it
+        self.can_transfer_channel(source, channel)
+    }
+
+    fn can_change_channel_settings(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult {
+        let source_access = source
+            .has_access_in(channel.id())
+            .ok_or(RegistrationPermissionError::NoAccess)?;
+
+        if !source_access.role()?.flags().is_set(ChannelAccessFlag::Set) {
+            return Err(RegistrationPermissionError::NoAccess.into());
+        }
+
+        Ok(())
+    }
+
+    fn can_manage_auto_kicks(
+        &self,
+        source: &wrapper::Account,
+        channel: &wrapper::ChannelRegistration,
+    ) -> PermissionResult {
+        let source_access = source
+            .has_access_in(channel.id())
+            .ok_or(RegistrationPermissionError::NoAccess)?;
+
+        if !source_access
+            .role()?
+            .flags()
+            .is_set(ChannelAccessFlag::Akick)
+        {
+            return Err(RegistrationPermissionError::NoAccess.into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_auto_kick_mask(&self, mask: &str) -> PermissionResult {
+        // Nothing but wildcards and separators (including an empty mask) matches everyone,
+        // which would make the channel unjoinable
+        if mask.chars().all(|c| matches!(c, '*' | '?' | '!' | '@')) {
+            return Err(RegistrationPermissionError::InvalidMask.into());
+        }
+
+        Ok(())
+    }
+
     fn can_create_role(
         &self,
         source: &wrapper::Account,
@@ -1,17 +1,91 @@
+use crate::network::config::NetworkConfig;
+use crate::sync::PeerConfig;
+use crate::validated::ServerName;
 use tokio::sync::oneshot::Sender;
 
 /// A management command
 pub struct ServerManagementCommand {
     pub cmd: ServerManagementCommandType,
-    pub response: Sender<String>,
+    pub response: Sender<ManagementResponse>,
+}
+
+/// The body of a successful management command, or the reason it failed
+pub type ManagementResponse = Result<String, ManagementError>;
+
+/// A management command which couldn't be carried out
+#[derive(Debug)]
+pub struct ManagementError {
+    pub kind: ManagementErrorKind,
+    /// A JSON object describing the error
+    pub body: serde_json::Value,
+}
+
+/// Why a management command failed, used to choose the response status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagementErrorKind {
+    /// The request was malformed or invalid
+    BadRequest,
+    /// The request referred to something which doesn't exist
+    NotFound,
+    /// The request isn't supported by this server
+    Unsupported,
+    /// The request was valid, but carrying it out failed
+    Internal,
+}
+
+impl ManagementError {
+    pub fn new(kind: ManagementErrorKind, message: impl ToString) -> Self {
+        Self {
+            kind,
+            body: serde_json::json!({ "error": message.to_string() }),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum ServerManagementCommandType {
     /// Collect server statistics
-    ServerStatistics,
+    ServerStatistics(StatisticsFormat),
     /// Dump network state (for debugging)
     DumpNetwork,
     /// Dump event log (for debugging)
     DumpEvents,
+    /// List the peers in the sync network
+    ListPeers,
+    /// Add a peer to the sync network, or update an existing one
+    AddPeer(PeerConfig),
+    /// Remove a peer from the sync network
+    RemovePeer(ServerName),
+    /// List recent reports of other servers whose network state differs from ours
+    StateDivergence,
+    /// Show the routes known for targeted messages
+    RoutingTable,
+    /// Show the current network configuration
+    ShowNetworkConfig,
+    /// Check whether a network configuration could be loaded
+    ValidateNetworkConfig(Box<NetworkConfig>),
+    /// List the changes a new network configuration would make
+    DiffNetworkConfig(Box<NetworkConfig>),
+    /// Load a new network configuration across the network
+    LoadNetworkConfig(LoadNetworkConfig),
+    /// Reread this server's TLS certificates from disk and start using them
+    ReloadTls,
+}
+
+/// A request to replace the network configuration
+#[derive(Debug)]
+pub struct LoadNetworkConfig {
+    pub config: Box<NetworkConfig>,
+    /// The name of the management client which requested the change
+    pub client: String,
+    pub client_addr: Option<std::net::IpAddr>,
+}
+
+/// Output formats for server statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatisticsFormat {
+    /// A JSON object
+    Json,
+    /// The Prometheus text exposition format
+    Prometheus,
 }
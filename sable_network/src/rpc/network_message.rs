@@ -3,7 +3,12 @@ use std::num::NonZeroUsize;
 use crate::{
     history::{HistoricalEvent, HistoryError, HistoryRequest},
     id::*,
-    network::{event::*, state::ChannelAccessSet, Network},
+    network::{
+        event::*,
+        state::{ChannelAccessSet, ChannelModeLock, PublicFieldError},
+        Network,
+    },
+    types::Pattern,
     validated::*,
 };
 use tokio::sync::{mpsc::Sender, oneshot};
@@ -95,12 +100,65 @@ pub enum RemoteServicesServerRequestType {
         id: ChannelRoleId,
         flags: Option<ChannelAccessSet>,
     },
+    /// Change one of the settings of a registered channel
+    SetChannelSetting {
+        source: AccountId,
+        channel: ChannelRegistrationId,
+        setting: ChannelSetting,
+    },
+    /// Offer ownership of a registered channel to another account
+    TransferChannel {
+        source: AccountId,
+        channel: ChannelRegistrationId,
+        target: AccountId,
+    },
+    /// Accept a pending ownership transfer
+    AcceptChannelTransfer {
+        source: AccountId,
+        channel: ChannelRegistrationId,
+    },
+    /// Drop a channel registration
+    DropChannel {
+        source: AccountId,
+        channel: ChannelRegistrationId,
+    },
+    /// Add an entry to a channel's auto-kick list
+    AddAutoKick {
+        source: AccountId,
+        channel: ChannelRegistrationId,
+        mask: Pattern,
+        reason: Option<String>,
+    },
+    /// Remove an entry from a channel's auto-kick list
+    RemoveAutoKick {
+        source: AccountId,
+        id: ChannelAutoKickId,
+    },
+    /// Set or clear one of an account's public information fields
+    SetAccountField {
+        account: AccountId,
+        name: String,
+        value: Option<String>,
+    },
+    /// Change whether an account's information is hidden from other users
+    SetAccountPrivate(AccountId, bool),
     /// Add an authorised fingerprint to an account
     AddAccountFingerprint(AccountId, String),
     /// Remove an authorised fingerprint from an account
     RemoveAccountFingerprint(AccountId, String),
 }
 
+/// A setting on a registered channel which can be changed by the channel's users
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum ChannelSetting {
+    /// Set (or clear, if empty) the channel's mode lock
+    ModeLock(ChannelModeLock),
+    /// Enable or disable topic retention
+    KeepTopic(bool),
+    /// Set or clear the message sent to users joining the channel
+    EntryMessage(Option<String>),
+}
+
 /// A message to be handled by a services node
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum RemoteHistoryServerRequestType {
@@ -175,6 +233,10 @@ pub enum RemoteServicesServerResponse {
     NoAccount,
     /// Channel isn't registered
     ChannelNotRegistered,
+    /// No channel transfer has been offered to the user
+    NoPendingTransfer,
+    /// The requested change to an account's public fields isn't allowed
+    InvalidAccountField(PublicFieldError),
 }
 
 /// Remote history server response type
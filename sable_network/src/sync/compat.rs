@@ -0,0 +1,150 @@
+//! Compatibility with peers speaking older protocol versions
+//!
+//! Messages are always built in the newest format, and downgraded just before
+//! being encoded for a connection that negotiated an older version. Anything an
+//! older peer couldn't decode is either replaced with its nearest older equivalent
+//! or, for events, with a [placeholder](is_placeholder).
+
+use super::message::MessageDetail;
+use crate::network::event::*;
+use crate::network::state::AuditLogCategory;
+use crate::prelude::*;
+use crate::rpc::{RemoteServerResponse, RemoteServicesServerResponse};
+
+/// Placeholder events target this server ID, which no real server may use
+pub(crate) const PLACEHOLDER_SERVER: ServerId = ServerId::new(u16::MAX);
+
+/// Rewrite `content` so that a peer speaking protocol `version` can decode it
+pub(super) fn downgrade(content: MessageDetail, version: u32) -> MessageDetail {
+    if version >= 2 {
+        return content;
+    }
+
+    match content {
+        MessageDetail::NewEvent(event) => MessageDetail::NewEvent(downgrade_event(event)),
+        MessageDetail::BulkEvents(events) => {
+            MessageDetail::BulkEvents(events.into_iter().map(downgrade_event).collect())
+        }
+        MessageDetail::NetworkState(mut net) => {
+            net.map_audit_categories(downgrade_audit_category);
+            MessageDetail::NetworkState(net)
+        }
+        // Version 1 nodes answer with whatever events they still hold, so the
+        // requester is left to notice the gap itself
+        MessageDetail::SyncUnavailable => MessageDetail::BulkEvents(Vec::new()),
+        MessageDetail::TargetedMessageResponse(RemoteServerResponse::Services(
+            RemoteServicesServerResponse::InvalidAccountField(e),
+        )) => MessageDetail::TargetedMessageResponse(RemoteServerResponse::Error(e.to_string())),
+        other => other,
+    }
+}
+
+fn downgrade_event(mut event: Event) -> Event {
+    match &mut event.details {
+        EventDetails::SyncPeerUpdate(_)
+        | EventDetails::ChannelAutoKickUpdate(_)
+        | EventDetails::ServerChannelKick(_) => placeholder_for(event),
+        EventDetails::NewAuditLogEntry(detail) => {
+            detail.entry.category = downgrade_audit_category(detail.entry.category);
+            event
+        }
+        _ => event,
+    }
+}
+
+fn downgrade_audit_category(category: AuditLogCategory) -> AuditLogCategory {
+    match category {
+        AuditLogCategory::NetworkConfig => AuditLogCategory::General,
+        other => other,
+    }
+}
+
+/// Replace an event which an older peer can't decode with one it can, but which
+/// has no effect.
+///
+/// The ID and clock are kept, so that events depending on this one can still be
+/// applied, and so that the peer's clock still advances past it.
+fn placeholder_for(event: Event) -> Event {
+    Event {
+        target: PLACEHOLDER_SERVER.into(),
+        details: details::ServerPing {
+            ts: event.timestamp,
+            digest: None,
+        }
+        .into(),
+        ..event
+    }
+}
+
+/// Whether `event` is a placeholder sent in place of a real event to an older peer,
+/// and relayed back to us. The real event will arrive from elsewhere, so placeholders
+/// are never applied.
+pub(super) fn is_placeholder(event: &Event) -> bool {
+    event.target == ObjectId::from(PLACEHOLDER_SERVER)
+        && matches!(event.details, EventDetails::ServerPing(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(target: impl Into<ObjectId>, details: impl Into<EventDetails>) -> Event {
+        let log = crate::sync::EventLog::new(ObjectIdGenerator::new(ServerId::new(1)), None);
+        log.create(target, details)
+    }
+
+    #[test]
+    fn new_events_become_placeholders() {
+        let channel = ChannelId::new(Snowflake::from_parts(ServerId::new(1), 0, 1));
+        let user = UserId::new(Snowflake::from_parts(ServerId::new(1), 0, 2));
+        let kick = event(
+            MembershipId::new(user, channel),
+            details::ServerChannelKick {
+                source: ServerId::new(1),
+                message: "kicked".to_string(),
+            },
+        );
+
+        for version in [1, 2] {
+            let content = downgrade(MessageDetail::NewEvent(kick.clone()), version);
+            let MessageDetail::NewEvent(sent) = content else {
+                panic!("{:?}", content);
+            };
+            assert_eq!(sent.id, kick.id);
+            assert_eq!(sent.clock, kick.clock);
+            assert_eq!(is_placeholder(&sent), version == 1);
+
+            let json = serde_json::to_string(&sent).unwrap();
+            assert_eq!(json.contains("ServerChannelKick"), version == 2);
+        }
+    }
+
+    #[test]
+    fn old_events_are_unchanged() {
+        let ping = event(
+            ServerId::new(1),
+            details::ServerPing {
+                ts: 1,
+                digest: None,
+            },
+        );
+
+        let content = downgrade(MessageDetail::BulkEvents(vec![ping.clone()]), 1);
+        let MessageDetail::BulkEvents(sent) = content else {
+            panic!("{:?}", content);
+        };
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].target, ping.target);
+        assert!(!is_placeholder(&sent[0]));
+    }
+
+    #[test]
+    fn sync_unavailable_becomes_empty_bulk_events() {
+        let content = downgrade(MessageDetail::SyncUnavailable, 1);
+        assert!(matches!(content, MessageDetail::BulkEvents(ref e) if e.is_empty()));
+        assert!(matches!(
+            downgrade(MessageDetail::SyncUnavailable, 2),
+            MessageDetail::SyncUnavailable
+        ));
+    }
+}
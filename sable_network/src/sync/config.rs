@@ -8,7 +8,7 @@ use std::{fs::File, io::BufReader, io::Read, net::SocketAddr, path::Path, path::
 
 use rustls::{Certificate, PrivateKey};
 
-use crate::validated::ServerName;
+use crate::validated::{ServerName, Validated};
 
 /// Configuration of a peer in the gossip network
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -18,6 +18,36 @@ pub struct PeerConfig {
     pub(crate) fingerprint: String,
 }
 
+impl PeerConfig {
+    pub fn name(&self) -> &ServerName {
+        &self.name
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// The name to verify the peer's certificate against, if the peer's name is
+    /// usable as one
+    pub fn tls_server_name(&self) -> Option<rustls::ServerName> {
+        rustls::ServerName::try_from(self.name.value() as &str).ok()
+    }
+}
+
+impl From<&crate::network::state::SyncPeer> for PeerConfig {
+    fn from(peer: &crate::network::state::SyncPeer) -> Self {
+        Self {
+            name: *peer.name(),
+            address: peer.address.clone(),
+            fingerprint: peer.fingerprint.clone(),
+        }
+    }
+}
+
 /// Configuration of the gossip network
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SyncConfig {
@@ -39,6 +69,33 @@ pub struct NodeConfig {
 #[derive(Debug, Deserialize)]
 pub struct EventLogConfig {
     pub(crate) event_expiry: i64,
+    /// If present, periodic snapshots of network state and the event log are
+    /// written to disk, and used to restart without a full resync
+    #[serde(default)]
+    pub(crate) snapshot: Option<SnapshotConfig>,
+}
+
+/// Configuration for on-disk snapshots
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Directory in which snapshots are stored
+    pub(crate) directory: PathBuf,
+    /// Seconds between snapshots
+    #[serde(default = "SnapshotConfig::default_interval")]
+    pub(crate) interval: u64,
+    /// Number of old snapshots to keep
+    #[serde(default = "SnapshotConfig::default_keep")]
+    pub(crate) keep: usize,
+}
+
+impl SnapshotConfig {
+    fn default_interval() -> u64 {
+        300
+    }
+
+    fn default_keep() -> usize {
+        3
+    }
 }
 
 /// Errors that could happen when loading or processing a config
@@ -0,0 +1,323 @@
+//! Long-lived, multiplexed connections to peers in the sync network
+//!
+//! Each connection carries any number of concurrent conversations. A conversation
+//! starts with a single message from the side which opened the connection, and then
+//! proceeds exactly as a single-use connection used to: either side may respond to the
+//! other's messages, and the conversation ends when either side sends
+//! [`MessageDetail::Done`].
+
+use super::compat;
+use super::message::Message;
+use super::network::{NetworkError, NetworkResult};
+use super::wire::{self, WireFormat};
+use super::*;
+use crate::validated::ServerName;
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf},
+    sync::mpsc::{channel, unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::{AbortHandle, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+/// A single message on a peer connection, tagged with the conversation to which it belongs
+#[derive(Debug, Serialize, Deserialize)]
+struct Frame {
+    /// Identifies the conversation. Conversation IDs are allocated by the side which
+    /// opened the connection.
+    conversation: u64,
+    /// Set on the first message of a new conversation
+    open: bool,
+    message: Message,
+}
+
+/// The leading field of a [`Frame`], which can still be decoded if its message can't
+#[derive(Deserialize)]
+struct FrameHeader {
+    conversation: u64,
+}
+
+/// An authenticated connection to a single peer
+pub(super) struct PeerConnection {
+    peer_name: ServerName,
+    format: WireFormat,
+    outgoing: UnboundedSender<Frame>,
+    conversations: Mutex<HashMap<u64, UnboundedSender<Message>>>,
+    next_conversation: AtomicU64,
+    closed: AtomicBool,
+    tasks: Mutex<Vec<AbortHandle>>,
+}
+
+impl PeerConnection {
+    /// Begin serving an established and authenticated stream.
+    ///
+    /// If `inbound` is provided, conversations opened by the remote side will be
+    /// accepted and their messages sent there; otherwise only conversations opened
+    /// locally are permitted.
+    ///
+    /// Returns the connection, and a handle which completes when the connection closes.
+    pub fn start<S>(
+        stream: S,
+        peer_name: ServerName,
+        format: WireFormat,
+        inbound: Option<UnboundedSender<Request>>,
+    ) -> (Arc<Self>, JoinHandle<()>)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, writer) = tokio::io::split(stream);
+        let (outgoing, outgoing_recv) = unbounded_channel();
+
+        let conn = Arc::new(Self {
+            peer_name,
+            format,
+            outgoing,
+            conversations: Mutex::new(HashMap::new()),
+            next_conversation: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            tasks: Mutex::new(Vec::new()),
+        });
+
+        let writer_task = tokio::spawn(Arc::clone(&conn).write_loop(writer, outgoing_recv));
+        let reader_task = tokio::spawn(Arc::clone(&conn).read_loop(reader, inbound));
+
+        conn.tasks
+            .lock()
+            .unwrap()
+            .extend([writer_task.abort_handle(), reader_task.abort_handle()]);
+
+        (conn, reader_task)
+    }
+
+    pub fn peer_name(&self) -> &ServerName {
+        &self.peer_name
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Close the connection. Any conversations in progress will fail.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+
+        // Dropping the senders will end all conversation tasks
+        self.conversations.lock().unwrap().clear();
+    }
+
+    /// Start a new conversation with the peer by sending `message`. Messages received
+    /// in response are sent to `response_sender`, and the returned task completes when
+    /// the conversation ends.
+    pub fn open_conversation(
+        self: &Arc<Self>,
+        message: Message,
+        response_sender: UnboundedSender<Request>,
+    ) -> Result<JoinHandle<NetworkResult>, NetworkError> {
+        if self.is_closed() {
+            return Err(NetworkError::ConnectionClosed);
+        }
+
+        let id = self.next_conversation.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = unbounded_channel();
+        self.conversations.lock().unwrap().insert(id, sender);
+
+        if let Err(e) = self.outgoing.send(Frame {
+            conversation: id,
+            open: true,
+            message,
+        }) {
+            self.end_conversation(id);
+            return Err(e.into());
+        }
+
+        Ok(tokio::spawn(Arc::clone(self).run_conversation(
+            id,
+            receiver,
+            response_sender,
+        )))
+    }
+
+    fn end_conversation(&self, id: u64) {
+        self.conversations.lock().unwrap().remove(&id);
+    }
+
+    #[instrument(skip_all, fields(peer=%self.peer_name, conversation=id))]
+    async fn run_conversation(
+        self: Arc<Self>,
+        id: u64,
+        mut receiver: UnboundedReceiver<Message>,
+        message_sender: UnboundedSender<Request>,
+    ) -> NetworkResult {
+        let result = self
+            .handle_conversation(id, &mut receiver, message_sender)
+            .await;
+
+        self.end_conversation(id);
+
+        if let Err(e) = &result {
+            tracing::error!("Error in network sync conversation: {}", e);
+        }
+
+        result
+    }
+
+    async fn handle_conversation(
+        &self,
+        id: u64,
+        receiver: &mut UnboundedReceiver<Message>,
+        message_sender: UnboundedSender<Request>,
+    ) -> NetworkResult {
+        loop {
+            let msg = receiver
+                .recv()
+                .await
+                .ok_or(NetworkError::ConnectionClosed)?;
+
+            if matches!(msg.content, MessageDetail::Done) {
+                return Ok(());
+            }
+
+            tracing::trace!("Processing inbound message: {:?}", msg);
+
+            let (req_send, mut req_recv) = channel(8);
+            let req = Request {
+                received_from: self.peer_name,
+                response: req_send,
+                message: msg,
+            };
+
+            message_sender.send(req)?;
+
+            while let Some(response) = req_recv.recv().await {
+                tracing::trace!("Sending network response: {:?}", response);
+
+                let done = matches!(response.content, MessageDetail::Done);
+
+                self.outgoing.send(Frame {
+                    conversation: id,
+                    open: false,
+                    message: response,
+                })?;
+
+                if done {
+                    tracing::trace!("Got done, ending conversation");
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    #[instrument(skip_all, fields(peer=%self.peer_name))]
+    async fn write_loop<S: AsyncWrite>(
+        self: Arc<Self>,
+        mut writer: WriteHalf<S>,
+        mut outgoing: UnboundedReceiver<Frame>,
+    ) {
+        while let Some(mut frame) = outgoing.recv().await {
+            frame.message.content = compat::downgrade(frame.message.content, self.format.version);
+
+            let result = match self.format.encode(&frame) {
+                Ok(buf) => wire::write_frame(&mut writer, &buf).await,
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                tracing::error!("Error writing to peer connection: {}", e);
+                break;
+            }
+        }
+
+        self.close();
+    }
+
+    #[instrument(skip_all, fields(peer=%self.peer_name))]
+    async fn read_loop<S: AsyncRead>(
+        self: Arc<Self>,
+        mut reader: ReadHalf<S>,
+        inbound: Option<UnboundedSender<Request>>,
+    ) {
+        if let Err(e) = self.read_frames(&mut reader, inbound).await {
+            if !self.is_closed() {
+                tracing::info!("Peer connection closed: {}", e);
+            }
+        }
+
+        self.close();
+    }
+
+    async fn read_frames<S: AsyncRead>(
+        self: &Arc<Self>,
+        reader: &mut ReadHalf<S>,
+        inbound: Option<UnboundedSender<Request>>,
+    ) -> NetworkResult {
+        loop {
+            let buf = wire::read_frame(reader).await?;
+            let frame: Frame = match self.format.decode(&buf) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    // Don't let one bad message take down every other conversation
+                    // on the connection
+                    match self.format.decode::<FrameHeader>(&buf) {
+                        Ok(header) => {
+                            tracing::warn!(
+                                "Ending conversation {} after undecodable message: {}",
+                                header.conversation,
+                                e
+                            );
+                            self.end_conversation(header.conversation);
+                        }
+                        Err(_) => tracing::warn!("Dropping undecodable frame: {}", e),
+                    }
+                    continue;
+                }
+            };
+
+            let existing = self
+                .conversations
+                .lock()
+                .unwrap()
+                .get(&frame.conversation)
+                .cloned();
+
+            match (existing, &inbound) {
+                (Some(sender), _) => {
+                    // If the conversation task has already finished, there's nobody
+                    // left to process this
+                    sender.send(frame.message).ok();
+                }
+                (None, Some(message_sender)) if frame.open => {
+                    let (sender, receiver) = unbounded_channel();
+                    sender.send(frame.message).ok();
+                    self.conversations
+                        .lock()
+                        .unwrap()
+                        .insert(frame.conversation, sender);
+
+                    tokio::spawn(Arc::clone(self).run_conversation(
+                        frame.conversation,
+                        receiver,
+                        message_sender.clone(),
+                    ));
+                }
+                (None, _) => {
+                    tracing::trace!(
+                        "Dropping message for unknown conversation {}: {:?}",
+                        frame.conversation,
+                        frame.message
+                    );
+                }
+            }
+        }
+    }
+}
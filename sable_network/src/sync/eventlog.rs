@@ -21,6 +21,8 @@ pub struct EventLog {
     id_gen: ObjectIdGenerator,
     event_sender: Option<UnboundedSender<Event>>,
     last_event_clock: EventClock,
+    /// The most recent event pruned from the log for each server
+    pruned_clock: EventClock,
 }
 
 /// Saved state for an [EventLog], used to save and restore across an upgrade
@@ -28,10 +30,13 @@ pub struct EventLog {
 pub struct EventLogState {
     id_gen: ObjectIdGenerator,
     clock: EventClock,
+    #[serde(default = "EventClock::new")]
+    pruned_clock: EventClock,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct EventLogStats {
+    pub events: usize,
     pub pending_events: usize,
     pub current_clock: EventClock,
 }
@@ -47,6 +52,7 @@ impl EventLog {
             id_gen: idgen,
             event_sender,
             last_event_clock: EventClock::new(),
+            pruned_clock: EventClock::new(),
         }
     }
 
@@ -58,6 +64,7 @@ impl EventLog {
             id_gen: state.id_gen,
             event_sender,
             last_event_clock: state.clock,
+            pruned_clock: state.pruned_clock,
         }
     }
 
@@ -68,6 +75,7 @@ impl EventLog {
         EventLogState {
             id_gen: self.id_gen,
             clock: self.last_event_clock,
+            pruned_clock: self.pruned_clock,
         }
     }
 
@@ -98,9 +106,25 @@ impl EventLog {
         &self.last_event_clock
     }
 
+    /// Determine whether every event not contained in `clock` is still held in this
+    /// log, i.e. whether a server at `clock` could be brought up to date using
+    /// [`get_since`](Self::get_since) alone.
+    pub fn can_serve_since(&self, clock: &EventClock) -> bool {
+        self.pruned_clock
+            .0
+            .values()
+            .all(|pruned| clock.contains(*pruned))
+    }
+
+    /// The number of events held back waiting for their dependencies
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
     /// Return some statistics about the event log
     pub fn get_stats(&self) -> EventLogStats {
         EventLogStats {
+            events: self.history.values().map(|events| events.len()).sum(),
             pending_events: self.pending.len(),
             current_clock: self.last_event_clock.clone(),
         }
@@ -118,13 +142,47 @@ impl EventLog {
         self.pending.values()
     }
 
+    /// Iterate over all events currently held in the log, excluding those which
+    /// are pending
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.history.values().flat_map(|m| m.values())
+    }
+
+    /// Load previously-processed events into the log's history, without
+    /// notifying them or updating the log's clock.
+    ///
+    /// This should only be used when restoring from a snapshot, alongside
+    /// [`set_clock`](Self::set_clock).
+    pub fn load_history(&mut self, events: impl IntoIterator<Item = Event>) {
+        for event in events {
+            self.history
+                .entry(event.id.server())
+                .or_default()
+                .insert(event.id, event);
+        }
+    }
+
     /// Set the clock for this log.
     ///
     /// This should only be used when importing a serialized
     /// [Network] state, to sync the event log's view of
     /// 'current' with that from the imported network state.
+    ///
+    /// Events covered by the new clock didn't pass through this log, so they're
+    /// treated as pruned and won't be offered to servers trying to catch up. Pending
+    /// events which the imported state already reflects are discarded, and any
+    /// whose dependencies are now satisfied are processed.
     pub fn set_clock(&mut self, new_clock: EventClock) {
+        self.pruned_clock.update_with_clock(&new_clock);
+        self.pending.retain(|id, _| !new_clock.contains(*id));
         self.last_event_clock = new_clock;
+        self.check_pending();
+    }
+
+    /// Determine whether the given event has been pruned from this log, and so
+    /// can't be supplied to other servers
+    pub fn is_pruned(&self, id: &EventId) -> bool {
+        self.pruned_clock.contains(*id) && self.get(id).is_none()
     }
 
     /// Add an event to the log.
@@ -179,7 +237,14 @@ impl EventLog {
     /// Remove events older than the provided timestamp
     pub fn prune_events_before(&mut self, threshold_timestamp: i64) {
         for (_server_id, server_events) in self.history.iter_mut() {
-            server_events.retain(|_id, event| event.timestamp < threshold_timestamp);
+            server_events.retain(|id, event| {
+                if event.timestamp < threshold_timestamp {
+                    self.pruned_clock.update_with_id(*id);
+                    false
+                } else {
+                    true
+                }
+            });
         }
     }
 
@@ -31,6 +31,11 @@ pub enum MessageDetail {
     MessageRejected,
     /// Finished processing; close the connection
     Done,
+    // New variants must be added at the end, to keep the binary encoding of
+    // existing ones stable between versions
+    /// Response to `SyncRequest` or `GetEvent` if some of the requested events have
+    /// already been pruned, so the full network state is needed instead
+    SyncUnavailable,
 }
 
 /// Details of a request/response message targeted to a particular server in the network
@@ -45,6 +50,10 @@ pub struct TargetedMessage {
     pub via: Vec<ServerName>,
     /// The content of the message
     pub content: rpc::RemoteServerRequestType,
+    /// How much longer the source server will wait for a response. Servers relaying
+    /// the message give up once this has passed.
+    #[serde(default)]
+    pub time_remaining: Option<std::time::Duration>,
 }
 
 /// A single protocol message
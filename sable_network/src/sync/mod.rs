@@ -1,17 +1,25 @@
 //! This crate contains the code required to manage the ircd's event log and
 //! synchronise it with other servers.
 
+mod compat;
 mod config;
+mod connection;
 mod eventlog;
 mod message;
 mod network;
+mod routing;
+mod wire;
 
 mod replicated_log;
+mod snapshot;
+
+pub mod replay;
 
 pub use config::ConfigError;
 pub use config::EventLogConfig;
 pub use config::NodeConfig;
 pub use config::PeerConfig;
+pub use config::SnapshotConfig;
 pub use config::SyncConfig;
 pub use eventlog::EventLog;
 pub use eventlog::EventLogState;
@@ -22,10 +30,17 @@ pub use message::Request;
 pub use network::GossipNetwork;
 pub use network::GossipNetworkState;
 pub use network::NetworkError;
+pub use network::PeerStatistics;
+pub use routing::RemoteRequestStats;
+pub use routing::RouteEntry;
 
 pub use replicated_log::EventLogSaveError;
 pub use replicated_log::ReplicatedEventLog;
 pub use replicated_log::ReplicatedEventLogState;
+pub use replicated_log::DEFAULT_REQUEST_TIMEOUT;
+
+pub use snapshot::Snapshot;
+pub use snapshot::SnapshotError;
 
 #[cfg(test)]
 mod tests;
@@ -1,26 +1,25 @@
 //! Networking code for the sync protocol
 
+use super::connection::PeerConnection;
 use super::message::Message;
+use super::wire;
 use super::*;
 use crate::validated::{ServerName, Validated};
 
+use backoff::{backoff::Backoff, ExponentialBackoff};
 use futures::future;
 use std::{
-    convert::TryInto,
     net::{IpAddr, SocketAddr, SocketAddrV6},
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
     sync::Arc,
-    sync::Mutex,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
 };
 use tokio::{
     io,
-    io::{AsyncReadExt, AsyncWriteExt},
     net::{lookup_host, TcpListener, TcpSocket, TcpStream},
     select,
-    sync::{
-        mpsc::{channel, UnboundedSender},
-        oneshot,
-    },
+    sync::{mpsc::UnboundedSender, oneshot},
     task::{JoinError, JoinHandle},
 };
 use tokio_rustls::{TlsAcceptor, TlsConnector, TlsStream};
@@ -33,6 +32,11 @@ use rand::prelude::*;
 use thiserror::Error;
 use tracing::instrument;
 
+/// How long to wait for a connection to a peer to be established and authenticated.
+/// Senders to the same peer wait for the attempt in progress, so this bounds how long
+/// they can be held up by an unresponsive peer.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// An interface to the gossip network used to synchronise state.
 pub struct GossipNetwork {
     fanout: usize,
@@ -46,21 +50,119 @@ pub struct GossipNetwork {
 ///
 /// Note that all additions to this struct must keep it `Send` and `Sync`.
 struct NetworkTaskState {
-    peers: Vec<Peer>,
+    peers: RwLock<Vec<Arc<Peer>>>,
     listen_addr: SocketAddr,
     tls_server_config: Arc<ServerConfig>,
     message_sender: UnboundedSender<Request>,
+    /// Connections opened to us by other peers, kept so they can be closed on shutdown
+    inbound_connections: Mutex<Vec<Arc<PeerConnection>>>,
 }
 
-struct Peer {
+pub(super) struct Peer {
     conf: PeerConfig,
     enabled: AtomicBool,
+    /// Our outbound connection to this peer, if one is established. This is an async
+    /// lock so that concurrent senders wait for a single connection attempt.
+    pub(super) connection: tokio::sync::Mutex<Option<Arc<PeerConnection>>>,
+    reconnect: Mutex<ReconnectState>,
+    messages_sent: AtomicU64,
+    send_failures: AtomicU64,
+}
+
+/// Health of the sync connection to a single peer
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PeerStatistics {
+    pub name: ServerName,
+    pub enabled: bool,
+    pub connected: bool,
+    /// If the last connection attempt failed, how long until the next one is allowed
+    pub retry_in_ms: Option<u128>,
+    pub messages_sent: u64,
+    pub send_failures: u64,
+}
+
+/// Tracks failed connection attempts to a peer, so that we don't try to reconnect
+/// to an unreachable server for every message we send.
+struct ReconnectState {
+    backoff: ExponentialBackoff,
+    retry_at: Option<Instant>,
+}
+
+impl Peer {
+    pub(super) fn new(conf: PeerConfig) -> Self {
+        Self {
+            conf,
+            enabled: AtomicBool::new(false),
+            connection: tokio::sync::Mutex::new(None),
+            reconnect: Mutex::new(ReconnectState {
+                backoff: ExponentialBackoff {
+                    initial_interval: Duration::from_millis(100),
+                    max_interval: Duration::from_secs(30),
+                    max_elapsed_time: None,
+                    ..ExponentialBackoff::default()
+                },
+                retry_at: None,
+            }),
+            messages_sent: AtomicU64::new(0),
+            send_failures: AtomicU64::new(0),
+        }
+    }
+
+    fn statistics(&self) -> PeerStatistics {
+        // If the connection is locked, someone is in the middle of connecting
+        let connected = self
+            .connection
+            .try_lock()
+            .map(|conn| conn.as_ref().is_some_and(|c| !c.is_closed()))
+            .unwrap_or(false);
+        let retry_in_ms = self
+            .reconnect
+            .lock()
+            .unwrap()
+            .retry_at
+            .and_then(|at| at.checked_duration_since(Instant::now()))
+            .map(|d| d.as_millis());
+
+        PeerStatistics {
+            name: self.conf.name,
+            enabled: self.enabled.load(Ordering::SeqCst),
+            connected,
+            retry_in_ms,
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            send_failures: self.send_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Close any established connection to this peer.
+    ///
+    /// If a connection attempt is in progress, the connection is closed once that
+    /// attempt completes, so that it can't be left open after the peer is removed.
+    pub(super) fn disconnect(self: &Arc<Self>) {
+        fn close(connection: &mut Option<Arc<PeerConnection>>) {
+            if let Some(conn) = connection.take() {
+                conn.close();
+            }
+        }
+
+        match self.connection.try_lock() {
+            Ok(mut connection) => close(&mut connection),
+            Err(_) => {
+                let peer = Arc::clone(self);
+                tokio::spawn(async move {
+                    close(&mut *peer.connection.lock().await);
+                });
+            }
+        }
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct GossipNetworkState {
     server_name: ServerName,
     peer_states: Vec<(ServerName, bool)>,
+    /// The full peer list, including any changes made at runtime
+    #[serde(default)]
+    peers: Option<Vec<PeerConfig>>,
 }
 
 #[derive(Debug, Error)]
@@ -71,6 +173,10 @@ pub enum NetworkError {
     Send(String),
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("Encoding error: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("Protocol negotiation failed: {0}")]
+    Handshake(String),
     #[error("Error joining task: {0}")]
     Join(#[from] JoinError),
     #[error("Listen task already spawned")]
@@ -83,6 +189,14 @@ pub enum NetworkError {
     Timeout,
     #[error("No address found when resolving {0}")]
     NoAddress(String),
+    #[error("Peer connection closed")]
+    ConnectionClosed,
+    #[error("Peer no longer holds the events needed to catch up")]
+    EventsPruned,
+    #[error("Not retrying connection to {0} until {1:?}")]
+    PeerUnavailable(ServerName, Instant),
+    #[error("{0} is not a valid TLS server name")]
+    InvalidServerName(ServerName),
 }
 pub type NetworkResult = Result<(), NetworkError>;
 
@@ -136,15 +250,10 @@ impl GossipNetwork {
             me,
             task_state: Arc::new(NetworkTaskState {
                 listen_addr: node_config.listen_addr,
-                peers: peers
-                    .into_iter()
-                    .map(|c| Peer {
-                        conf: c,
-                        enabled: AtomicBool::new(false),
-                    })
-                    .collect(),
+                peers: RwLock::new(peers.into_iter().map(Peer::new).map(Arc::new).collect()),
                 tls_server_config: Arc::new(server_config),
                 message_sender,
+                inbound_connections: Mutex::new(Vec::new()),
             }),
         }
     }
@@ -157,6 +266,15 @@ impl GossipNetwork {
     ) -> Self {
         let ret = Self::new(&state.server_name, net_config, node_config, message_sender);
 
+        if let Some(peers) = state.peers {
+            *ret.task_state.peers.write().unwrap() = peers
+                .into_iter()
+                .filter(|p| p.name != ret.me.name)
+                .map(Peer::new)
+                .map(Arc::new)
+                .collect();
+        }
+
         for (peer, enabled) in state.peer_states {
             if enabled {
                 ret.enable_peer(&peer);
@@ -171,10 +289,11 @@ impl GossipNetwork {
             server_name: self.me.name,
             peer_states: self
                 .task_state
-                .peers
+                .peers()
                 .iter()
                 .map(|peer| (peer.conf.name, peer.enabled.load(Ordering::SeqCst)))
                 .collect(),
+            peers: Some(self.peers()),
         }
     }
 
@@ -184,15 +303,109 @@ impl GossipNetwork {
                 sender.send(()).ok();
             }
         }
+
+        for peer in self.task_state.peers() {
+            peer.disconnect();
+        }
     }
 
     pub fn me(&self) -> &PeerConfig {
         &self.me
     }
 
+    /// The current list of peers
+    pub fn peers(&self) -> Vec<PeerConfig> {
+        self.task_state
+            .peers()
+            .iter()
+            .map(|p| p.conf.clone())
+            .collect()
+    }
+
+    /// Report the health of each peer connection
+    pub fn peer_statistics(&self) -> Vec<PeerStatistics> {
+        self.task_state
+            .peers()
+            .iter()
+            .map(|p| p.statistics())
+            .collect()
+    }
+
+    /// The peers which are currently enabled
+    pub fn active_peers(&self) -> Vec<PeerConfig> {
+        self.task_state
+            .peers()
+            .iter()
+            .filter(|p| p.enabled.load(Ordering::SeqCst))
+            .map(|p| p.conf.clone())
+            .collect()
+    }
+
+    /// Add a peer to the network, or update its details if it's already known.
+    ///
+    /// The new peer starts disabled unless `enabled` is set; it will be enabled as
+    /// normal once the corresponding server is seen to join the network.
+    pub fn add_peer(&self, conf: PeerConfig, enabled: bool) {
+        if conf.name == self.me.name {
+            return;
+        }
+
+        tracing::info!("adding peer {} at {}", conf.name, conf.address);
+
+        let mut peers = self.task_state.peers.write().unwrap();
+
+        if let Some(existing) = peers.iter_mut().find(|p| p.conf.name == conf.name) {
+            if existing.conf.address == conf.address
+                && existing.conf.fingerprint == conf.fingerprint
+            {
+                if enabled {
+                    existing.enabled.store(true, Ordering::SeqCst);
+                }
+                return;
+            }
+
+            // The peer's details have changed, so any existing connection is suspect
+            let enabled = enabled || existing.enabled.load(Ordering::SeqCst);
+            existing.disconnect();
+            *existing = Arc::new(Peer::new(conf));
+            existing.enabled.store(enabled, Ordering::SeqCst);
+        } else {
+            let peer = Peer::new(conf);
+            peer.enabled.store(enabled, Ordering::SeqCst);
+            peers.push(Arc::new(peer));
+        }
+    }
+
+    /// Remove a peer from the network, closing any connections to or from it
+    pub fn remove_peer(&self, name: &ServerName) {
+        tracing::info!("removing peer {}", name);
+
+        self.task_state.peers.write().unwrap().retain(|p| {
+            if &p.conf.name == name {
+                p.disconnect();
+                false
+            } else {
+                true
+            }
+        });
+
+        self.task_state
+            .inbound_connections
+            .lock()
+            .unwrap()
+            .retain(|c| {
+                if c.peer_name() == name {
+                    c.close();
+                    false
+                } else {
+                    true
+                }
+            });
+    }
+
     pub fn enable_peer(&self, name: &ServerName) {
         tracing::debug!("enabling peer {}", name);
-        for p in self.task_state.peers.iter() {
+        for p in self.task_state.peers() {
             if &p.conf.name == name {
                 p.enabled.store(true, Ordering::SeqCst);
             }
@@ -202,22 +415,23 @@ impl GossipNetwork {
     pub fn disable_peer(&self, name: &ServerName) {
         tracing::debug!("disabling peer {}", name);
 
-        for p in self.task_state.peers.iter() {
+        for p in self.task_state.peers() {
             if &p.conf.name == name {
                 p.enabled.store(false, Ordering::SeqCst);
+                p.disconnect();
             }
         }
     }
 
     #[instrument(skip_all)]
-    pub fn choose_peer(&self) -> Option<&PeerConfig> {
+    pub fn choose_peer(&self) -> Option<PeerConfig> {
         let ret = self
             .task_state
-            .peers
-            .iter()
+            .peers()
+            .into_iter()
             .filter(|p| p.enabled.load(Ordering::SeqCst))
             .choose(&mut rand::thread_rng())
-            .map(|p| &p.conf);
+            .map(|p| p.conf.clone());
 
         if ret.is_none() {
             tracing::info!("No active peer available to choose");
@@ -226,13 +440,13 @@ impl GossipNetwork {
     }
 
     #[instrument(skip_all)]
-    pub fn choose_any_peer(&self) -> Option<&PeerConfig> {
+    pub fn choose_any_peer(&self) -> Option<PeerConfig> {
         let ret = self
             .task_state
-            .peers
-            .iter()
+            .peers()
+            .into_iter()
             .choose(&mut rand::thread_rng())
-            .map(|p| &p.conf);
+            .map(|p| p.conf.clone());
 
         if ret.is_none() {
             tracing::info!("No peer available to choose");
@@ -241,14 +455,14 @@ impl GossipNetwork {
     }
 
     /// Choose a peer at random that isn't in the provided list
-    pub fn choose_peer_except(&self, except: &[ServerName]) -> Option<&PeerConfig> {
+    pub fn choose_peer_except(&self, except: &[ServerName]) -> Option<PeerConfig> {
         let ret = self
             .task_state
-            .peers
-            .iter()
+            .peers()
+            .into_iter()
             .filter(|p| p.enabled.load(Ordering::SeqCst) && !except.contains(&p.conf.name))
             .choose(&mut rand::thread_rng())
-            .map(|p| &p.conf);
+            .map(|p| p.conf.clone());
 
         if ret.is_none() {
             tracing::info!("No active peer available to choose");
@@ -257,14 +471,14 @@ impl GossipNetwork {
     }
 
     /// Find a peer config with the given server name
-    pub fn find_peer(&self, name: &ServerName) -> Option<&PeerConfig> {
+    pub fn find_peer(&self, name: &ServerName) -> Option<PeerConfig> {
         let ret = self
             .task_state
-            .peers
-            .iter()
+            .peers()
+            .into_iter()
             .filter(|p| p.enabled.load(Ordering::Relaxed))
             .find(|p| &p.conf.name == name)
-            .map(|p| &p.conf);
+            .map(|p| p.conf.clone());
 
         if ret.is_none() {
             tracing::info!("No peer named {} available", name);
@@ -273,11 +487,10 @@ impl GossipNetwork {
     }
 
     pub async fn propagate(&self, msg: &Message) {
+        let peers = self.task_state.peers();
         let mut tasks = Vec::new();
 
-        let chosen_peers = self
-            .task_state
-            .peers
+        let chosen_peers = peers
             .iter()
             .filter(|p| p.enabled.load(Ordering::SeqCst))
             .choose_multiple(&mut rand::thread_rng(), self.fanout);
@@ -370,27 +583,106 @@ impl GossipNetwork {
         msg: Message,
         response_sender: UnboundedSender<Request>,
     ) -> Result<JoinHandle<NetworkResult>, NetworkError> {
+        let result = match self.connection_to(peer).await {
+            Ok(connection) => connection.open_conversation(msg, response_sender),
+            Err(e) => Err(e),
+        };
+
+        if let Some(peer_state) = self
+            .task_state
+            .peers()
+            .iter()
+            .find(|p| p.conf.name == peer.name)
+        {
+            let counter = if result.is_ok() {
+                &peer_state.messages_sent
+            } else {
+                &peer_state.send_failures
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+
+        result
+    }
+
+    /// Get the established connection to the given peer, connecting if needed
+    async fn connection_to(&self, peer: &PeerConfig) -> Result<Arc<PeerConnection>, NetworkError> {
+        let peer_state = self
+            .task_state
+            .peers()
+            .into_iter()
+            .find(|p| p.conf.name == peer.name)
+            .ok_or_else(|| NetworkError::InternalError(format!("Unknown peer {}", peer.name)))?;
+
+        let mut connection = peer_state.connection.lock().await;
+
+        if let Some(conn) = connection.as_ref() {
+            if !conn.is_closed() {
+                return Ok(Arc::clone(conn));
+            }
+        }
+
+        if let Some(retry_at) = peer_state.reconnect.lock().unwrap().retry_at {
+            if retry_at > Instant::now() {
+                return Err(NetworkError::PeerUnavailable(peer.name, retry_at));
+            }
+        }
+
+        let result = tokio::time::timeout(CONNECT_TIMEOUT, self.open_connection(peer))
+            .await
+            .unwrap_or(Err(NetworkError::Timeout));
+
+        match result {
+            Ok(conn) => {
+                let mut reconnect = peer_state.reconnect.lock().unwrap();
+                reconnect.backoff.reset();
+                reconnect.retry_at = None;
+
+                *connection = Some(Arc::clone(&conn));
+                Ok(conn)
+            }
+            Err(e) => {
+                let mut reconnect = peer_state.reconnect.lock().unwrap();
+                let delay = reconnect
+                    .backoff
+                    .next_backoff()
+                    .unwrap_or(reconnect.backoff.max_interval);
+                reconnect.retry_at = Some(Instant::now() + delay);
+
+                *connection = None;
+                Err(e)
+            }
+        }
+    }
+
+    async fn open_connection(
+        &self,
+        peer: &PeerConfig,
+    ) -> Result<Arc<PeerConnection>, NetworkError> {
         let mut local_addr = self.task_state.listen_addr;
         local_addr.set_port(0);
         let connector = TlsConnector::from(Arc::clone(&self.tls_client_config));
         let conn = Self::connect(&local_addr, &peer.address).await?;
-        let server_name = (peer.name.value() as &str)
-            .try_into()
-            .expect("Invalid server name");
-        let stream = connector.connect(server_name, conn).await?;
+        let server_name = peer
+            .tls_server_name()
+            .ok_or(NetworkError::InvalidServerName(peer.name))?;
+        let mut stream: TlsStream<TcpStream> = connector.connect(server_name, conn).await?.into();
 
-        let task_state = Arc::clone(&self.task_state);
-        Ok(tokio::spawn(async move {
-            let result = task_state
-                .send_and_handle_response(stream.into(), msg, response_sender)
-                .await;
+        let peer_name = self.task_state.authenticate_peer(&stream).await?;
+        if peer_name != peer.name {
+            return Err(NetworkError::AuthzError(format!(
+                "Connected to {} but found {}",
+                peer.name, peer_name
+            )));
+        }
 
-            if let Err(e) = &result {
-                tracing::error!("Error in outbound network sync connection: {}", e);
-            }
+        let format = wire::negotiate_outbound(&mut stream).await?;
+        tracing::debug!(?format, "Negotiated wire format with {}", peer_name);
 
-            result
-        }))
+        // Conversations are only ever opened by the connecting side, so there's
+        // nothing to accept on this connection
+        let (connection, _) = PeerConnection::start(stream, peer_name, format, None);
+        Ok(connection)
     }
 
     pub async fn spawn_listen_task(&self) -> Result<JoinHandle<()>, NetworkError> {
@@ -418,6 +710,11 @@ impl GossipNetwork {
 }
 
 impl NetworkTaskState {
+    /// Take a snapshot of the current peer list
+    fn peers(&self) -> Vec<Arc<Peer>> {
+        self.peers.read().unwrap().clone()
+    }
+
     #[instrument(skip(self))]
     async fn listen_loop(
         self: Arc<NetworkTaskState>,
@@ -450,6 +747,10 @@ impl NetworkTaskState {
             }
         }
 
+        for connection in self.inbound_connections.lock().unwrap().drain(..) {
+            connection.close();
+        }
+
         Ok(())
     }
 
@@ -460,41 +761,33 @@ impl NetworkTaskState {
         conn: TcpStream,
         message_sender: UnboundedSender<Request>,
     ) -> Result<(), NetworkError> {
-        let stream = tls_acceptor.accept(conn).await?;
+        let mut stream: TlsStream<TcpStream> = tls_acceptor.accept(conn).await?.into();
+        let peer_name = self.authenticate_peer(&stream).await?;
 
-        if let Err(e) = self
-            .read_and_handle_message(stream.into(), message_sender)
-            .await
+        let format = wire::negotiate_inbound(&mut stream).await?;
+        tracing::debug!(?format, "Accepted sync connection from {}", peer_name);
+
+        let (connection, closed) =
+            PeerConnection::start(stream, peer_name, format, Some(message_sender));
         {
-            tracing::error!("Error handling message: {}", e);
+            let mut inbound = self.inbound_connections.lock().unwrap();
+            inbound.retain(|c| !c.is_closed());
+            inbound.push(connection);
         }
 
-        Ok(())
-    }
-
-    #[instrument(skip(self))]
-    async fn send_and_handle_response(
-        self: Arc<NetworkTaskState>,
-        mut stream: TlsStream<TcpStream>,
-        message: Message,
-        response_sender: UnboundedSender<Request>,
-    ) -> Result<(), NetworkError> {
-        let buf = serde_json::to_vec(&message)?;
-        stream.write_u32(buf.len().try_into().unwrap()).await?;
-        stream.write_all(&buf).await?;
+        closed.await.ok();
 
-        self.read_and_handle_message(stream, response_sender)
-            .await?;
+        tracing::debug!("Sync connection from {} closed", peer_name);
 
         Ok(())
     }
 
-    #[instrument(skip(self))]
-    async fn read_and_handle_message(
-        self: Arc<NetworkTaskState>,
-        mut stream: TlsStream<TcpStream>,
-        message_sender: UnboundedSender<Request>,
-    ) -> Result<(), NetworkError> {
+    /// Check that the remote end of a newly established stream is a configured peer,
+    /// and return its name
+    async fn authenticate_peer(
+        &self,
+        stream: &TlsStream<TcpStream>,
+    ) -> Result<ServerName, NetworkError> {
         // Get the peer name we're talking to from the tls certificate
         let (tcp_stream, state) = stream.get_ref();
         let peer_certs = state
@@ -519,8 +812,8 @@ impl NetworkTaskState {
         })?;
 
         let peer = self
-            .peers
-            .iter()
+            .peers()
+            .into_iter()
             .find(|p| p.conf.name == peer_name)
             .ok_or_else(|| {
                 NetworkError::AuthzError("Couldn't find peer configuration".to_string())
@@ -558,40 +851,6 @@ impl NetworkTaskState {
             )));
         }
 
-        loop {
-            let length = stream.read_u32().await?;
-
-            let mut buf = vec![0; length.try_into().unwrap()];
-            stream.read_exact(&mut buf).await?;
-
-            let msg: Message = serde_json::from_slice(&buf)?;
-
-            if matches!(msg.content, MessageDetail::Done) {
-                return Ok(());
-            }
-
-            tracing::trace!("Processing inbound message: {:?}", msg);
-
-            let (req_send, mut req_recv) = channel(8);
-            let req = Request {
-                received_from: peer_name,
-                response: req_send,
-                message: msg,
-            };
-
-            message_sender.send(req)?;
-
-            while let Some(response) = req_recv.recv().await {
-                tracing::trace!("Sending network response: {:?}", response);
-                let buf = serde_json::to_vec(&response).expect("Failed to serialise response");
-                stream.write_u32(buf.len().try_into().unwrap()).await?;
-                stream.write_all(&buf).await?;
-
-                if matches!(response.content, MessageDetail::Done) {
-                    tracing::trace!("Got done, ending connection");
-                    return Ok(());
-                }
-            }
-        }
+        Ok(peer_name)
     }
 }
@@ -0,0 +1,228 @@
+//! Offline checking and replay of event logs, for tracking down divergent state
+//!
+//! These are used by the `sable_eventlog` tool to take a dump of events from one
+//! node, apply it to a fresh [`Network`], and compare the result against another
+//! node's view of the network.
+
+use crate::prelude::*;
+
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tokio::sync::mpsc::unbounded_channel;
+
+/// A problem with the ordering of events in a log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderingError {
+    /// The same event appears more than once
+    Duplicate(EventId),
+    /// An event depends on another which only appears later in the log
+    OutOfOrder { event: EventId, dependency: EventId },
+    /// An event depends on one which is neither in the log nor covered by the
+    /// starting clock. For a pruned log, this usually means the base state is missing.
+    MissingDependency { event: EventId, dependency: EventId },
+}
+
+/// Check that every event in `events` appears after all of its dependencies,
+/// assuming that everything in `base` has already been processed.
+pub fn check_ordering(events: &[Event], base: &EventClock) -> Vec<OrderingError> {
+    let mut positions = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (i, event) in events.iter().enumerate() {
+        if positions.insert(event.id, i).is_some() {
+            errors.push(OrderingError::Duplicate(event.id));
+        }
+    }
+
+    for (i, event) in events.iter().enumerate() {
+        for dependency in event.clock.0.values() {
+            if base.contains(*dependency) {
+                continue;
+            }
+
+            match positions.get(dependency) {
+                Some(pos) if *pos < i => {}
+                Some(_) => errors.push(OrderingError::OutOfOrder {
+                    event: event.id,
+                    dependency: *dependency,
+                }),
+                None => errors.push(OrderingError::MissingDependency {
+                    event: event.id,
+                    dependency: *dependency,
+                }),
+            }
+        }
+    }
+
+    errors
+}
+
+/// The outcome of replaying a sequence of events
+pub struct Replay {
+    /// The resulting network state
+    pub network: Network,
+    /// Events which were applied, in the order they were applied
+    pub applied: Vec<Event>,
+    /// Events which couldn't be applied because their dependencies never arrived
+    pub unapplied: Vec<Event>,
+    /// Events which the network state rejected
+    pub failed: Vec<(Event, WrongIdTypeError)>,
+}
+
+struct NopUpdateReceiver;
+
+impl NetworkUpdateReceiver for NopUpdateReceiver {
+    fn notify_update(&self, _update: NetworkStateChange, _event: &Event) {}
+}
+
+/// Apply `events` to `base`, in dependency order regardless of the order in which
+/// they're provided. Events already reflected in `base` are skipped.
+pub fn replay(base: Network, events: Vec<Event>) -> Replay {
+    let (sender, mut receiver) = unbounded_channel();
+    let mut log = EventLog::new(ObjectIdGenerator::new(ServerId::new(0)), Some(sender));
+    log.set_clock(base.clock().clone());
+
+    for event in events.iter().cloned() {
+        log.add(event);
+    }
+
+    let unapplied = events
+        .into_iter()
+        .filter(|e| !log.clock().contains(e.id))
+        .collect();
+
+    let mut network = base;
+    let mut applied = Vec::new();
+    let mut failed = Vec::new();
+
+    while let Ok(event) = receiver.try_recv() {
+        if network.clock().contains(event.id) {
+            continue;
+        }
+
+        match network.apply(&event, &NopUpdateReceiver) {
+            Ok(()) => applied.push(event),
+            Err(e) => failed.push((event, e)),
+        }
+    }
+
+    Replay {
+        network,
+        applied,
+        unapplied,
+        failed,
+    }
+}
+
+/// A single point of difference between two JSON documents
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// Location of the difference, e.g. `.users[1234].nick`
+    pub path: String,
+    /// Key of the outermost map entry containing the difference. For a network
+    /// dump, this is the ID of the object which differs.
+    pub object: Option<Value>,
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+}
+
+/// Compare two JSON documents, returning every location at which they differ.
+///
+/// Lists of key/value pairs, as used to serialise the maps in [`Network`], are
+/// compared by key rather than by position, since their order isn't meaningful.
+pub fn diff_json(left: &Value, right: &Value) -> Vec<Difference> {
+    let mut out = Vec::new();
+    diff_values(String::new(), None, left, right, &mut out);
+    out
+}
+
+fn diff_values(
+    path: String,
+    object: Option<&Value>,
+    left: &Value,
+    right: &Value,
+    out: &mut Vec<Difference>,
+) {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            let keys: BTreeSet<_> = l.keys().chain(r.keys()).collect();
+            for key in keys {
+                diff_entry(format!("{path}.{key}"), object, l.get(key), r.get(key), out);
+            }
+        }
+        (Value::Array(l), Value::Array(r)) if is_pair_list(l) && is_pair_list(r) => {
+            let l = pair_map(l);
+            let r = pair_map(r);
+            let keys: BTreeMap<_, _> = l
+                .iter()
+                .chain(r.iter())
+                .map(|(k, (key, _))| (k, *key))
+                .collect();
+
+            for (name, key) in keys {
+                diff_entry(
+                    format!("{path}[{name}]"),
+                    object.or(Some(key)),
+                    l.get(name).map(|(_, v)| *v),
+                    r.get(name).map(|(_, v)| *v),
+                    out,
+                );
+            }
+        }
+        (Value::Array(l), Value::Array(r)) if l.len() == r.len() => {
+            for (i, (l, r)) in l.iter().zip(r).enumerate() {
+                diff_values(format!("{path}[{i}]"), object, l, r, out);
+            }
+        }
+        _ if left != right => out.push(Difference {
+            path,
+            object: object.cloned(),
+            left: Some(left.clone()),
+            right: Some(right.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_entry(
+    path: String,
+    object: Option<&Value>,
+    left: Option<&Value>,
+    right: Option<&Value>,
+    out: &mut Vec<Difference>,
+) {
+    match (left, right) {
+        (Some(l), Some(r)) => diff_values(path, object, l, r, out),
+        _ => out.push(Difference {
+            path,
+            object: object.cloned(),
+            left: left.cloned(),
+            right: right.cloned(),
+        }),
+    }
+}
+
+fn is_pair_list(list: &[Value]) -> bool {
+    list.iter()
+        .all(|item| matches!(item, Value::Array(pair) if pair.len() == 2))
+}
+
+/// Index a list of key/value pairs by the serialised form of the key
+fn pair_map(list: &[Value]) -> HashMap<String, (&Value, &Value)> {
+    list.iter()
+        .filter_map(|item| match item {
+            Value::Array(pair) => Some((pair[0].to_string(), (&pair[0], &pair[1]))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Determine whether `event` targets the object identified by `key`, as found in
+/// [`Difference::object`]
+pub fn event_targets(event: &Event, key: &Value) -> bool {
+    // Object IDs serialise as `{"Type": <id>}`; map keys are just the inner ID
+    match serde_json::to_value(event.target) {
+        Ok(Value::Object(target)) => target.values().any(|id| id == key),
+        _ => false,
+    }
+}
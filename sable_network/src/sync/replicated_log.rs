@@ -4,7 +4,13 @@ use crate::prelude::*;
 use crate::rpc::*;
 
 use backoff::ExponentialBackoff;
-use std::{cell::RefCell, collections::HashMap, sync::Arc, sync::RwLock, time::Duration};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    sync::RwLock,
+    time::Duration,
+};
 use tokio::sync::Notify;
 use tokio::{
     select,
@@ -18,8 +24,14 @@ use tokio::{
 
 use thiserror::Error;
 
+use super::compat;
 use super::message::TargetedMessage;
 use super::network::NetworkResult;
+use super::routing::{RemoteRequestStats, RouteEntry, Router};
+
+/// How long to wait for a response to a remote server request, unless the caller
+/// specifies otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Error)]
 pub enum EventLogSaveError {
@@ -31,6 +43,15 @@ pub enum EventLogSaveError {
     UnknownError(#[from] anyhow::Error),
 }
 
+/// Determine whether a node whose state was saved at `saved_at` can rejoin by requesting
+/// only the events it missed, given that peers keep events for `event_expiry` seconds.
+///
+/// A margin is left so that events aren't pruned while the request is in flight.
+pub(crate) fn within_catch_up_window(saved_at: i64, now: i64, event_expiry: i64) -> bool {
+    let margin = (event_expiry / 10).max(5);
+    saved_at <= now && now - saved_at < event_expiry - margin
+}
+
 /// Saved state for a [ReplicatedEventLog], used to save and restore across an upgrade
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ReplicatedEventLogState {
@@ -39,6 +60,8 @@ pub struct ReplicatedEventLogState {
     server_tombstones: HashMap<ServerId, (ServerName, EpochId)>,
     network_state: GossipNetworkState,
     event_expiry: i64,
+    #[serde(default)]
+    snapshot_config: Option<SnapshotConfig>,
 }
 
 /// A replicated event log.
@@ -50,6 +73,10 @@ pub struct ReplicatedEventLog {
     task_state: Arc<Mutex<TaskState>>,
     new_event_send: UnboundedSender<EventLogMessage>,
     net: Arc<GossipNetwork>,
+    router: Arc<Router>,
+    request_stats: std::sync::Mutex<BTreeMap<ServerName, RemoteRequestStats>>,
+    snapshot_config: Option<SnapshotConfig>,
+    event_expiry: i64,
 }
 
 struct SharedState {
@@ -60,6 +87,7 @@ struct SharedState {
 
 struct TaskState {
     net: Arc<GossipNetwork>,
+    router: Arc<Router>,
     new_event_recv: UnboundedReceiver<EventLogMessage>,
     network_recv: UnboundedReceiver<Request>,
     log_recv: UnboundedReceiver<Event>,
@@ -76,7 +104,11 @@ enum EventLogMessage {
         Option<oneshot::Sender<EventId>>,
         Option<Arc<Notify>>,
     ),
-    TargetedMessage(TargetedMessage, oneshot::Sender<RemoteServerResponse>),
+    TargetedMessage(
+        TargetedMessage,
+        Duration,
+        oneshot::Sender<Result<RemoteServerResponse, NetworkError>>,
+    ),
 }
 
 impl ReplicatedEventLog {
@@ -104,6 +136,11 @@ impl ReplicatedEventLog {
         node_config: NodeConfig,
         eventlog_config: EventLogConfig,
     ) -> Self {
+        assert!(
+            server_id != compat::PLACEHOLDER_SERVER,
+            "Server ID {server_id:?} is reserved"
+        );
+
         let (log_send, log_recv) = unbounded_channel();
         let (net_send, net_recv) = unbounded_channel();
         let (new_event_send, new_event_recv) = unbounded_channel();
@@ -115,6 +152,8 @@ impl ReplicatedEventLog {
             net_send,
         ));
 
+        let router = Arc::new(Router::new(Arc::clone(&net), (server_id, epoch)));
+
         let shared_state = Arc::new(SharedState {
             server: (server_id, epoch),
             server_tombstones: RwLock::new(HashMap::new()),
@@ -126,6 +165,7 @@ impl ReplicatedEventLog {
 
         let task_state = Arc::new(Mutex::new(TaskState {
             net: Arc::clone(&net),
+            router: Arc::clone(&router),
             new_event_recv,
             network_recv: net_recv,
             log_recv,
@@ -138,7 +178,11 @@ impl ReplicatedEventLog {
             shared_state,
             task_state,
             net,
+            router,
+            request_stats: std::sync::Mutex::new(BTreeMap::new()),
             new_event_send,
+            snapshot_config: eventlog_config.snapshot,
+            event_expiry: eventlog_config.event_expiry,
         }
     }
 
@@ -165,6 +209,8 @@ impl ReplicatedEventLog {
             net_send,
         ));
 
+        let router = Arc::new(Router::new(Arc::clone(&net), state.server));
+
         let shared_state = Arc::new(SharedState {
             server: state.server,
             server_tombstones: RwLock::new(state.server_tombstones),
@@ -173,6 +219,7 @@ impl ReplicatedEventLog {
 
         let task_state = Arc::new(Mutex::new(TaskState {
             net: Arc::clone(&net),
+            router: Arc::clone(&router),
             new_event_recv,
             network_recv: net_recv,
             log_recv,
@@ -185,7 +232,11 @@ impl ReplicatedEventLog {
             shared_state,
             task_state,
             net,
+            router,
+            request_stats: std::sync::Mutex::new(BTreeMap::new()),
             new_event_send,
+            snapshot_config: state.snapshot_config,
+            event_expiry: state.event_expiry,
         }
     }
 
@@ -267,29 +318,88 @@ impl ReplicatedEventLog {
         self.net.enable_peer(&name);
     }
 
+    /// Add, update or (if `config` is `None`) remove a peer in the gossip network.
+    /// This should be called when the replicated peer list changes.
+    pub fn update_peer(&self, name: &ServerName, config: Option<PeerConfig>, enabled: bool) {
+        match config {
+            Some(config) => self.net.add_peer(config, enabled),
+            None => self.net.remove_peer(name),
+        }
+    }
+
+    /// The current list of peers in the gossip network
+    pub fn peers(&self) -> Vec<PeerConfig> {
+        self.net.peers()
+    }
+
     /// Send a request to another server in the network, and wait for the response
     pub async fn send_remote_request(
         &self,
         target: ServerName,
         request: RemoteServerRequestType,
+    ) -> Result<RemoteServerResponse, NetworkError> {
+        self.send_remote_request_with_timeout(target, request, DEFAULT_REQUEST_TIMEOUT)
+            .await
+    }
+
+    /// Send a request to another server in the network, and wait up to `timeout` for
+    /// the response. Returns [`NetworkError::Timeout`] if none arrives in time.
+    pub async fn send_remote_request_with_timeout(
+        &self,
+        target: ServerName,
+        request: RemoteServerRequestType,
+        timeout: Duration,
     ) -> Result<RemoteServerResponse, NetworkError> {
         let (sender, receiver) = oneshot::channel();
+        let started = tokio::time::Instant::now();
 
         let targeted_message = TargetedMessage {
             source: self.net.me().name,
             target,
             via: Vec::new(),
             content: request,
+            time_remaining: None,
         };
         self.new_event_send
-            .send(EventLogMessage::TargetedMessage(targeted_message, sender))
+            .send(EventLogMessage::TargetedMessage(
+                targeted_message,
+                timeout,
+                sender,
+            ))
             .expect("Couldn't send to event log task");
 
         // If this fails, it's because the Sender dropped, almost certainly because the sync task
         // gave up on waiting for a response
-        receiver
-            .await
-            .map_err(|_| NetworkError::InternalError("channel receive error".to_string()))
+        let result = match receiver.await {
+            Ok(result) => result,
+            Err(_) => Err(NetworkError::InternalError(
+                "channel receive error".to_string(),
+            )),
+        };
+
+        self.request_stats
+            .lock()
+            .unwrap()
+            .entry(target)
+            .or_default()
+            .record(started.elapsed(), &result);
+
+        result
+    }
+
+    /// Statistics about remote requests sent from this server, by target server
+    pub fn remote_request_statistics(&self) -> BTreeMap<ServerName, RemoteRequestStats> {
+        self.request_stats.lock().unwrap().clone()
+    }
+
+    /// Report the health of each peer connection
+    pub fn peer_statistics(&self) -> Vec<PeerStatistics> {
+        self.net.peer_statistics()
+    }
+
+    /// The known routes to other servers for targeted messages
+    pub fn routing_table(&self) -> Vec<RouteEntry> {
+        self.router.routing_table()
     }
 
     /// Run and wait for the initial synchronisation to the network.
@@ -368,7 +478,7 @@ impl ReplicatedEventLog {
                 source_server: self.shared_state.server,
                 content: MessageDetail::GetNetworkState,
             };
-            Ok(self.net.send_and_process(peer, msg, sender.clone()).await?)
+            Ok(self.net.send_and_process(&peer, msg, sender.clone()).await?)
         }).await.expect("start_sync_to_network returned an error")
     }
 
@@ -384,6 +494,149 @@ impl ReplicatedEventLog {
         self.shared_state.log.read().unwrap()
     }
 
+    /// The configuration for on-disk snapshots, if enabled
+    pub fn snapshot_config(&self) -> Option<&SnapshotConfig> {
+        self.snapshot_config.as_ref()
+    }
+
+    /// Copy the events currently held in the log, for inclusion in a snapshot
+    pub fn snapshot_events(&self) -> Vec<Event> {
+        self.shared_state
+            .log
+            .read()
+            .unwrap()
+            .events()
+            .cloned()
+            .collect()
+    }
+
+    /// Prepare the log to resume from a snapshot whose network state is `net`.
+    ///
+    /// Only those events which had already been applied to `net` are loaded; any
+    /// others will be requested again by [`catch_up`](Self::catch_up).
+    pub fn restore_snapshot(&self, net: &Network, events: Vec<Event>) {
+        {
+            let mut log = self.shared_state.log.write().unwrap();
+            log.set_clock(net.clock().clone());
+            log.load_history(events.into_iter().filter(|e| net.clock().contains(e.id)));
+        }
+
+        for peer in net.sync_peers() {
+            self.net.add_peer(peer.into(), false);
+        }
+        for server in net.servers() {
+            self.enable_server(*server.name(), server.id());
+        }
+    }
+
+    /// Request every event that's newer than the log's current clock from a peer,
+    /// and add them to the log. Used to catch up after restoring from a snapshot,
+    /// instead of downloading the entire network state.
+    #[tracing::instrument(skip(self))]
+    pub async fn catch_up(&self) -> Result<(), NetworkError> {
+        let backoff = ExponentialBackoff {
+            initial_interval: Duration::from_millis(100),
+            max_elapsed_time: Some(Duration::from_secs(30)),
+            ..ExponentialBackoff::default()
+        };
+
+        backoff::future::retry(backoff, || async {
+            let peer = self.net.choose_any_peer().ok_or_else(|| {
+                backoff::Error::permanent(NetworkError::InternalError(
+                    "No peer available to catch up from".to_string(),
+                ))
+            })?;
+
+            let clock = self.event_log().clock().clone();
+            tracing::debug!("Requesting events since {:?} from {:?}", clock, peer);
+
+            self.request_events_since(&peer, clock)
+                .await
+                .map_err(|e| match e {
+                    // Another peer is unlikely to have kept events for longer
+                    NetworkError::EventsPruned => backoff::Error::permanent(e),
+                    e => backoff::Error::transient(e),
+                })
+        })
+        .await
+    }
+
+    async fn request_events_since(
+        &self,
+        peer: &PeerConfig,
+        clock: EventClock,
+    ) -> Result<(), NetworkError> {
+        let (send, mut recv) = unbounded_channel();
+        let msg = Message {
+            source_server: self.shared_state.server,
+            content: MessageDetail::SyncRequest(clock),
+        };
+        let handle = self.net.send_and_process(peer, msg, send).await?;
+        let mut result = Ok(());
+
+        while let Some(req) = recv.recv().await {
+            match req.message.content {
+                MessageDetail::BulkEvents(events) => {
+                    tracing::debug!("Caught up with {} events", events.len());
+                    let mut log = self.shared_state.log.write().unwrap();
+                    for event in events.into_iter().filter(|e| !compat::is_placeholder(e)) {
+                        log.add(event);
+                    }
+                }
+                MessageDetail::SyncUnavailable => {
+                    result = Err(NetworkError::EventsPruned);
+                }
+                _ => continue,
+            }
+
+            let done = Message {
+                source_server: self.shared_state.server,
+                content: MessageDetail::Done,
+            };
+            let _ = req.response.send(done).await;
+        }
+
+        handle.await??;
+        result
+    }
+
+    /// Rejoin the network after a restart.
+    ///
+    /// If `snapshot` was taken recently enough that peers should still hold every
+    /// event since, it is restored and only the missing events are requested.
+    /// Otherwise, or if that fails, the full network state is downloaded as for
+    /// [`sync_to_network`](Self::sync_to_network).
+    #[tracing::instrument(skip_all)]
+    pub async fn rejoin_network(&self, snapshot: Option<Snapshot>) -> Box<Network> {
+        let Some(snapshot) = snapshot else {
+            return self.sync_to_network().await;
+        };
+
+        if !within_catch_up_window(snapshot.timestamp, crate::utils::now(), self.event_expiry) {
+            tracing::info!("Snapshot is too old to catch up from; requesting full state");
+            return self.sync_to_network().await;
+        }
+
+        self.restore_snapshot(&snapshot.network, snapshot.events);
+
+        match self.catch_up().await {
+            Ok(()) if self.event_log().pending_count() == 0 => Box::new(snapshot.network),
+            Ok(()) => {
+                tracing::warn!(
+                    "Catch-up left events with missing dependencies; requesting full state"
+                );
+                self.sync_to_network().await
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Couldn't catch up from snapshot ({}); requesting full state",
+                    e
+                );
+                self.sync_to_network().await
+            }
+        }
+    }
+
     pub fn save_state(self) -> Result<ReplicatedEventLogState, EventLogSaveError> {
         // This set of structs takes a bit of untangling to deconstruct.
         // First, extract the task state from the mutex. If this fails (because there's
@@ -410,6 +663,7 @@ impl ReplicatedEventLog {
             server_tombstones,
             network_state: self.net.save_state(),
             event_expiry: task_state.event_expiry,
+            snapshot_config: self.snapshot_config,
         })
     }
 }
@@ -472,30 +726,18 @@ impl TaskState {
                             }
                             self.net.propagate(&self.message(MessageDetail::NewEvent(event))).await
                         },
-                        Some(EventLogMessage::TargetedMessage(message, sender)) =>
+                        Some(EventLogMessage::TargetedMessage(message, timeout, sender)) =>
                         {
-                            let timeout = tokio::time::timeout(Duration::from_secs(5),
-                                                               self.send_targeted_message(message));
-
-                            match timeout.await
-                            {
-                                Ok(Ok(response)) =>
-                                {
-                                    // If the receiver hung up, the response isn't relevant so don't do anything
-                                    let _ = sender.send(response);
-                                }
-                                Ok(Err(e)) =>
-                                {
+                            // Routing can take a while, so don't hold up the sync task
+                            let router = Arc::clone(&self.router);
+                            tokio::spawn(async move {
+                                let result = router.send(message, timeout).await;
+                                if let Err(e) = &result {
                                     tracing::error!("Error sending out remote server message: {}", e);
-                                    // As above
-                                    let _ = sender.send(RemoteServerResponse::Error("Network error".to_string()));
                                 }
-                                Err(_) =>
-                                {
-                                    // If we get into this branch it's because the timeout timed out
-                                    let _ = sender.send(RemoteServerResponse::Error("Response timeout".to_string()));
-                                }
-                            }
+                                // If the receiver hung up, the response isn't relevant so don't do anything
+                                let _ = sender.send(result);
+                            });
                         }
                         None => break
                     }
@@ -535,6 +777,11 @@ impl TaskState {
         mut should_propagate: bool,
         response: &Sender<Message>,
     ) -> bool {
+        if compat::is_placeholder(&evt) {
+            tracing::trace!("Ignoring placeholder event {:?}", evt.id);
+            return true;
+        }
+
         let mut is_done = true;
         // Calling reserve() here means we don't need to `await` the send operation while holding the lock on `log`
         let response = response.reserve().await;
@@ -588,80 +835,6 @@ impl TaskState {
         None
     }
 
-    async fn send_targeted_message(
-        &self,
-        mut detail: TargetedMessage,
-    ) -> Result<RemoteServerResponse, NetworkError> {
-        let (sender, mut receiver) = unbounded_channel();
-
-        // First, try to send directly to the target
-        if let Some(target) = self.net.find_peer(&detail.target) {
-            tracing::debug!(?target, ?detail, "Found target peer, sending message");
-
-            // If this succeeds, then we could connect successfully to the target server, so any error that occurs
-            // later in the process won't be solved by re-routing
-            let send_result = self
-                .net
-                .send_and_process(
-                    target,
-                    self.message(MessageDetail::TargetedMessage(detail.clone())),
-                    sender.clone(),
-                )
-                .await;
-
-            tracing::debug!(?send_result, "Got send result");
-
-            if send_result.is_ok() {
-                while let Some(response) = receiver.recv().await {
-                    tracing::debug!(?response, "Got targeted message response");
-                    if let MessageDetail::TargetedMessageResponse(resp) = response.message.content {
-                        return Ok(resp);
-                    }
-                }
-                // The other end sent back something that's not the response we were expecting. Raise it as an internal error
-                return Err(NetworkError::InternalError(
-                    "Unexpected response type from targeted message".to_string(),
-                ));
-            }
-        }
-
-        // If the above didn't work, then pick another server (that hasn't already seen it)
-        // to try to send it along
-
-        // Make sure it doesn't come back to us
-        detail.via.push(self.net.me().name);
-
-        // Keep going until we succeed in sending it somewhere
-        while let Some(peer) = self.net.choose_peer_except(&detail.via) {
-            // If this succeeds, then we could connect successfully to the target server, so any error that occurs
-            // later in the process won't be solved by re-routing
-            if self
-                .net
-                .send_and_process(
-                    peer,
-                    self.message(MessageDetail::TargetedMessage(detail.clone())),
-                    sender.clone(),
-                )
-                .await
-                .is_ok()
-            {
-                while let Some(response) = receiver.recv().await {
-                    if let MessageDetail::TargetedMessageResponse(resp) = response.message.content {
-                        return Ok(resp);
-                    }
-                }
-                // The other end sent back something that's not the response we were expecting. Raise it as an internal error
-                return Err(NetworkError::InternalError(
-                    "Unexpected response type from targeted message".to_string(),
-                ));
-            }
-        }
-
-        Err(NetworkError::InternalError(
-            "Ran out of potential peers to route targeted message".to_string(),
-        ))
-    }
-
     #[tracing::instrument(skip(self))]
     async fn handle_network_request(&mut self, req: Request) {
         // If this is a server we've seen quit, don't accept any events from it
@@ -700,36 +873,52 @@ impl TaskState {
                 }
             }
             MessageDetail::SyncRequest(clock) => {
-                let new_events: Vec<Event> = self
-                    .shared_state
-                    .log
-                    .read()
-                    .unwrap()
-                    .get_since(clock)
-                    .cloned()
-                    .collect();
+                let response = {
+                    let log = self.shared_state.log.read().unwrap();
+                    if log.can_serve_since(&clock) {
+                        MessageDetail::BulkEvents(log.get_since(clock).cloned().collect())
+                    } else {
+                        tracing::debug!("Can't serve sync request from {:?}", clock);
+                        MessageDetail::SyncUnavailable
+                    }
+                };
 
-                if let Err(e) = req
-                    .response
-                    .send(self.message(MessageDetail::BulkEvents(new_events)))
-                    .await
-                {
+                if let Err(e) = req.response.send(self.message(response)).await {
                     tracing::error!("Error sending response to network message: {}", e);
                 }
             }
             MessageDetail::GetEvent(ids) => {
                 tracing::debug!("Got request for events {:?}", ids);
-                let mut events = Vec::new();
-
-                for id in ids.iter() {
-                    if let Some(new_event) = self.shared_state.log.read().unwrap().get(id) {
-                        events.push(new_event.clone());
+                let response = {
+                    let log = self.shared_state.log.read().unwrap();
+
+                    if ids.iter().any(|id| log.is_pruned(id)) {
+                        // The requester will never be able to process whatever depends on
+                        // these, so tell it to fetch the full state instead
+                        tracing::debug!("Can't serve pruned events {:?}", ids);
+                        MessageDetail::SyncUnavailable
+                    } else {
+                        let events: Vec<_> =
+                            ids.iter().filter_map(|id| log.get(id)).cloned().collect();
+                        tracing::debug!("Sending events {:?}", events);
+                        MessageDetail::BulkEvents(events)
                     }
+                };
+
+                if let Err(e) = req.response.send(self.message(response)).await {
+                    tracing::error!("Error sending response to network message: {}", e);
                 }
-                tracing::debug!("Sending events {:?}", events);
+            }
+            MessageDetail::SyncUnavailable => {
+                // A peer couldn't supply events that we're waiting for, because they've
+                // been pruned. Importing the current state will resolve anything pending.
+                tracing::warn!(
+                    "Events requested from {} are no longer available; requesting network state",
+                    req.received_from
+                );
                 if let Err(e) = req
                     .response
-                    .send(self.message(MessageDetail::BulkEvents(events)))
+                    .send(self.message(MessageDetail::GetNetworkState))
                     .await
                 {
                     tracing::error!("Error sending response to network message: {}", e);
@@ -767,6 +956,11 @@ impl TaskState {
                         .set_clock(net.clock().clone());
                 }
 
+                // Pick up any peers that were added to the network at runtime
+                for peer in net.sync_peers() {
+                    self.net.add_peer(peer.into(), false);
+                }
+
                 // Then reset our list of active peers to those that are active in the incoming state
                 for server in net.servers() {
                     self.net.enable_peer(server.name());
@@ -783,44 +977,61 @@ impl TaskState {
                 }
             }
             MessageDetail::TargetedMessage(detail) => {
-                let response = if detail.target == self.net.me().name {
-                    // We're the target. Handle it
-                    let (sender, receiver) = oneshot::channel();
-                    let request = RemoteServerRequest {
-                        req: detail.content,
-                        response: sender,
-                    };
-
-                    if let Err(e) = self
-                        .server_send
-                        .send(NetworkMessage::RemoteServerRequest(request))
-                    {
-                        tracing::error!("Error sending request to server task: {}", e);
-                        Ok(RemoteServerResponse::Error(
-                            "Couldn't send request to server task".to_string(),
-                        ))
+                let server_send = self.server_send.clone();
+                let router = Arc::clone(&self.router);
+                let me = self.net.me().name;
+                let source_server = self.shared_state.server;
+                let response_channel = req.response;
+                // Once the source server has given up, there's no point carrying on
+                let timeout = detail.time_remaining.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+
+                // Waiting for the response can take a while, so don't hold up the sync task
+                tokio::spawn(async move {
+                    let response = if detail.target == me {
+                        // We're the target. Handle it
+                        let (sender, receiver) = oneshot::channel();
+                        let request = RemoteServerRequest {
+                            req: detail.content,
+                            response: sender,
+                        };
+
+                        if let Err(e) =
+                            server_send.send(NetworkMessage::RemoteServerRequest(request))
+                        {
+                            tracing::error!("Error sending request to server task: {}", e);
+                            Ok(RemoteServerResponse::Error(
+                                "Couldn't send request to server task".to_string(),
+                            ))
+                        } else {
+                            match tokio::time::timeout(timeout, receiver).await {
+                                Ok(response) => response.map_err(|_| {
+                                    NetworkError::InternalError(
+                                        "Couldn't send to server task".to_string(),
+                                    )
+                                }),
+                                Err(_) => Err(NetworkError::Timeout),
+                            }
+                        }
                     } else {
-                        receiver.await.map_err(|_| {
-                            NetworkError::InternalError("Couldn't send to server task".to_string())
-                        })
-                    }
-                } else {
-                    // We're not the target. Pass it along
-                    self.send_targeted_message(detail).await
-                };
+                        // We're not the target. Pass it along
+                        router.send(detail, timeout).await
+                    };
 
-                match response {
-                    Ok(response) => {
-                        // This will only fail if the receiver hung up, in which case the response isn't relevant any more anyway
-                        let _ = req
-                            .response
-                            .send(self.message(MessageDetail::TargetedMessageResponse(response)))
-                            .await;
-                    }
-                    Err(e) => {
-                        tracing::error!("Error handling targeted message: {}", e);
+                    match response {
+                        Ok(response) => {
+                            // This will only fail if the receiver hung up, in which case the response isn't relevant any more anyway
+                            let _ = response_channel
+                                .send(Message {
+                                    source_server,
+                                    content: MessageDetail::TargetedMessageResponse(response),
+                                })
+                                .await;
+                        }
+                        Err(e) => {
+                            tracing::error!("Error handling targeted message: {}", e);
+                        }
                     }
-                }
+                });
             }
             MessageDetail::MessageRejected => {
                 // If the target server rejected one of our sync messages, then they'll continue
@@ -0,0 +1,261 @@
+//! Delivery of [`TargetedMessage`]s to their destination server
+//!
+//! A targeted message is sent directly to its target if that server is one of our
+//! peers. Otherwise it's handed to a peer which can forward it; whichever peers have
+//! recently delivered messages to a given target are preferred, and others are only
+//! tried if none of those can be reached.
+
+use super::message::{Message, MessageDetail, TargetedMessage};
+use super::network::{GossipNetwork, NetworkError};
+use super::PeerConfig;
+use crate::id::{EpochId, ServerId};
+use crate::rpc::RemoteServerResponse;
+use crate::validated::ServerName;
+
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::mpsc::unbounded_channel, time::Instant};
+
+/// Routes which haven't delivered a message for this long (in seconds) are no
+/// longer preferred
+const ROUTE_EXPIRY: i64 = 600;
+
+/// What we know about reaching one target server through one peer
+#[derive(Debug, Clone, Default)]
+struct PathStats {
+    last_success: Option<i64>,
+    last_failure: Option<i64>,
+    latency: Option<Duration>,
+    consecutive_failures: u32,
+}
+
+/// An entry in the routing table, as reported to the management service
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteEntry {
+    pub target: ServerName,
+    pub next_hop: ServerName,
+    pub last_success: Option<i64>,
+    pub last_failure: Option<i64>,
+    pub latency_ms: Option<u128>,
+    pub consecutive_failures: u32,
+}
+
+/// Outcomes of the remote requests sent from this server to one target
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RemoteRequestStats {
+    pub requests: u64,
+    pub failures: u64,
+    pub timeouts: u64,
+    /// Total time spent waiting for responses, in seconds
+    pub latency_seconds_sum: f64,
+    pub latency_seconds_max: f64,
+}
+
+impl RemoteRequestStats {
+    pub(super) fn record<T>(&mut self, latency: Duration, result: &Result<T, NetworkError>) {
+        let seconds = latency.as_secs_f64();
+
+        self.requests += 1;
+        self.latency_seconds_sum += seconds;
+        self.latency_seconds_max = self.latency_seconds_max.max(seconds);
+
+        match result {
+            Ok(_) => {}
+            Err(NetworkError::Timeout) => self.timeouts += 1,
+            Err(_) => self.failures += 1,
+        }
+    }
+}
+
+pub(super) struct Router {
+    net: Arc<GossipNetwork>,
+    server: (ServerId, EpochId),
+    routes: Mutex<HashMap<ServerName, HashMap<ServerName, PathStats>>>,
+}
+
+impl Router {
+    pub fn new(net: Arc<GossipNetwork>, server: (ServerId, EpochId)) -> Self {
+        Self {
+            net,
+            server,
+            routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A copy of the current routing table
+    pub fn routing_table(&self) -> Vec<RouteEntry> {
+        let routes = self.routes.lock().unwrap();
+        let mut ret: Vec<_> = routes
+            .iter()
+            .flat_map(|(target, paths)| {
+                paths.iter().map(|(next_hop, stats)| RouteEntry {
+                    target: *target,
+                    next_hop: *next_hop,
+                    last_success: stats.last_success,
+                    last_failure: stats.last_failure,
+                    latency_ms: stats.latency.map(|l| l.as_millis()),
+                    consecutive_failures: stats.consecutive_failures,
+                })
+            })
+            .collect();
+
+        ret.sort_by(|a, b| (&a.target, &a.next_hop).cmp(&(&b.target, &b.next_hop)));
+        ret
+    }
+
+    /// Deliver a targeted message and wait for the response, giving up after `timeout`.
+    ///
+    /// The time left is passed along with the message, so that servers relaying it
+    /// don't keep trying after we've given up.
+    pub async fn send(
+        &self,
+        mut detail: TargetedMessage,
+        timeout: Duration,
+    ) -> Result<RemoteServerResponse, NetworkError> {
+        let deadline = Instant::now() + timeout;
+
+        // Make sure it doesn't come back to us
+        let me = self.net.me().name;
+        if !detail.via.contains(&me) {
+            detail.via.push(me);
+        }
+
+        for peer in self.candidates(&detail) {
+            tracing::debug!(peer=?peer.name(), target=?detail.target, "Attempting to route targeted message");
+
+            let started = Instant::now();
+            detail.time_remaining = Some(deadline.saturating_duration_since(started));
+            let attempt = self.send_via(&peer, &detail);
+
+            match tokio::time::timeout_at(deadline, attempt).await {
+                Ok(Ok(response)) => {
+                    self.record(&detail.target, peer.name(), Ok(started.elapsed()));
+                    return Ok(response);
+                }
+                // Couldn't reach this peer at all, so try the next
+                Ok(Err(Attempt::Unreachable(e))) => {
+                    tracing::debug!(peer=?peer.name(), "Couldn't send targeted message: {}", e);
+                    self.record(&detail.target, peer.name(), Err(()));
+                }
+                // The message was sent, so any error from here on won't be solved by
+                // re-routing, and retrying could cause the request to be processed twice
+                Ok(Err(Attempt::Failed(e))) => {
+                    self.record(&detail.target, peer.name(), Err(()));
+                    return Err(e);
+                }
+                Err(_) => {
+                    self.record(&detail.target, peer.name(), Err(()));
+                    return Err(NetworkError::Timeout);
+                }
+            }
+        }
+
+        Err(NetworkError::InternalError(
+            "Ran out of potential peers to route targeted message".to_string(),
+        ))
+    }
+
+    /// List the peers to try, in order of preference
+    fn candidates(&self, detail: &TargetedMessage) -> Vec<PeerConfig> {
+        let mut peers: Vec<_> = self
+            .net
+            .active_peers()
+            .into_iter()
+            .filter(|p| !detail.via.contains(p.name()))
+            .collect();
+        peers.shuffle(&mut rand::thread_rng());
+
+        let now = crate::utils::now();
+        let routes = self.routes.lock().unwrap();
+        let known = routes.get(&detail.target);
+
+        // Direct delivery first, then known-good routes by reliability and speed, then
+        // everything else
+        peers.sort_by_key(|p| {
+            if p.name() == &detail.target {
+                return (0, 0, Duration::ZERO);
+            }
+            match known.and_then(|paths| paths.get(p.name())) {
+                Some(stats) if stats.last_success.is_some_and(|ts| now - ts < ROUTE_EXPIRY) => (
+                    1,
+                    stats.consecutive_failures,
+                    stats.latency.unwrap_or(Duration::MAX),
+                ),
+                _ => (2, 0, Duration::ZERO),
+            }
+        });
+
+        peers
+    }
+
+    async fn send_via(
+        &self,
+        peer: &PeerConfig,
+        detail: &TargetedMessage,
+    ) -> Result<RemoteServerResponse, Attempt> {
+        let (sender, mut receiver) = unbounded_channel();
+
+        self.net
+            .send_and_process(
+                peer,
+                self.message(MessageDetail::TargetedMessage(detail.clone())),
+                sender,
+            )
+            .await
+            .map_err(Attempt::Unreachable)?;
+
+        while let Some(response) = receiver.recv().await {
+            tracing::debug!(?response, "Got targeted message response");
+            if let MessageDetail::TargetedMessageResponse(resp) = response.message.content {
+                // Let the other side know the conversation is finished
+                let _ = response
+                    .response
+                    .send(self.message(MessageDetail::Done))
+                    .await;
+                return Ok(resp);
+            }
+        }
+
+        // The other end sent back something that's not the response we were expecting
+        Err(Attempt::Failed(NetworkError::InternalError(
+            "Unexpected response type from targeted message".to_string(),
+        )))
+    }
+
+    fn record(&self, target: &ServerName, peer: &ServerName, result: Result<Duration, ()>) {
+        let mut routes = self.routes.lock().unwrap();
+        let stats = routes.entry(*target).or_default().entry(*peer).or_default();
+        let now = crate::utils::now();
+
+        match result {
+            Ok(latency) => {
+                stats.last_success = Some(now);
+                stats.latency = Some(latency);
+                stats.consecutive_failures = 0;
+            }
+            Err(()) => {
+                stats.last_failure = Some(now);
+                stats.consecutive_failures += 1;
+            }
+        }
+    }
+
+    fn message(&self, content: MessageDetail) -> Message {
+        Message {
+            source_server: self.server,
+            content,
+        }
+    }
+}
+
+enum Attempt {
+    /// The message couldn't be sent to the chosen peer
+    Unreachable(NetworkError),
+    /// The message was sent, but no valid response came back
+    Failed(NetworkError),
+}
@@ -0,0 +1,141 @@
+//! Crash-safe on-disk snapshots of network state and the event log
+
+use super::SnapshotConfig;
+use crate::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const SNAPSHOT_SUFFIX: &str = ".json";
+
+/// A point-in-time copy of a node's view of the network, along with the
+/// events it had processed
+#[derive(Debug, Deserialize)]
+pub struct Snapshot {
+    /// The server which wrote the snapshot
+    pub server: ServerName,
+    /// When the snapshot was taken
+    pub timestamp: i64,
+    /// Network state at the time of the snapshot
+    pub network: Network,
+    /// Unpruned events from the event log
+    pub events: Vec<Event>,
+}
+
+/// Borrowed form of [`Snapshot`], so that writing doesn't need to copy the network state
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    server: ServerName,
+    timestamp: i64,
+    network: &'a Network,
+    events: &'a [Event],
+}
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl SnapshotConfig {
+    /// Write a new snapshot, then remove any beyond the configured number to keep.
+    ///
+    /// The snapshot is written to a temporary file and renamed into place, so a crash
+    /// part-way through will never leave a truncated snapshot behind.
+    pub fn write(
+        &self,
+        server: ServerName,
+        timestamp: i64,
+        network: &Network,
+        events: &[Event],
+    ) -> Result<PathBuf, SnapshotError> {
+        fs::create_dir_all(&self.directory)?;
+
+        let name = format!("{SNAPSHOT_PREFIX}{timestamp:020}{SNAPSHOT_SUFFIX}");
+        let path = self.directory.join(&name);
+        let tmp_path = self.directory.join(format!("{name}.tmp"));
+
+        let snapshot = SnapshotRef {
+            server,
+            timestamp,
+            network,
+            events,
+        };
+
+        {
+            let file = File::create(&tmp_path)?;
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer(&mut writer, &snapshot)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+        }
+
+        fs::rename(&tmp_path, &path)?;
+        // Make sure the rename itself is durable
+        File::open(&self.directory)?.sync_all()?;
+
+        for (_, old) in self.list()?.into_iter().rev().skip(self.keep.max(1)) {
+            if let Err(e) = fs::remove_file(&old) {
+                tracing::warn!("Couldn't remove old snapshot {}: {}", old.display(), e);
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Load the most recent readable snapshot, if there is one
+    pub fn load_latest(&self) -> Result<Option<Snapshot>, SnapshotError> {
+        if !self.directory.exists() {
+            return Ok(None);
+        }
+
+        for (_, path) in self.list()?.into_iter().rev() {
+            match Self::load(&path) {
+                Ok(snapshot) => {
+                    tracing::info!("Loaded snapshot {}", path.display());
+                    return Ok(Some(snapshot));
+                }
+                Err(e) => {
+                    tracing::warn!("Couldn't load snapshot {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn load(path: &Path) -> Result<Snapshot, SnapshotError> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// List existing snapshots, oldest first
+    fn list(&self) -> Result<Vec<(i64, PathBuf)>, SnapshotError> {
+        let mut snapshots = Vec::new();
+
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            let timestamp = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(SNAPSHOT_PREFIX))
+                .and_then(|name| name.strip_suffix(SNAPSHOT_SUFFIX))
+                .and_then(|ts| ts.parse().ok());
+
+            if let Some(timestamp) = timestamp {
+                snapshots.push((timestamp, path));
+            }
+        }
+
+        snapshots.sort();
+        Ok(snapshots)
+    }
+}
@@ -157,3 +157,923 @@ fn get_since() {
     assert_eq!(entries[0].id, e2.id);
     assert_eq!(entries[1].id, e3.id);
 }
+
+#[test]
+fn prune_keeps_recent_events() {
+    let server_id = ServerId::new(1);
+    let idgen = ObjectIdGenerator::new(server_id);
+    let mut log = EventLog::new(idgen, None);
+
+    let uid = UserId::new(Snowflake::from_parts(server_id, 0, 1));
+
+    let mut e1 = log.create(
+        uid,
+        details::UserQuit {
+            message: "aaa".to_string(),
+        },
+    );
+    e1.timestamp = 100;
+    log.add(e1.clone());
+
+    let mut e2 = log.create(
+        uid,
+        details::UserQuit {
+            message: "bbb".to_string(),
+        },
+    );
+    e2.timestamp = 200;
+    log.add(e2.clone());
+
+    log.prune_events_before(150);
+
+    let entries: Vec<&Event> = log.get_since(EventClock::new()).collect();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, e2.id);
+}
+
+#[test]
+fn can_serve_since_pruned() {
+    let server_id = ServerId::new(1);
+    let idgen = ObjectIdGenerator::new(server_id);
+    let mut log = EventLog::new(idgen, None);
+
+    let uid = UserId::new(Snowflake::from_parts(server_id, 0, 1));
+
+    let mut e1 = log.create(
+        uid,
+        details::UserQuit {
+            message: "aaa".to_string(),
+        },
+    );
+    e1.timestamp = 100;
+    log.add(e1.clone());
+
+    let after_e1 = log.clock().clone();
+
+    let mut e2 = log.create(
+        uid,
+        details::UserQuit {
+            message: "bbb".to_string(),
+        },
+    );
+    e2.timestamp = 200;
+    log.add(e2.clone());
+
+    assert!(log.can_serve_since(&EventClock::new()));
+
+    log.prune_events_before(150);
+
+    assert!(!log.can_serve_since(&EventClock::new()));
+    assert!(log.can_serve_since(&after_e1));
+    assert!(log.can_serve_since(log.clock()));
+
+    // The pruned state must survive a save and restore
+    let log = EventLog::restore(log.save_state(), None);
+    assert!(!log.can_serve_since(&EventClock::new()));
+    assert!(log.can_serve_since(&after_e1));
+}
+
+fn quit_events(log: &mut EventLog, count: usize) -> Vec<Event> {
+    let uid = UserId::new(Snowflake::from_parts(ServerId::new(1), 0, 1));
+
+    (0..count)
+        .map(|i| {
+            let event = log.create(
+                uid,
+                details::UserQuit {
+                    message: format!("quit {i}"),
+                },
+            );
+            log.add(event.clone());
+            event
+        })
+        .collect()
+}
+
+#[test]
+fn restored_log_refuses_sync_before_snapshot() {
+    let server_id = ServerId::new(1);
+    let mut origin = EventLog::new(ObjectIdGenerator::new(server_id), None);
+    let events = quit_events(&mut origin, 3);
+
+    let mut after_first = EventClock::new();
+    after_first.update_with_id(events[0].id);
+    let mut snapshot_clock = after_first.clone();
+    snapshot_clock.update_with_id(events[1].id);
+
+    // Restore as ReplicatedEventLog::restore_snapshot does
+    let mut log = EventLog::new(ObjectIdGenerator::new(ServerId::new(2)), None);
+    log.set_clock(snapshot_clock.clone());
+    log.load_history(events[..2].iter().cloned());
+    log.add(events[2].clone());
+
+    assert!(!log.can_serve_since(&EventClock::new()));
+    assert!(!log.can_serve_since(&after_first));
+    assert!(log.can_serve_since(&snapshot_clock));
+    assert_eq!(
+        log.get_since(snapshot_clock)
+            .map(|e| e.id)
+            .collect::<Vec<_>>(),
+        vec![events[2].id]
+    );
+
+    // Events loaded from the snapshot can still be fetched individually
+    assert!(!log.is_pruned(&events[0].id));
+    let mut empty = EventLog::new(ObjectIdGenerator::new(ServerId::new(2)), None);
+    empty.set_clock(log.clock().clone());
+    assert!(empty.is_pruned(&events[0].id));
+}
+
+#[test]
+fn set_clock_resolves_pending_events() {
+    let server_id = ServerId::new(1);
+    let mut origin = EventLog::new(ObjectIdGenerator::new(server_id), None);
+    let events = quit_events(&mut origin, 3);
+
+    let (sender, mut receiver) = unbounded_channel::<Event>();
+    let mut log = EventLog::new(ObjectIdGenerator::new(ServerId::new(2)), Some(sender));
+
+    // Neither can be processed without the first event
+    log.add(events[1].clone());
+    log.add(events[2].clone());
+    assert_eq!(log.pending_count(), 2);
+
+    // Import a state which already includes the second event
+    let mut imported = EventClock::new();
+    imported.update_with_id(events[1].id);
+    log.set_clock(imported);
+
+    assert_eq!(log.pending_count(), 0);
+    assert_eq!(
+        drain_from(&mut receiver)
+            .iter()
+            .map(|e| e.id)
+            .collect::<Vec<_>>(),
+        vec![events[2].id]
+    );
+}
+
+#[test]
+fn catch_up_window() {
+    use super::replicated_log::within_catch_up_window;
+
+    let expiry = 1800;
+
+    assert!(within_catch_up_window(1000, 1000, expiry));
+    assert!(within_catch_up_window(1000, 1000 + 600, expiry));
+    // Too close to expiry to be safe
+    assert!(!within_catch_up_window(1000, 1000 + expiry - 1, expiry));
+    assert!(!within_catch_up_window(1000, 1000 + expiry, expiry));
+    assert!(!within_catch_up_window(1000, 1000 + 10 * expiry, expiry));
+    // A snapshot from the future means the clock can't be trusted
+    assert!(!within_catch_up_window(2000, 1000, expiry));
+}
+
+fn bulk_events(count: usize) -> Message {
+    let server_id = ServerId::new(1);
+    let idgen = ObjectIdGenerator::new(server_id);
+    let log = EventLog::new(idgen, None);
+
+    let uid = UserId::new(Snowflake::from_parts(server_id, 0, 1));
+    let events = (0..count)
+        .map(|i| {
+            log.create(
+                uid,
+                details::UserQuit {
+                    message: format!("quit message {i}"),
+                },
+            )
+        })
+        .collect();
+
+    Message {
+        source_server: (server_id, 1),
+        content: MessageDetail::BulkEvents(events),
+    }
+}
+
+#[test]
+fn wire_format_round_trip() {
+    use wire::*;
+
+    for encoding in [WireEncoding::Json, WireEncoding::Bincode] {
+        for compression in [false, true] {
+            let format = WireFormat {
+                version: PROTOCOL_VERSION,
+                encoding,
+                compression,
+            };
+
+            for count in [1, 100] {
+                let message = bulk_events(count);
+                let encoded = format.encode(&message).unwrap();
+                let decoded: Message = format.decode(&encoded).unwrap();
+
+                let MessageDetail::BulkEvents(original) = message.content else {
+                    unreachable!()
+                };
+                let MessageDetail::BulkEvents(decoded) = decoded.content else {
+                    panic!("Decoded wrong message type");
+                };
+                assert_eq!(
+                    original.iter().map(|e| e.id).collect::<Vec<_>>(),
+                    decoded.iter().map(|e| e.id).collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn wire_format_compresses_large_frames() {
+    use wire::*;
+
+    let message = bulk_events(100);
+    let plain = WireFormat {
+        version: PROTOCOL_VERSION,
+        encoding: WireEncoding::Bincode,
+        compression: false,
+    };
+    let compressed = WireFormat {
+        compression: true,
+        ..plain
+    };
+
+    assert!(compressed.encode(&message).unwrap().len() < plain.encode(&message).unwrap().len());
+}
+
+#[test]
+fn wire_format_negotiation() {
+    use wire::*;
+
+    let local = Hello::local();
+
+    let json_only = Hello {
+        encodings: vec![WireEncoding::Json],
+        compression: false,
+        ..Hello::local()
+    };
+    let format = local.negotiate(&json_only).unwrap();
+    assert_eq!(format.encoding, WireEncoding::Json);
+    assert!(!format.compression);
+
+    let newer = Hello {
+        min_version: PROTOCOL_VERSION,
+        max_version: PROTOCOL_VERSION + 5,
+        ..Hello::local()
+    };
+    assert_eq!(local.negotiate(&newer).unwrap().version, PROTOCOL_VERSION);
+
+    let too_new = Hello {
+        min_version: PROTOCOL_VERSION + 1,
+        max_version: PROTOCOL_VERSION + 5,
+        ..Hello::local()
+    };
+    assert!(local.negotiate(&too_new).is_err());
+
+    let version_one = Hello {
+        min_version: 1,
+        max_version: 1,
+        ..Hello::local()
+    };
+    let format = local.negotiate(&version_one).unwrap();
+    assert_eq!(format.version, 1);
+    assert_eq!(format.encoding, WireEncoding::Json);
+
+    // Version 2 is the first whose types bincode can encode
+    let version_two = Hello {
+        min_version: 1,
+        max_version: 2,
+        ..Hello::local()
+    };
+    let format = local.negotiate(&version_two).unwrap();
+    assert_eq!(format.version, 2);
+    assert_eq!(format.encoding, WireEncoding::Bincode);
+}
+
+#[test]
+fn check_ordering() {
+    let server_id = ServerId::new(1);
+    let idgen = ObjectIdGenerator::new(server_id);
+    let mut log = EventLog::new(idgen, None);
+
+    let uid = UserId::new(Snowflake::from_parts(server_id, 0, 1));
+
+    let e1 = log.create(
+        uid,
+        details::UserQuit {
+            message: "aaa".to_string(),
+        },
+    );
+    log.add(e1.clone());
+
+    let e2 = log.create(
+        uid,
+        details::UserQuit {
+            message: "bbb".to_string(),
+        },
+    );
+    log.add(e2.clone());
+
+    let ordered = vec![e1.clone(), e2.clone()];
+    assert!(replay::check_ordering(&ordered, &EventClock::new()).is_empty());
+
+    let reversed = vec![e2.clone(), e1.clone()];
+    assert_eq!(
+        replay::check_ordering(&reversed, &EventClock::new()),
+        vec![replay::OrderingError::OutOfOrder {
+            event: e2.id,
+            dependency: e1.id
+        }]
+    );
+
+    let pruned = vec![e2.clone()];
+    assert_eq!(
+        replay::check_ordering(&pruned, &EventClock::new()),
+        vec![replay::OrderingError::MissingDependency {
+            event: e2.id,
+            dependency: e1.id
+        }]
+    );
+
+    let mut base = EventClock::new();
+    base.update_with_id(e1.id);
+    assert!(replay::check_ordering(&pruned, &base).is_empty());
+}
+
+#[test]
+fn diff_json_matches_map_entries_by_key() {
+    use serde_json::json;
+
+    let left = json!({ "users": [[1, { "nick": "a" }], [2, { "nick": "b" }]], "clock": 1 });
+    let right =
+        json!({ "users": [[2, { "nick": "c" }], [1, { "nick": "a" }], [3, {}]], "clock": 1 });
+
+    let differences = replay::diff_json(&left, &right);
+
+    assert_eq!(differences.len(), 2);
+    assert_eq!(differences[0].path, ".users[2].nick");
+    assert_eq!(differences[0].object, Some(json!(2)));
+    assert_eq!(differences[0].left, Some(json!("b")));
+    assert_eq!(differences[0].right, Some(json!("c")));
+    assert_eq!(differences[1].path, ".users[3]");
+    assert_eq!(differences[1].left, None);
+}
+
+#[test]
+fn remote_request_stats_record_outcomes() {
+    use std::time::Duration;
+
+    let mut stats = RemoteRequestStats::default();
+    stats.record(Duration::from_millis(500), &Ok::<(), _>(()));
+    stats.record(Duration::from_secs(2), &Err::<(), _>(NetworkError::Timeout));
+    stats.record(
+        Duration::from_millis(250),
+        &Err::<(), _>(NetworkError::InternalError("failed".to_string())),
+    );
+
+    assert_eq!(stats.requests, 3);
+    assert_eq!(stats.timeouts, 1);
+    assert_eq!(stats.failures, 1);
+    assert_eq!(stats.latency_seconds_sum, 2.75);
+    assert_eq!(stats.latency_seconds_max, 2.0);
+}
+
+fn test_server_name(name: &str) -> crate::validated::ServerName {
+    use crate::validated::Validated;
+    crate::validated::ServerName::convert(name).unwrap()
+}
+
+/// Two ends of an in-memory peer connection. Conversations opened on the first are
+/// delivered to the returned request receiver on the second.
+fn connected_pair() -> (
+    std::sync::Arc<connection::PeerConnection>,
+    std::sync::Arc<connection::PeerConnection>,
+    UnboundedReceiver<Request>,
+) {
+    let format = wire::WireFormat {
+        version: wire::PROTOCOL_VERSION,
+        encoding: wire::WireEncoding::Bincode,
+        compression: true,
+    };
+    let (client_stream, server_stream) = tokio::io::duplex(64 * 1024);
+    let (inbound_send, inbound_recv) = unbounded_channel();
+
+    let (client, _) = connection::PeerConnection::start(
+        client_stream,
+        test_server_name("server.b"),
+        format,
+        None,
+    );
+    let (server, _) = connection::PeerConnection::start(
+        server_stream,
+        test_server_name("server.a"),
+        format,
+        Some(inbound_send),
+    );
+
+    (client, server, inbound_recv)
+}
+
+fn text_message(text: &str) -> Message {
+    Message {
+        source_server: (ServerId::new(1), 1),
+        content: MessageDetail::NewEvent({
+            let log = EventLog::new(ObjectIdGenerator::new(ServerId::new(1)), None);
+            log.create(
+                UserId::new(Snowflake::from_parts(ServerId::new(1), 0, 1)),
+                details::UserQuit {
+                    message: text.to_string(),
+                },
+            )
+        }),
+    }
+}
+
+fn message_text(message: &Message) -> &str {
+    match &message.content {
+        MessageDetail::NewEvent(event) => match &event.details {
+            EventDetails::UserQuit(quit) => &quit.message,
+            _ => panic!("Unexpected event: {:?}", event),
+        },
+        _ => panic!("Unexpected message: {:?}", message),
+    }
+}
+
+#[tokio::test]
+async fn peer_connection_multiplexes_conversations() {
+    let (client, _server, mut inbound) = connected_pair();
+    let (response_send, mut responses) = unbounded_channel();
+
+    let first = client
+        .open_conversation(text_message("first"), response_send.clone())
+        .unwrap();
+    let second = client
+        .open_conversation(text_message("second"), response_send)
+        .unwrap();
+
+    let mut requests = [inbound.recv().await.unwrap(), inbound.recv().await.unwrap()];
+    requests.sort_by_key(|req| message_text(&req.message).to_string());
+    assert_eq!(requests[0].received_from, test_server_name("server.a"));
+    assert_eq!(message_text(&requests[0].message), "first");
+    assert_eq!(message_text(&requests[1].message), "second");
+
+    // Answer the second conversation first, to check responses are routed by
+    // conversation rather than by order
+    for (req, reply) in requests.iter().rev().zip(["reply two", "reply one"]) {
+        req.response.send(text_message(reply)).await.unwrap();
+        req.response
+            .send(Message {
+                source_server: (ServerId::new(2), 1),
+                content: MessageDetail::Done,
+            })
+            .await
+            .unwrap();
+    }
+
+    let mut replies = [
+        message_text(&responses.recv().await.unwrap().message).to_string(),
+        message_text(&responses.recv().await.unwrap().message).to_string(),
+    ];
+    replies.sort();
+    assert_eq!(replies, ["reply one", "reply two"]);
+
+    first.await.unwrap().unwrap();
+    second.await.unwrap().unwrap();
+    assert!(!client.is_closed());
+}
+
+#[tokio::test]
+async fn closed_peer_connection_ends_conversations() {
+    let (client, server, mut inbound) = connected_pair();
+    let (response_send, _responses) = unbounded_channel();
+
+    let conversation = client
+        .open_conversation(text_message("hello"), response_send.clone())
+        .unwrap();
+    let _request = inbound.recv().await.unwrap();
+
+    server.close();
+
+    assert!(matches!(
+        conversation.await.unwrap(),
+        Err(NetworkError::ConnectionClosed)
+    ));
+    assert!(client.is_closed());
+    assert!(matches!(
+        client.open_conversation(text_message("again"), response_send),
+        Err(NetworkError::ConnectionClosed)
+    ));
+}
+
+#[tokio::test]
+async fn undecodable_frame_is_skipped() {
+    use serde_json::json;
+
+    let format = wire::WireFormat {
+        version: wire::PROTOCOL_VERSION,
+        encoding: wire::WireEncoding::Json,
+        compression: false,
+    };
+    let (mut client_stream, server_stream) = tokio::io::duplex(64 * 1024);
+    let (inbound_send, mut inbound) = unbounded_channel();
+    let (server, _) = connection::PeerConnection::start(
+        server_stream,
+        test_server_name("server.a"),
+        format,
+        Some(inbound_send),
+    );
+
+    let frames = [
+        json!({ "conversation": 0, "open": true, "message": { "unknown": true } }),
+        json!("not a frame"),
+        json!({ "conversation": 1, "open": true, "message": text_message("hello") }),
+    ];
+    for frame in frames {
+        let buf = format.encode(&frame).unwrap();
+        wire::write_frame(&mut client_stream, &buf).await.unwrap();
+    }
+
+    let request = inbound.recv().await.unwrap();
+    assert_eq!(message_text(&request.message), "hello");
+    assert!(!server.is_closed());
+}
+
+#[tokio::test]
+async fn disconnect_waits_for_connection_attempt() {
+    let peer = std::sync::Arc::new(network::Peer::new(PeerConfig {
+        name: test_server_name("server.b"),
+        address: "127.0.0.1:1".to_string(),
+        fingerprint: String::new(),
+    }));
+
+    // Hold the lock as a sender would while connecting
+    let mut attempt = peer.connection.lock().await;
+    peer.disconnect();
+
+    let (client, _server, _inbound) = connected_pair();
+    *attempt = Some(std::sync::Arc::clone(&client));
+    drop(attempt);
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !client.is_closed() {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("connection was not closed");
+    assert!(peer.connection.lock().await.is_none());
+}
+
+/// Start a sync node for the test certificate `name`, with `peers` as the network config
+fn start_test_node(
+    id: u16,
+    name: &str,
+    peers: &[PeerConfig],
+) -> (
+    std::sync::Arc<ReplicatedEventLog>,
+    UnboundedReceiver<crate::rpc::NetworkMessage>,
+    tokio::sync::broadcast::Sender<crate::rpc::ShutdownAction>,
+) {
+    let server_name = test_server_name(name);
+    let me = peers.iter().find(|p| p.name == server_name).unwrap();
+    let short_name = name.split('.').next().unwrap();
+
+    let (server_send, server_recv) = unbounded_channel();
+    let log = std::sync::Arc::new(ReplicatedEventLog::new(
+        ServerId::new(id),
+        &server_name,
+        1,
+        server_send,
+        SyncConfig {
+            peers: peers.to_vec(),
+            fanout: 1,
+            ca_file: "../configs/ca_cert.pem".into(),
+        },
+        NodeConfig {
+            listen_addr: me.address.parse().unwrap(),
+            cert_file: format!("../configs/{short_name}.pem").into(),
+            key_file: format!("../configs/{short_name}.key").into(),
+        },
+        EventLogConfig {
+            event_expiry: 1800,
+            snapshot: None,
+        },
+    ));
+
+    let (shutdown_send, shutdown_recv) = tokio::sync::broadcast::channel(1);
+    log.start_sync(shutdown_recv);
+
+    (log, server_recv, shutdown_send)
+}
+
+/// Three nodes in a line, where the first can only reach the last through the second
+async fn start_test_line() -> Vec<(
+    std::sync::Arc<ReplicatedEventLog>,
+    UnboundedReceiver<crate::rpc::NetworkMessage>,
+    tokio::sync::broadcast::Sender<crate::rpc::ShutdownAction>,
+)> {
+    let names = ["server1.test", "server2.test", "services.test"];
+    let fingerprints = [
+        "1e73e460163a89fcadfd9acbc8fc115d6e4f3a81",
+        "8bd6fbb218ddb36ec5f649f4243ece12777b572a",
+        "e42729000055e11e51056bacc34aa4c45d8defcc",
+    ];
+
+    let mut peers = Vec::new();
+    for (name, fingerprint) in names.iter().zip(fingerprints) {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        peers.push(PeerConfig {
+            name: test_server_name(name),
+            address: format!("127.0.0.1:{port}"),
+            fingerprint: fingerprint.to_string(),
+        });
+    }
+
+    let nodes: Vec<_> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| start_test_node(i as u16 + 1, name, &peers))
+        .collect();
+
+    // Each node only knows its neighbours are up
+    for (i, (log, _, _)) in nodes.iter().enumerate() {
+        for j in [i.wrapping_sub(1), i + 1] {
+            if let Some(peer) = peers.get(j) {
+                log.enable_server(peer.name, ServerId::new(j as u16 + 1));
+            }
+        }
+    }
+
+    for peer in &peers {
+        while tokio::net::TcpStream::connect(&peer.address).await.is_err() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    }
+
+    nodes
+}
+
+/// Wait for the next remote request delivered to a node's server task
+async fn next_remote_request(
+    recv: &mut UnboundedReceiver<crate::rpc::NetworkMessage>,
+) -> crate::rpc::RemoteServerRequest {
+    loop {
+        if let crate::rpc::NetworkMessage::RemoteServerRequest(req) = recv.recv().await.unwrap() {
+            return req;
+        }
+    }
+}
+
+#[tokio::test]
+async fn targeted_message_is_relayed() {
+    use crate::rpc::*;
+
+    let mut nodes = start_test_line().await;
+    let target = test_server_name("services.test");
+
+    let source = std::sync::Arc::clone(&nodes[0].0);
+    let request = tokio::spawn(async move {
+        source
+            .send_remote_request(target, RemoteServerRequestType::Ping)
+            .await
+    });
+
+    let req = next_remote_request(&mut nodes[2].1).await;
+    assert!(matches!(req.req, RemoteServerRequestType::Ping));
+    req.response.send(RemoteServerResponse::Success).unwrap();
+
+    assert!(matches!(
+        request.await.unwrap(),
+        Ok(RemoteServerResponse::Success)
+    ));
+
+    // The first node has learned a route via the second
+    let routes = nodes[0].0.routing_table();
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].target, target);
+    assert_eq!(routes[0].next_hop, test_server_name("server2.test"));
+    assert!(routes[0].last_success.is_some());
+
+    for (_, _, shutdown) in &nodes {
+        let _ = shutdown.send(ShutdownAction::Shutdown);
+    }
+}
+
+#[tokio::test]
+async fn relayed_message_honours_deadline() {
+    use crate::rpc::*;
+    use std::time::Duration;
+
+    let mut nodes = start_test_line().await;
+    let target = test_server_name("services.test");
+
+    let source = std::sync::Arc::clone(&nodes[0].0);
+    let request = tokio::spawn(async move {
+        source
+            .send_remote_request_with_timeout(
+                target,
+                RemoteServerRequestType::Ping,
+                Duration::from_millis(500),
+            )
+            .await
+    });
+
+    // Don't answer; the target should stop waiting at about the same time as the source,
+    // well before the default timeout would expire
+    let mut req = next_remote_request(&mut nodes[2].1).await;
+    tokio::time::timeout(Duration::from_secs(2), req.response.closed())
+        .await
+        .expect("target didn't honour the deadline");
+
+    assert!(matches!(request.await.unwrap(), Err(NetworkError::Timeout)));
+
+    for (_, _, shutdown) in &nodes {
+        let _ = shutdown.send(ShutdownAction::Shutdown);
+    }
+}
+
+/// Peer configs for `server1.test` and `server2.test`, on free local ports
+fn test_pair_peers() -> Vec<PeerConfig> {
+    let mut peers = Vec::new();
+    for (name, fingerprint) in [
+        ("server1.test", "1e73e460163a89fcadfd9acbc8fc115d6e4f3a81"),
+        ("server2.test", "8bd6fbb218ddb36ec5f649f4243ece12777b572a"),
+    ] {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        peers.push(PeerConfig {
+            name: test_server_name(name),
+            address: format!("127.0.0.1:{port}"),
+            fingerprint: fingerprint.to_string(),
+        });
+    }
+    peers
+}
+
+async fn wait_for_listener(peer: &PeerConfig) {
+    while tokio::net::TcpStream::connect(&peer.address).await.is_err() {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+}
+
+/// Connect to the `server1.test` node from [`test_pair_peers`] as `server2.test`,
+/// offering `hello` in the handshake
+async fn connect_as_server2(
+    peers: &[PeerConfig],
+    hello: &wire::Hello,
+) -> (std::sync::Arc<connection::PeerConnection>, wire::WireFormat) {
+    use wire::*;
+
+    let ca_cert = SyncConfig {
+        peers: peers.to_vec(),
+        fanout: 1,
+        ca_file: "../configs/ca_cert.pem".into(),
+    }
+    .load_ca_cert()
+    .unwrap();
+    let (cert, key) = NodeConfig {
+        listen_addr: peers[1].address.parse().unwrap(),
+        cert_file: "../configs/server2.pem".into(),
+        key_file: "../configs/server2.key".into(),
+    }
+    .load_cert_and_keys()
+    .unwrap();
+    let mut root_store = rustls::RootCertStore::empty();
+    root_store.add(&ca_cert).unwrap();
+    let client_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_store)
+        .with_single_cert(cert, key)
+        .unwrap();
+
+    let tcp = tokio::net::TcpStream::connect(&peers[0].address)
+        .await
+        .unwrap();
+    let mut stream = tokio_rustls::TlsConnector::from(std::sync::Arc::new(client_config))
+        .connect(peers[0].tls_server_name().unwrap(), tcp)
+        .await
+        .unwrap();
+
+    write_frame(&mut stream, &serde_json::to_vec(hello).unwrap())
+        .await
+        .unwrap();
+    let response: HelloResponse =
+        serde_json::from_slice(&read_frame(&mut stream).await.unwrap()).unwrap();
+    let HelloResponse::Accept(format) = response else {
+        panic!("{:?}", response);
+    };
+
+    let (connection, _) = connection::PeerConnection::start(stream, peers[0].name, format, None);
+    (connection, format)
+}
+
+#[tokio::test]
+async fn version_one_peer_gets_placeholders() {
+    use wire::*;
+
+    let peers = test_pair_peers();
+    let (node, _server_recv, shutdown) = start_test_node(1, "server1.test", &peers);
+    wait_for_listener(&peers[0]).await;
+
+    let channel = ChannelId::new(Snowflake::from_parts(ServerId::new(1), 0, 1));
+    let user = UserId::new(Snowflake::from_parts(ServerId::new(1), 0, 2));
+    let kick_id = node
+        .create_event_with_id(
+            MembershipId::new(user, channel).into(),
+            details::ServerChannelKick {
+                source: ServerId::new(1),
+                message: "kicked".to_string(),
+            }
+            .into(),
+        )
+        .await;
+
+    // Connect as server2, running a build which only speaks version 1
+    let hello = Hello {
+        min_version: 1,
+        max_version: 1,
+        encodings: vec![WireEncoding::Json],
+        compression: false,
+    };
+    let (connection, format) = connect_as_server2(&peers, &hello).await;
+    assert_eq!(format.version, 1);
+    assert_eq!(format.encoding, WireEncoding::Json);
+
+    let (response_send, mut responses) = unbounded_channel();
+    let conversation = connection
+        .open_conversation(
+            Message {
+                source_server: (ServerId::new(2), 1),
+                content: MessageDetail::GetEvent(vec![kick_id]),
+            },
+            response_send,
+        )
+        .unwrap();
+
+    let req = responses.recv().await.unwrap();
+    let json = serde_json::to_string(&req.message).unwrap();
+    assert!(!json.contains("ServerChannelKick"), "{}", json);
+
+    let MessageDetail::BulkEvents(events) = &req.message.content else {
+        panic!("{:?}", req.message);
+    };
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, kick_id);
+    assert!(compat::is_placeholder(&events[0]));
+
+    req.response
+        .send(Message {
+            source_server: (ServerId::new(2), 1),
+            content: MessageDetail::Done,
+        })
+        .await
+        .unwrap();
+    conversation.await.unwrap().unwrap();
+
+    let _ = shutdown.send(crate::rpc::ShutdownAction::Shutdown);
+}
+
+#[tokio::test]
+async fn removed_peer_inbound_connection_is_closed() {
+    let peers = test_pair_peers();
+    let (node, _server_recv, shutdown) = start_test_node(1, "server1.test", &peers);
+    wait_for_listener(&peers[0]).await;
+
+    let (connection, _) = connect_as_server2(&peers, &wire::Hello::local()).await;
+    // The node only registers the connection once its handshake completes
+    let (response_send, _responses) = unbounded_channel();
+    let _ = connection.open_conversation(text_message("hello"), response_send);
+
+    node.update_peer(&peers[1].name, None, false);
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        while !connection.is_closed() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("connection was not closed");
+
+    let _ = shutdown.send(crate::rpc::ShutdownAction::Shutdown);
+}
+
+#[test]
+fn peer_names_must_be_tls_server_names() {
+    let peer = |name: &str| PeerConfig {
+        name: test_server_name(name),
+        address: "127.0.0.1:1".to_string(),
+        fingerprint: String::new(),
+    };
+
+    assert!(peer("server1.test").tls_server_name().is_some());
+    assert!(peer("a..b").tls_server_name().is_none());
+}
@@ -0,0 +1,248 @@
+//! Encoding of sync protocol messages on the wire
+//!
+//! When a peer connection is established, the connecting side sends a [`Hello`]
+//! listing the protocol versions and encodings it understands, and the accepting
+//! side replies with the [`WireFormat`] to be used for the rest of the connection.
+//! The handshake itself is always JSON, so that nodes of any version can at least
+//! tell each other that they can't interoperate.
+//!
+//! Every frame is a `u32` length prefix followed by a flags byte and the encoded
+//! payload.
+
+use super::network::NetworkError;
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    convert::TryInto,
+    io::{Read, Write},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The newest protocol version understood by this build.
+///
+/// - Version 1: negotiated wire format, with optional bincode encoding and compression.
+/// - Version 2:
+///   - `digest` in server pings
+///   - `gateway` and `tls_fingerprint` on new users and their state
+///   - `connection_limits` in the network config
+///   - `time_remaining` on targeted messages
+///   - `SyncUnavailable` as a reply to `GetEvent` and `SyncRequest` for pruned events
+///   - `SyncPeerUpdate`, `ChannelAutoKickUpdate` and `ServerChannelKick` events
+///   - the `NetworkConfig` audit log category
+///   - the `InvalidAccountField` services response
+///
+/// Messages sent to version 1 peers are downgraded by [`compat`](super::compat).
+/// Events of the new types reach them only as no-op placeholders, so a version 1
+/// node's state misses their effects until it is upgraded.
+///
+/// Nodes built before the handshake existed send no [`Hello`] and can't connect
+/// to this build at all, so upgrading from them can't be done one node at a time;
+/// the whole network has to be restarted together.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// The oldest protocol version this build can still speak
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// Version 2 added fields to some message, event and state types. JSON tolerates
+/// their absence, but bincode doesn't, so older versions have to fall back to JSON.
+const MIN_BINCODE_VERSION: u32 = 2;
+
+/// Frames larger than this are rejected, to bound memory use on a bad connection
+const MAX_FRAME_SIZE: u32 = 1 << 30;
+/// Payloads smaller than this aren't worth compressing
+const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Set in a frame's flags byte if the payload is compressed
+const FLAG_COMPRESSED: u8 = 0x01;
+
+/// Serialisation formats supported for protocol messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireEncoding {
+    Json,
+    Bincode,
+}
+
+/// Sent by the connecting side to begin negotiation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hello {
+    pub min_version: u32,
+    pub max_version: u32,
+    /// Supported encodings, in order of preference
+    pub encodings: Vec<WireEncoding>,
+    /// Whether large frames may be compressed
+    pub compression: bool,
+}
+
+impl Hello {
+    /// The capabilities of this build
+    pub fn local() -> Self {
+        Self {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            encodings: vec![WireEncoding::Bincode, WireEncoding::Json],
+            compression: true,
+        }
+    }
+
+    /// Choose the wire format to use with a peer who sent `remote`, preferring the
+    /// newest common version and the remote side's preferred encoding.
+    pub fn negotiate(&self, remote: &Hello) -> Result<WireFormat, String> {
+        let version = self.max_version.min(remote.max_version);
+        if version < self.min_version.max(remote.min_version) {
+            return Err(format!(
+                "No common protocol version (local {}-{}, remote {}-{})",
+                self.min_version, self.max_version, remote.min_version, remote.max_version
+            ));
+        }
+
+        let encoding = remote
+            .encodings
+            .iter()
+            .find(|e| self.encodings.contains(e) && encoding_supported(version, **e))
+            .copied()
+            .ok_or_else(|| format!("No common encoding in {:?}", remote.encodings))?;
+
+        Ok(WireFormat {
+            version,
+            encoding,
+            compression: self.compression && remote.compression,
+        })
+    }
+}
+
+fn encoding_supported(version: u32, encoding: WireEncoding) -> bool {
+    encoding != WireEncoding::Bincode || version >= MIN_BINCODE_VERSION
+}
+
+/// The accepting side's reply to a [`Hello`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HelloResponse {
+    Accept(WireFormat),
+    Reject(String),
+}
+
+/// The negotiated format for a single connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireFormat {
+    pub version: u32,
+    pub encoding: WireEncoding,
+    pub compression: bool,
+}
+
+impl WireFormat {
+    /// Encode a value into a frame body, including the flags byte
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, NetworkError> {
+        let payload = match self.encoding {
+            WireEncoding::Json => serde_json::to_vec(value)?,
+            WireEncoding::Bincode => bincode::serialize(value)?,
+        };
+
+        if self.compression && payload.len() >= COMPRESSION_THRESHOLD {
+            let mut buf = vec![FLAG_COMPRESSED];
+            let mut encoder = ZlibEncoder::new(&mut buf, Compression::fast());
+            encoder.write_all(&payload)?;
+            encoder.finish()?;
+            Ok(buf)
+        } else {
+            let mut buf = Vec::with_capacity(payload.len() + 1);
+            buf.push(0);
+            buf.extend_from_slice(&payload);
+            Ok(buf)
+        }
+    }
+
+    /// Decode a frame body produced by [`encode`](Self::encode)
+    pub fn decode<T: DeserializeOwned>(&self, buf: &[u8]) -> Result<T, NetworkError> {
+        let (flags, payload) = buf
+            .split_first()
+            .ok_or_else(|| NetworkError::InternalError("Empty frame".to_string()))?;
+
+        let decompressed;
+        let payload = if flags & FLAG_COMPRESSED != 0 {
+            let mut out = Vec::new();
+            ZlibDecoder::new(payload).read_to_end(&mut out)?;
+            decompressed = out;
+            &decompressed[..]
+        } else {
+            payload
+        };
+
+        Ok(match self.encoding {
+            WireEncoding::Json => serde_json::from_slice(payload)?,
+            WireEncoding::Bincode => bincode::deserialize(payload)?,
+        })
+    }
+}
+
+/// Write a single length-prefixed frame
+pub async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    buf: &[u8],
+) -> Result<(), NetworkError> {
+    let length: u32 = buf
+        .len()
+        .try_into()
+        .map_err(|_| NetworkError::InternalError("Frame too large".to_string()))?;
+    writer.write_u32(length).await?;
+    writer.write_all(buf).await?;
+    Ok(())
+}
+
+/// Read a single length-prefixed frame
+pub async fn read_frame(reader: &mut (impl AsyncRead + Unpin)) -> Result<Vec<u8>, NetworkError> {
+    let length = reader.read_u32().await?;
+    if length > MAX_FRAME_SIZE {
+        return Err(NetworkError::InternalError(format!(
+            "Frame of {length} bytes exceeds maximum size"
+        )));
+    }
+
+    let mut buf = vec![0; length.try_into().unwrap()];
+    reader.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Perform the connecting side of the handshake
+pub async fn negotiate_outbound(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+) -> Result<WireFormat, NetworkError> {
+    write_frame(stream, &serde_json::to_vec(&Hello::local())?).await?;
+
+    match serde_json::from_slice(&read_frame(stream).await?)? {
+        HelloResponse::Accept(format) => {
+            let local = Hello::local();
+            if format.version < local.min_version
+                || format.version > local.max_version
+                || !local.encodings.contains(&format.encoding)
+                || !encoding_supported(format.version, format.encoding)
+            {
+                return Err(NetworkError::Handshake(format!(
+                    "Peer chose unsupported wire format {format:?}"
+                )));
+            }
+            Ok(format)
+        }
+        HelloResponse::Reject(reason) => Err(NetworkError::Handshake(reason)),
+    }
+}
+
+/// Perform the accepting side of the handshake
+pub async fn negotiate_inbound(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+) -> Result<WireFormat, NetworkError> {
+    let remote: Hello = serde_json::from_slice(&read_frame(stream).await?)?;
+
+    match Hello::local().negotiate(&remote) {
+        Ok(format) => {
+            write_frame(stream, &serde_json::to_vec(&HelloResponse::Accept(format))?).await?;
+            Ok(format)
+        }
+        Err(reason) => {
+            write_frame(
+                stream,
+                &serde_json::to_vec(&HelloResponse::Reject(reason.clone()))?,
+            )
+            .await?;
+            Err(NetworkError::Handshake(reason))
+        }
+    }
+}
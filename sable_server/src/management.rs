@@ -1,13 +1,21 @@
 use crate::config::*;
 use sable_network::{
     config::TlsData,
-    rpc::{ServerManagementCommand, ServerManagementCommandType, ShutdownAction},
+    network::config::NetworkConfig,
+    rpc::{
+        LoadNetworkConfig, ManagementError, ManagementErrorKind, ServerManagementCommand,
+        ServerManagementCommandType, ShutdownAction, StatisticsFormat,
+    },
+    sync::PeerConfig,
+    validated::ServerName,
 };
 
 use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
 use sha1::{Digest, Sha1};
 use std::{
     future::Future,
+    net::IpAddr,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -39,6 +47,7 @@ pub struct ManagementServer {
 struct ManagementService {
     data: Arc<ManagementServiceData>,
     authorised_fingerprint: AuthorisedFingerprint,
+    remote_addr: Option<IpAddr>,
 }
 
 struct ManagementServiceData {
@@ -60,6 +69,36 @@ fn internal_error() -> hyper::Result<Response<Body>> {
     Ok(response)
 }
 
+fn bad_request(message: String) -> hyper::Result<Response<Body>> {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = StatusCode::BAD_REQUEST;
+    Ok(response)
+}
+
+fn error_response(error: ManagementError) -> hyper::Result<Response<Body>> {
+    let mut response = Response::new(Body::from(error.body.to_string()));
+    *response.status_mut() = match error.kind {
+        ManagementErrorKind::BadRequest => StatusCode::BAD_REQUEST,
+        ManagementErrorKind::NotFound => StatusCode::NOT_FOUND,
+        ManagementErrorKind::Unsupported => StatusCode::NOT_IMPLEMENTED,
+        ManagementErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    Ok(response)
+}
+
+/// Parse a JSON request body
+async fn parse_body<T: DeserializeOwned>(body: Body) -> Result<T, String> {
+    let bytes = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+struct RemovePeerRequest {
+    name: ServerName,
+}
+
 impl ManagementService {
     async fn server_management_command(
         command_sender: Sender<ManagementCommand>,
@@ -77,12 +116,41 @@ impl ManagementService {
         {
             internal_error()
         } else if let Ok(response) = recv.await {
-            Ok(Response::new(Body::from(response)))
+            match response {
+                Ok(body) => Ok(Response::new(Body::from(body))),
+                Err(error) => error_response(error),
+            }
         } else {
             internal_error()
         }
     }
 
+    /// Fetch server statistics in the given format, labelling the response with the
+    /// matching content type
+    async fn statistics_command(
+        command_sender: Sender<ManagementCommand>,
+        format: StatisticsFormat,
+    ) -> Result<Response<Body>, hyper::Error> {
+        let content_type = match format {
+            StatisticsFormat::Json => "application/json",
+            StatisticsFormat::Prometheus => "text/plain; version=0.0.4",
+        };
+
+        let mut response = Self::server_management_command(
+            command_sender,
+            ServerManagementCommandType::ServerStatistics(format),
+        )
+        .await?;
+
+        if response.status() == StatusCode::OK {
+            response.headers_mut().insert(
+                hyper::header::CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static(content_type),
+            );
+        }
+        Ok(response)
+    }
+
     async fn shutdown_command(
         command_sender: Sender<ManagementCommand>,
         cmd: ShutdownAction,
@@ -111,17 +179,27 @@ impl hyper::service::Service<Request<Body>> for ManagementService {
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
         let command_sender = self.data.command_sender.clone();
+        let client = self.authorised_fingerprint.name.clone();
+        let client_addr = self.remote_addr;
 
         tracing::debug!(method=?req.method(), path=?req.uri().path(), user=?self.authorised_fingerprint.name, "Got management request");
 
         Box::pin(async move {
-            match (req.method(), req.uri().path()) {
+            let method = req.method().clone();
+            let path = req.uri().path().to_owned();
+
+            match (&method, path.as_str()) {
                 (&Method::GET, "/statistics") => {
-                    Self::server_management_command(
-                        command_sender,
-                        ServerManagementCommandType::ServerStatistics,
-                    )
-                    .await
+                    let format = match req.uri().query() {
+                        Some(query) if query.split('&').any(|q| q == "format=prometheus") => {
+                            StatisticsFormat::Prometheus
+                        }
+                        _ => StatisticsFormat::Json,
+                    };
+                    Self::statistics_command(command_sender, format).await
+                }
+                (&Method::GET, "/metrics") => {
+                    Self::statistics_command(command_sender, StatisticsFormat::Prometheus).await
                 }
                 (&Method::GET, "/dump-network") => {
                     Self::server_management_command(
@@ -137,6 +215,107 @@ impl hyper::service::Service<Request<Body>> for ManagementService {
                     )
                     .await
                 }
+                (&Method::GET, "/peers") => {
+                    Self::server_management_command(
+                        command_sender,
+                        ServerManagementCommandType::ListPeers,
+                    )
+                    .await
+                }
+                (&Method::POST, "/peers/add") => {
+                    match parse_body::<PeerConfig>(req.into_body()).await {
+                        Ok(peer) => {
+                            Self::server_management_command(
+                                command_sender,
+                                ServerManagementCommandType::AddPeer(peer),
+                            )
+                            .await
+                        }
+                        Err(e) => bad_request(e),
+                    }
+                }
+                (&Method::POST, "/peers/remove") => {
+                    match parse_body::<RemovePeerRequest>(req.into_body()).await {
+                        Ok(request) => {
+                            Self::server_management_command(
+                                command_sender,
+                                ServerManagementCommandType::RemovePeer(request.name),
+                            )
+                            .await
+                        }
+                        Err(e) => bad_request(e),
+                    }
+                }
+                (&Method::GET, "/routes") => {
+                    Self::server_management_command(
+                        command_sender,
+                        ServerManagementCommandType::RoutingTable,
+                    )
+                    .await
+                }
+                (&Method::GET, "/divergence") => {
+                    Self::server_management_command(
+                        command_sender,
+                        ServerManagementCommandType::StateDivergence,
+                    )
+                    .await
+                }
+                (&Method::GET, "/network-config") => {
+                    Self::server_management_command(
+                        command_sender,
+                        ServerManagementCommandType::ShowNetworkConfig,
+                    )
+                    .await
+                }
+                (&Method::POST, "/network-config/validate") => {
+                    match parse_body::<NetworkConfig>(req.into_body()).await {
+                        Ok(config) => {
+                            Self::server_management_command(
+                                command_sender,
+                                ServerManagementCommandType::ValidateNetworkConfig(Box::new(
+                                    config,
+                                )),
+                            )
+                            .await
+                        }
+                        Err(e) => bad_request(e),
+                    }
+                }
+                (&Method::POST, "/network-config/diff") => {
+                    match parse_body::<NetworkConfig>(req.into_body()).await {
+                        Ok(config) => {
+                            Self::server_management_command(
+                                command_sender,
+                                ServerManagementCommandType::DiffNetworkConfig(Box::new(config)),
+                            )
+                            .await
+                        }
+                        Err(e) => bad_request(e),
+                    }
+                }
+                (&Method::POST, "/network-config/load") => {
+                    match parse_body::<NetworkConfig>(req.into_body()).await {
+                        Ok(config) => {
+                            Self::server_management_command(
+                                command_sender,
+                                ServerManagementCommandType::LoadNetworkConfig(LoadNetworkConfig {
+                                    config: Box::new(config),
+                                    client,
+                                    client_addr,
+                                }),
+                            )
+                            .await
+                        }
+                        Err(e) => bad_request(e),
+                    }
+                }
+                (&Method::POST, "/reload-tls") => {
+                    Self::server_management_command(
+                        command_sender,
+                        ServerManagementCommandType::ReloadTls,
+                    )
+                    .await
+                }
                 (&Method::POST, "/shutdown") => {
                     Self::shutdown_command(command_sender, ShutdownAction::Shutdown).await
                 }
@@ -185,6 +364,7 @@ impl ManagementServer {
         acceptor: Arc<TlsAcceptor>,
         data: Arc<ManagementServiceData>,
     ) -> Result<(), anyhow::Error> {
+        let remote_addr = conn.peer_addr().ok().map(|addr| addr.ip());
         let stream = acceptor.accept(conn).await?;
         let (_, tls_state) = stream.get_ref();
         let client_cert = tls_state
@@ -205,6 +385,7 @@ impl ManagementServer {
 
         let service = ManagementService {
             authorised_fingerprint,
+            remote_addr,
             data,
         };
         let http = hyper::server::conn::Http::new();
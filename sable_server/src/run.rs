@@ -146,7 +146,9 @@ where
 ///
 /// If `bootstrap_config` is `Some`, then an empty network state will be created, with the network
 /// configuration from the provided file path. If it is `None`, then the new server will sync to an existing
-/// network according to the configuration at the provided `sync_config_path`.
+/// network according to the configuration at the provided `sync_config_path`. If the sync config enables
+/// snapshots and one exists, the network state is restored from it instead, and `bootstrap_config` is
+/// ignored with a warning.
 ///
 /// If `upgrade_fd` is `Some`, then a saved server state will be read from it and used to resume
 /// processing after an in-place upgrade. In this case, `bootstrap_config` will not be used, but must still
@@ -8,7 +8,10 @@ use sable_network::{
     node::NetworkNodeState,
     policy::StandardPolicyService,
     prelude::*,
-    rpc::{RemoteServerRequest, ShutdownAction},
+    rpc::{
+        ManagementError, ManagementErrorKind, RemoteServerRequest, ServerManagementCommandType,
+        ShutdownAction,
+    },
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio::sync::{
@@ -63,6 +66,9 @@ pub struct Server<ST> {
     log: Arc<ReplicatedEventLog>,
     server: Arc<ST>,
     management_config: ManagementConfig,
+    tls_config: TlsConfig,
+    /// The certificates loaded at startup, for the management service to fall back on
+    /// if they can't be reread when it starts
     tls_data: TlsData,
     remote_command_recv: Mutex<Option<UnboundedReceiver<RemoteServerRequest>>>,
 }
@@ -86,6 +92,9 @@ where
     ///
     /// If `bootstrap_config` is `None`, then this function will call out to one of the defined
     /// network peers to retrieve the current network state.
+    ///
+    /// If a snapshot is available, it takes priority over `bootstrap_config`, which is then
+    /// ignored with a warning.
     pub async fn new(
         conf: ServerConfig<ST>,
         server_conf: ST::ProcessedConfig,
@@ -110,9 +119,29 @@ where
             conf.event_log,
         ));
 
-        let network = match bootstrap_config {
-            Some(conf) => Network::new(conf),
-            None => *log.sync_to_network().await,
+        let snapshot = log.snapshot_config().and_then(|config| {
+            config.load_latest().unwrap_or_else(|e| {
+                tracing::warn!("Couldn't read snapshots: {}", e);
+                None
+            })
+        });
+
+        let network = match (bootstrap_config, snapshot) {
+            (Some(conf), Some(snapshot)) => {
+                // If we're bootstrapping a new network but have a snapshot of the old one,
+                // the whole network went away and this is the best state we have
+                let changes = snapshot.network.config().diff(&conf);
+                tracing::warn!(
+                    snapshot_time = snapshot.timestamp,
+                    differences = ?changes.iter().map(|c| &c.path).collect::<Vec<_>>(),
+                    "Bootstrap network config ignored in favour of existing snapshot; \
+                     remove the snapshot directory to bootstrap a new network"
+                );
+                log.restore_snapshot(&snapshot.network, snapshot.events);
+                snapshot.network
+            }
+            (Some(conf), None) => Network::new(conf),
+            (None, snapshot) => *log.rejoin_network(snapshot).await,
         };
 
         let node = Arc::new(NetworkNode::new(
@@ -138,10 +167,8 @@ where
             log,
             server,
             management_config: conf.management,
-            tls_data: conf
-                .tls_config
-                .load_from_disk()
-                .expect("Couldn't load TLS files"),
+            tls_config: conf.tls_config,
+            tls_data,
             remote_command_recv: Mutex::new(Some(remote_recv)),
         })
     }
@@ -174,6 +201,15 @@ where
             Self::run_event_pump(shutdown_recv, remote_command_recv, server).await
         });
 
+        if let Some(interval) = self.tls_config.reload_interval {
+            let tls_config = self.tls_config.clone();
+            let server = Arc::clone(&self.server);
+            let shutdown_recv = shutdown_send.subscribe();
+            tokio::spawn(async move {
+                Self::watch_tls_files(tls_config, interval, server, shutdown_recv).await
+            });
+        }
+
         tokio::select! {
             // The management task will exit on receiving a shutdown command, so just wait for it to finish
             // then propagate the shutdown action
@@ -251,12 +287,61 @@ where
         }
     }
 
+    /// Reread the TLS certificates from disk and pass them to the server. The management
+    /// service isn't affected.
+    async fn reload_tls(tls_config: &TlsConfig, server: &ST) -> anyhow::Result<()> {
+        let tls_data = tls_config
+            .load_from_disk()
+            .context("Couldn't load TLS files")?;
+        server.reload_tls(&tls_data).await
+    }
+
+    /// Check every `interval` seconds whether the TLS files have been modified, and
+    /// reload them if so
+    async fn watch_tls_files(
+        tls_config: TlsConfig,
+        interval: u64,
+        server: Arc<ST>,
+        mut shutdown: broadcast::Receiver<ShutdownAction>,
+    ) {
+        let mut last_modified = tls_config.last_modified();
+        let mut timer = tokio::time::interval(std::time::Duration::from_secs(interval.max(1)));
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = timer.tick() => {
+                    let modified = tls_config.last_modified();
+                    if modified == last_modified {
+                        continue;
+                    }
+                    last_modified = modified;
+
+                    match Self::reload_tls(&tls_config, &server).await {
+                        Ok(()) => tracing::info!("Reloaded changed TLS certificates"),
+                        Err(e) => tracing::error!("Couldn't reload changed TLS certificates: {:#}", e),
+                    }
+                }
+            }
+        }
+    }
+
     async fn run_management(&self) -> ShutdownAction {
         let (server_shutdown_send, server_shutdown_recv) = oneshot::channel();
+        // Prefer the certificates on disk, which may have been reloaded since startup.
+        // The management service keeps whichever it starts with, as reloads only
+        // apply to the server type's listeners.
+        let tls_data = self.tls_config.load_from_disk().unwrap_or_else(|e| {
+            tracing::warn!(
+                "Couldn't reread TLS files for the management service, using those loaded at startup: {:#}",
+                e
+            );
+            self.tls_data.clone()
+        });
 
         let mut server = management::ManagementServer::start(
             self.management_config.clone(),
-            self.tls_data.clone(),
+            tls_data,
             server_shutdown_recv,
         );
 
@@ -267,7 +352,29 @@ where
                     management::ManagementCommand::ServerCommand(scmd) => {
                         let command = &scmd.cmd;
                         tracing::debug!(?command, "Management server command");
-                        self.node.handle_management_command(scmd).await;
+                        match scmd.cmd {
+                            // Statistics need input from the server type as well as the node
+                            ServerManagementCommandType::ServerStatistics(format) => {
+                                let mut stats = self.node.server_statistics();
+                                stats.server_type = self.server.statistics();
+                                let _ = scmd.response.send(Ok(stats.render(format)));
+                            }
+                            // TLS certificates are held by the server type
+                            ServerManagementCommandType::ReloadTls => {
+                                let response =
+                                    match Self::reload_tls(&self.tls_config, &self.server).await {
+                                        Ok(()) => {
+                                            Ok(serde_json::json!({ "reloaded": true }).to_string())
+                                        }
+                                        Err(e) => Err(ManagementError::new(
+                                            ManagementErrorKind::Internal,
+                                            format!("{e:#}"),
+                                        )),
+                                    };
+                                let _ = scmd.response.send(response);
+                            }
+                            _ => self.node.handle_management_command(scmd).await,
+                        }
                     }
                     management::ManagementCommand::Shutdown(action) => {
                         break action;
@@ -357,7 +464,8 @@ where
             tls_data: server_config
                 .tls_config
                 .load_from_disk()
-                .expect("Couldn't load TLS data files"),
+                .context("Couldn't load TLS files")?,
+            tls_config: server_config.tls_config,
             remote_command_recv: Mutex::new(Some(remote_recv)),
         })
     }
@@ -1,7 +1,7 @@
 use sable_network::{config::TlsData, node::*, rpc::*};
 
 use serde::{de::DeserializeOwned, Serialize};
-use std::{future::Future, sync::Arc};
+use std::{collections::BTreeMap, future::Future, sync::Arc};
 use thiserror::Error;
 use tokio::sync::{broadcast, mpsc::UnboundedReceiver};
 
@@ -68,4 +68,17 @@ pub trait ServerType: Send + Sync + Sized + 'static {
         &self,
         request: RemoteServerRequestType,
     ) -> impl Future<Output = RemoteServerResponse> + Send;
+
+    /// Start using newly loaded TLS certificates for client-facing services. Called
+    /// when the management service asks for a reload, or the certificate files change.
+    /// An error means the new certificates were rejected, and the old ones are still in use.
+    fn reload_tls(&self, _tls_data: &TlsData) -> impl Future<Output = anyhow::Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Statistics specific to this server type, to be reported by the management service
+    /// alongside those collected by the network node
+    fn statistics(&self) -> BTreeMap<String, u64> {
+        BTreeMap::new()
+    }
 }
@@ -0,0 +1,7 @@
+DROP TABLE channel_accesses;
+DROP TABLE channel_roles;
+DROP TABLE channel_registrations;
+DROP TABLE nick_registrations;
+DROP TABLE account_fingerprints;
+DROP TABLE account_auth;
+DROP TABLE accounts;
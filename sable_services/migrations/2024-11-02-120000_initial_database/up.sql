@@ -0,0 +1,51 @@
+-- Only portable SQL here: the same migrations are run against PostgreSQL and SQLite
+
+CREATE TABLE accounts (
+    id BIGINT PRIMARY KEY,
+    name TEXT NOT NULL,
+    name_folded TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE account_auth (
+    account_id BIGINT PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+    password_hash TEXT NOT NULL
+);
+
+CREATE TABLE account_fingerprints (
+    account_id BIGINT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    fingerprint TEXT NOT NULL,
+
+    PRIMARY KEY (account_id, fingerprint)
+);
+
+CREATE TABLE nick_registrations (
+    id BIGINT PRIMARY KEY,
+    nick TEXT NOT NULL,
+    nick_folded TEXT NOT NULL UNIQUE,
+    account_id BIGINT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX nick_registrations_by_account ON nick_registrations (account_id);
+
+CREATE TABLE channel_registrations (
+    id BIGINT PRIMARY KEY,
+    channel_name TEXT NOT NULL,
+    channel_name_folded TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE channel_roles (
+    id BIGINT PRIMARY KEY,
+    channel_id BIGINT REFERENCES channel_registrations(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    flags TEXT NOT NULL
+);
+
+CREATE INDEX channel_roles_by_channel ON channel_roles (channel_id);
+
+CREATE TABLE channel_accesses (
+    account_id BIGINT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    channel_id BIGINT NOT NULL REFERENCES channel_registrations(id) ON DELETE CASCADE,
+    role_id BIGINT NOT NULL REFERENCES channel_roles(id) ON DELETE CASCADE,
+
+    PRIMARY KEY (account_id, channel_id)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE channel_auto_kicks (
+    id BIGINT PRIMARY KEY NOT NULL,
+    channel_id BIGINT NOT NULL REFERENCES channel_registrations(id) ON DELETE CASCADE,
+    mask TEXT NOT NULL,
+    reason TEXT,
+    setter_id BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX channel_auto_kicks_by_channel ON channel_auto_kicks (channel_id);
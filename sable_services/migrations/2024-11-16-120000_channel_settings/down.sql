@@ -0,0 +1,8 @@
+ALTER TABLE channel_registrations DROP COLUMN entry_message;
+ALTER TABLE channel_registrations DROP COLUMN topic_timestamp;
+ALTER TABLE channel_registrations DROP COLUMN topic_setter;
+ALTER TABLE channel_registrations DROP COLUMN topic_text;
+ALTER TABLE channel_registrations DROP COLUMN keep_topic;
+ALTER TABLE channel_registrations DROP COLUMN mode_lock_key;
+ALTER TABLE channel_registrations DROP COLUMN mode_lock_unset;
+ALTER TABLE channel_registrations DROP COLUMN mode_lock_set;
@@ -0,0 +1,8 @@
+ALTER TABLE channel_registrations ADD COLUMN mode_lock_set TEXT NOT NULL DEFAULT '';
+ALTER TABLE channel_registrations ADD COLUMN mode_lock_unset TEXT NOT NULL DEFAULT '';
+ALTER TABLE channel_registrations ADD COLUMN mode_lock_key TEXT;
+ALTER TABLE channel_registrations ADD COLUMN keep_topic BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE channel_registrations ADD COLUMN topic_text TEXT;
+ALTER TABLE channel_registrations ADD COLUMN topic_setter TEXT;
+ALTER TABLE channel_registrations ADD COLUMN topic_timestamp BIGINT;
+ALTER TABLE channel_registrations ADD COLUMN entry_message TEXT;
@@ -0,0 +1 @@
+ALTER TABLE channel_registrations DROP COLUMN registered;
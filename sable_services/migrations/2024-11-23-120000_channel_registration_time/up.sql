@@ -0,0 +1 @@
+ALTER TABLE channel_registrations ADD COLUMN registered BIGINT NOT NULL DEFAULT 0;
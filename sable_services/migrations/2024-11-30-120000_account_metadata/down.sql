@@ -0,0 +1,5 @@
+DROP TABLE account_public_fields;
+
+ALTER TABLE accounts DROP COLUMN private;
+ALTER TABLE accounts DROP COLUMN last_login;
+ALTER TABLE accounts DROP COLUMN registered;
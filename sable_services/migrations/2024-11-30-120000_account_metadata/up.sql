@@ -0,0 +1,11 @@
+ALTER TABLE accounts ADD COLUMN registered BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE accounts ADD COLUMN last_login BIGINT;
+ALTER TABLE accounts ADD COLUMN private BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE account_public_fields (
+    account_id BIGINT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+
+    PRIMARY KEY (account_id, name)
+);
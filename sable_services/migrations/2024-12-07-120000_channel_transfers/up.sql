@@ -0,0 +1,6 @@
+CREATE TABLE channel_transfers (
+    channel_id BIGINT PRIMARY KEY NOT NULL REFERENCES channel_registrations(id) ON DELETE CASCADE,
+    from_account_id BIGINT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    to_account_id BIGINT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    timestamp BIGINT NOT NULL
+);
@@ -26,7 +26,7 @@ pub fn main() -> Result<(), anyhow::Error> {
     let opts = Opts::from_args();
 
     sable_server::run::run_server::<
-        sable_services::ServicesServer<sable_services::database::jsonfile::JsonDatabase>,
+        sable_services::ServicesServer<sable_services::database::AnyDatabase>,
     >(
         opts.server_conf,
         opts.network_conf,
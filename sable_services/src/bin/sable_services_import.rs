@@ -0,0 +1,35 @@
+use sable_services::database::{
+    import_database, jsonfile::JsonDatabase, sql::SqlDatabase, DatabaseConnection,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all = "kebab")]
+/// One-shot import of a JSON services database into an SQL database
+struct Opts {
+    /// JSON database file to read
+    #[structopt(short, long)]
+    from: PathBuf,
+
+    /// Destination database URL (`postgres://...` or `sqlite://<path>`)
+    #[structopt(short, long)]
+    to: String,
+}
+
+pub fn main() -> Result<(), anyhow::Error> {
+    let opts = Opts::from_args();
+
+    if !opts.from.exists() {
+        anyhow::bail!("Source database {} does not exist", opts.from.display());
+    }
+
+    let source = JsonDatabase::connect(opts.from.to_string_lossy())?;
+    let dest = SqlDatabase::connect(&opts.to)?;
+
+    import_database(&source, &dest)?;
+
+    println!("Imported {} into {}", opts.from.display(), opts.to);
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use super::jsonfile::JsonDatabase;
+use super::sql::SqlDatabase;
+use super::*;
+
+/// A database provider which selects one of the concrete providers based on the
+/// configured connection string
+///
+/// URLs recognised by [`SqlDatabase`] use the SQL provider; anything else is
+/// taken to be the filename of a [`JsonDatabase`].
+pub enum AnyDatabase {
+    Json(Box<JsonDatabase>),
+    Sql(SqlDatabase),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $db:ident => $e:expr) => {
+        match $self {
+            AnyDatabase::Json($db) => $e,
+            AnyDatabase::Sql($db) => $e,
+        }
+    };
+}
+
+macro_rules! dispatch_iter {
+    ($self:ident, $db:ident => $e:expr) => {
+        match $self {
+            AnyDatabase::Json($db) => Ok(Box::new($e?) as Box<dyn Iterator<Item = _> + '_>),
+            AnyDatabase::Sql($db) => Ok(Box::new($e?) as Box<dyn Iterator<Item = _> + '_>),
+        }
+    };
+}
+
+impl DatabaseConnection for AnyDatabase {
+    fn connect(conn: impl AsRef<str>) -> Result<Self> {
+        if SqlDatabase::handles(conn.as_ref()) {
+            Ok(Self::Sql(SqlDatabase::connect(conn)?))
+        } else {
+            Ok(Self::Json(Box::new(JsonDatabase::connect(conn)?)))
+        }
+    }
+
+    fn new_account(&self, data: state::Account, auth: AccountAuth) -> Result<state::Account> {
+        dispatch!(self, db => db.new_account(data, auth))
+    }
+
+    fn account(&self, id: AccountId) -> Result<state::Account> {
+        dispatch!(self, db => db.account(id))
+    }
+
+    fn account_named(&self, name: &Nickname) -> Result<state::Account> {
+        dispatch!(self, db => db.account_named(name))
+    }
+
+    fn update_account(&self, new_data: &state::Account) -> Result<()> {
+        dispatch!(self, db => db.update_account(new_data))
+    }
+
+    fn all_accounts(&self) -> Result<impl Iterator<Item = state::Account> + '_> {
+        dispatch_iter!(self, db => db.all_accounts())
+    }
+
+    fn auth_for_account(&self, id: AccountId) -> Result<AccountAuth> {
+        dispatch!(self, db => db.auth_for_account(id))
+    }
+
+    fn new_nick_registration(
+        &self,
+        data: state::NickRegistration,
+    ) -> Result<state::NickRegistration> {
+        dispatch!(self, db => db.new_nick_registration(data))
+    }
+
+    fn nick_registration(&self, id: NickRegistrationId) -> Result<state::NickRegistration> {
+        dispatch!(self, db => db.nick_registration(id))
+    }
+
+    fn update_nick_registration(&self, new_data: &state::NickRegistration) -> Result<()> {
+        dispatch!(self, db => db.update_nick_registration(new_data))
+    }
+
+    fn all_nick_registrations(&self) -> Result<impl Iterator<Item = state::NickRegistration> + '_> {
+        dispatch_iter!(self, db => db.all_nick_registrations())
+    }
+
+    fn new_channel_registration(
+        &self,
+        data: state::ChannelRegistration,
+    ) -> Result<state::ChannelRegistration> {
+        dispatch!(self, db => db.new_channel_registration(data))
+    }
+
+    fn channel_registration(
+        &self,
+        id: ChannelRegistrationId,
+    ) -> Result<state::ChannelRegistration> {
+        dispatch!(self, db => db.channel_registration(id))
+    }
+
+    fn update_channel_registration(&self, new_data: &state::ChannelRegistration) -> Result<()> {
+        dispatch!(self, db => db.update_channel_registration(new_data))
+    }
+
+    fn remove_channel_registration(&self, id: ChannelRegistrationId) -> Result<()> {
+        dispatch!(self, db => db.remove_channel_registration(id))
+    }
+
+    fn all_channel_registrations(
+        &self,
+    ) -> Result<impl Iterator<Item = state::ChannelRegistration> + '_> {
+        dispatch_iter!(self, db => db.all_channel_registrations())
+    }
+
+    fn new_channel_role(&self, data: state::ChannelRole) -> Result<state::ChannelRole> {
+        dispatch!(self, db => db.new_channel_role(data))
+    }
+
+    fn channel_role(&self, id: ChannelRoleId) -> Result<state::ChannelRole> {
+        dispatch!(self, db => db.channel_role(id))
+    }
+
+    fn update_channel_role(&self, data: &state::ChannelRole) -> Result<()> {
+        dispatch!(self, db => db.update_channel_role(data))
+    }
+
+    fn all_channel_roles(&self) -> Result<impl Iterator<Item = state::ChannelRole> + '_> {
+        dispatch_iter!(self, db => db.all_channel_roles())
+    }
+
+    fn remove_channel_role(&self, id: ChannelRoleId) -> Result<()> {
+        dispatch!(self, db => db.remove_channel_role(id))
+    }
+
+    fn update_channel_access(&self, data: &state::ChannelAccess) -> Result<()> {
+        dispatch!(self, db => db.update_channel_access(data))
+    }
+
+    fn channel_access(&self, id: ChannelAccessId) -> Result<state::ChannelAccess> {
+        dispatch!(self, db => db.channel_access(id))
+    }
+
+    fn all_channel_accesses(&self) -> Result<impl Iterator<Item = state::ChannelAccess> + '_> {
+        dispatch_iter!(self, db => db.all_channel_accesses())
+    }
+
+    fn remove_channel_access(&self, id: ChannelAccessId) -> Result<()> {
+        dispatch!(self, db => db.remove_channel_access(id))
+    }
+
+    fn new_channel_auto_kick(
+        &self,
+        data: state::ChannelAutoKick,
+    ) -> Result<state::ChannelAutoKick> {
+        dispatch!(self, db => db.new_channel_auto_kick(data))
+    }
+
+    fn channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<state::ChannelAutoKick> {
+        dispatch!(self, db => db.channel_auto_kick(id))
+    }
+
+    fn all_channel_auto_kicks(&self) -> Result<impl Iterator<Item = state::ChannelAutoKick> + '_> {
+        dispatch_iter!(self, db => db.all_channel_auto_kicks())
+    }
+
+    fn remove_channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<()> {
+        dispatch!(self, db => db.remove_channel_auto_kick(id))
+    }
+
+    fn update_channel_transfer(&self, data: &ChannelTransfer) -> Result<()> {
+        dispatch!(self, db => db.update_channel_transfer(data))
+    }
+
+    fn channel_transfer(&self, channel: ChannelRegistrationId) -> Result<ChannelTransfer> {
+        dispatch!(self, db => db.channel_transfer(channel))
+    }
+
+    fn remove_channel_transfer(&self, channel: ChannelRegistrationId) -> Result<()> {
+        dispatch!(self, db => db.remove_channel_transfer(channel))
+    }
+}
@@ -35,6 +35,14 @@ struct JsonDatabaseState {
 
     #[serde_as(as = "Vec<(_,_)>")]
     channel_accesses: HashMap<ChannelAccessId, state::ChannelAccess>,
+
+    #[serde_as(as = "Vec<(_,_)>")]
+    #[serde(default)]
+    channel_auto_kicks: HashMap<ChannelAutoKickId, state::ChannelAutoKick>,
+
+    #[serde_as(as = "Vec<(_,_)>")]
+    #[serde(default)]
+    channel_transfers: HashMap<ChannelRegistrationId, ChannelTransfer>,
 }
 
 #[ouroboros::self_referencing]
@@ -248,6 +256,26 @@ impl DatabaseConnection for JsonDatabase {
         ret
     }
 
+    fn remove_channel_registration(&self, id: ChannelRegistrationId) -> Result<()> {
+        {
+            let mut state = self.state.write();
+            if state.channel_registrations.remove(&id).is_none() {
+                return Err(DatabaseError::NoSuchId);
+            }
+            state
+                .channel_roles
+                .retain(|_, role| role.channel != Some(id));
+            state
+                .channel_accesses
+                .retain(|access_id, _| access_id.channel() != id);
+            state
+                .channel_auto_kicks
+                .retain(|_, entry| entry.channel != id);
+            state.channel_transfers.remove(&id);
+        }
+        self.save()
+    }
+
     fn all_channel_registrations(
         &self,
     ) -> Result<impl Iterator<Item = state::ChannelRegistration> + '_> {
@@ -289,9 +317,10 @@ impl DatabaseConnection for JsonDatabase {
     }
 
     fn remove_channel_access(&self, id: ChannelAccessId) -> Result<()> {
-        self.state.write().channel_accesses.remove(&id);
-        self.save()?;
-        Ok(())
+        if self.state.write().channel_accesses.remove(&id).is_none() {
+            return Err(DatabaseError::NoSuchId);
+        }
+        self.save()
     }
 
     fn new_channel_role(&self, data: state::ChannelRole) -> Result<state::ChannelRole> {
@@ -334,7 +363,67 @@ impl DatabaseConnection for JsonDatabase {
     }
 
     fn remove_channel_role(&self, id: ChannelRoleId) -> Result<()> {
-        self.state.write().channel_roles.remove(&id);
+        if self.state.write().channel_roles.remove(&id).is_none() {
+            return Err(DatabaseError::NoSuchId);
+        }
+        self.save()
+    }
+
+    fn new_channel_auto_kick(
+        &self,
+        data: state::ChannelAutoKick,
+    ) -> Result<state::ChannelAutoKick> {
+        let ret = match self.state.write().channel_auto_kicks.entry(data.id) {
+            Entry::Occupied(_) => Err(DatabaseError::DuplicateId),
+            Entry::Vacant(entry) => Ok(entry.insert(data).clone()),
+        };
+
+        self.save()?;
+        ret
+    }
+
+    fn channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<state::ChannelAutoKick> {
+        self.state
+            .read()
+            .channel_auto_kicks
+            .get(&id)
+            .ok_or(DatabaseError::NoSuchId)
+            .cloned()
+    }
+
+    fn all_channel_auto_kicks(&self) -> Result<impl Iterator<Item = state::ChannelAutoKick> + '_> {
+        Ok(LockedHashMapValueIterator::new(
+            self.state.read(),
+            |state| state.channel_auto_kicks.values(),
+        ))
+    }
+
+    fn remove_channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<()> {
+        if self.state.write().channel_auto_kicks.remove(&id).is_none() {
+            return Err(DatabaseError::NoSuchId);
+        }
+        self.save()
+    }
+
+    fn update_channel_transfer(&self, data: &ChannelTransfer) -> Result<()> {
+        self.state
+            .write()
+            .channel_transfers
+            .insert(data.channel, data.clone());
+        self.save()
+    }
+
+    fn channel_transfer(&self, channel: ChannelRegistrationId) -> Result<ChannelTransfer> {
+        self.state
+            .read()
+            .channel_transfers
+            .get(&channel)
+            .ok_or(DatabaseError::NoSuchId)
+            .cloned()
+    }
+
+    fn remove_channel_transfer(&self, channel: ChannelRegistrationId) -> Result<()> {
+        self.state.write().channel_transfers.remove(&channel);
         self.save()
     }
 }
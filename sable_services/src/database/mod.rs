@@ -71,6 +71,9 @@ pub trait DatabaseConnection: Sized + Send + Sync + 'static {
         -> Result<state::ChannelRegistration>;
     /// Update a channel registration
     fn update_channel_registration(&self, new_data: &state::ChannelRegistration) -> Result<()>;
+    /// Remove a channel registration, along with its roles, accesses and auto-kick entries.
+    /// Returns [`DatabaseError::NoSuchId`] if it doesn't exist
+    fn remove_channel_registration(&self, id: ChannelRegistrationId) -> Result<()>;
     /// Retrieve all channel registrations in the database
     fn all_channel_registrations(
         &self,
@@ -84,7 +87,7 @@ pub trait DatabaseConnection: Sized + Send + Sync + 'static {
     fn update_channel_role(&self, data: &state::ChannelRole) -> Result<()>;
     /// Retrieve all channel roles in the database
     fn all_channel_roles(&self) -> Result<impl Iterator<Item = state::ChannelRole> + '_>;
-    /// Remove a channel role
+    /// Remove a channel role. Returns [`DatabaseError::NoSuchId`] if it doesn't exist
     fn remove_channel_role(&self, id: ChannelRoleId) -> Result<()>;
 
     /// Create or update a channel access
@@ -95,8 +98,56 @@ pub trait DatabaseConnection: Sized + Send + Sync + 'static {
     fn channel_access(&self, id: ChannelAccessId) -> Result<state::ChannelAccess>;
     /// Retrieve all channel accesses in the database
     fn all_channel_accesses(&self) -> Result<impl Iterator<Item = state::ChannelAccess> + '_>;
-    /// Remove a channel access
+    /// Remove a channel access. Returns [`DatabaseError::NoSuchId`] if it doesn't exist
     fn remove_channel_access(&self, id: ChannelAccessId) -> Result<()>;
+
+    /// Create a new channel auto-kick entry
+    fn new_channel_auto_kick(&self, data: state::ChannelAutoKick)
+        -> Result<state::ChannelAutoKick>;
+    /// Retrieve a channel auto-kick entry
+    fn channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<state::ChannelAutoKick>;
+    /// Retrieve all channel auto-kick entries in the database
+    fn all_channel_auto_kicks(&self) -> Result<impl Iterator<Item = state::ChannelAutoKick> + '_>;
+    /// Remove a channel auto-kick entry. Returns [`DatabaseError::NoSuchId`] if it doesn't exist
+    fn remove_channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<()>;
+
+    /// Record a pending ownership transfer, replacing any existing offer for the channel
+    fn update_channel_transfer(&self, data: &ChannelTransfer) -> Result<()>;
+    /// Retrieve the pending ownership transfer for a channel
+    fn channel_transfer(&self, channel: ChannelRegistrationId) -> Result<ChannelTransfer>;
+    /// Remove the pending ownership transfer for a channel, if there is one
+    fn remove_channel_transfer(&self, channel: ChannelRegistrationId) -> Result<()>;
+}
+
+/// Copy the entire contents of one database into another, for migrating between
+/// providers. The destination is expected to be empty. Pending channel transfers are
+/// short-lived and aren't copied.
+pub fn import_database(from: &impl DatabaseConnection, to: &impl DatabaseConnection) -> Result<()> {
+    for account in from.all_accounts()? {
+        let auth = from.auth_for_account(account.id)?;
+        to.new_account(account, auth)?;
+    }
+    for nick in from.all_nick_registrations()? {
+        to.new_nick_registration(nick)?;
+    }
+    for channel in from.all_channel_registrations()? {
+        to.new_channel_registration(channel)?;
+    }
+    for role in from.all_channel_roles()? {
+        to.new_channel_role(role)?;
+    }
+    for access in from.all_channel_accesses()? {
+        to.update_channel_access(&access)?;
+    }
+    for auto_kick in from.all_channel_auto_kicks()? {
+        to.new_channel_auto_kick(auto_kick)?;
+    }
+
+    Ok(())
 }
 
 pub mod jsonfile;
+pub mod sql;
+
+mod any;
+pub use any::AnyDatabase;
@@ -0,0 +1,667 @@
+//! SQL-backed database provider, supporting PostgreSQL and SQLite
+
+use std::collections::{BTreeMap, HashMap};
+
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use parking_lot::Mutex;
+
+use super::*;
+
+mod models;
+mod schema;
+
+#[cfg(test)]
+mod tests;
+
+use models::*;
+use schema::*;
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+/// A connection to one of the supported SQL backends
+#[derive(diesel::MultiConnection)]
+pub enum AnyConnection {
+    Postgresql(diesel::PgConnection),
+    Sqlite(diesel::SqliteConnection),
+}
+
+/// A database provider storing services data in PostgreSQL or SQLite
+///
+/// The connection string is a URL; `postgres://` or `postgresql://` URLs connect
+/// to a PostgreSQL server, and `sqlite://<path>` opens (or creates) an SQLite
+/// database file. Pending migrations are applied on connection.
+pub struct SqlDatabase {
+    connection: Mutex<AnyConnection>,
+}
+
+impl From<DieselError> for DatabaseError {
+    fn from(error: DieselError) -> Self {
+        match error {
+            DieselError::NotFound => DatabaseError::NoSuchId,
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                DatabaseError::DuplicateName
+            }
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                DatabaseError::InvalidData
+            }
+            other => DatabaseError::from_inner(other),
+        }
+    }
+}
+
+impl SqlDatabase {
+    /// Whether `conn` names a database that this provider can connect to
+    pub fn handles(conn: &str) -> bool {
+        conn.starts_with("postgres://")
+            || conn.starts_with("postgresql://")
+            || conn.starts_with("sqlite://")
+    }
+
+    fn establish(conn: &str) -> Result<AnyConnection> {
+        if let Some(path) = conn.strip_prefix("sqlite://") {
+            let mut connection =
+                diesel::SqliteConnection::establish(path).map_err(DatabaseError::from_inner)?;
+            // SQLite doesn't enforce foreign keys unless asked to
+            diesel::sql_query("PRAGMA foreign_keys = ON").execute(&mut connection)?;
+            Ok(AnyConnection::Sqlite(connection))
+        } else if Self::handles(conn) {
+            Ok(AnyConnection::Postgresql(
+                diesel::PgConnection::establish(conn).map_err(DatabaseError::from_inner)?,
+            ))
+        } else {
+            Err(anyhow::anyhow!("Unsupported database URL").into())
+        }
+    }
+
+    fn run_migrations(connection: &mut AnyConnection) -> Result<()> {
+        let applied = match connection {
+            AnyConnection::Postgresql(conn) => conn.run_pending_migrations(MIGRATIONS),
+            AnyConnection::Sqlite(conn) => conn.run_pending_migrations(MIGRATIONS),
+        }
+        .map_err(|e| anyhow::anyhow!("Database migrations failed: {e}"))?;
+
+        if applied.is_empty() {
+            tracing::info!("No database migrations to run");
+        } else {
+            for migration in applied {
+                tracing::info!(%migration, "Applied database migration");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fingerprints_for(conn: &mut AnyConnection, account: i64) -> Result<Vec<String>> {
+        Ok(account_fingerprints::table
+            .filter(account_fingerprints::account_id.eq(account))
+            .select(account_fingerprints::fingerprint)
+            .order(account_fingerprints::fingerprint)
+            .load(conn)?)
+    }
+
+    fn public_fields_for(
+        conn: &mut AnyConnection,
+        account: i64,
+    ) -> Result<BTreeMap<String, String>> {
+        Ok(account_public_fields::table
+            .filter(account_public_fields::account_id.eq(account))
+            .select((account_public_fields::name, account_public_fields::value))
+            .load::<(String, String)>(conn)?
+            .into_iter()
+            .collect())
+    }
+
+    fn load_account(conn: &mut AnyConnection, row: AccountRow) -> Result<state::Account> {
+        let fingerprints = Self::fingerprints_for(conn, row.id)?;
+        let public_fields = Self::public_fields_for(conn, row.id)?;
+        row.into_state(fingerprints, public_fields)
+    }
+
+    /// Write the fingerprints and public fields for an account, which are stored in
+    /// their own tables. Any existing rows must have been removed first.
+    fn insert_account_details(
+        conn: &mut AnyConnection,
+        account: i64,
+        data: &state::Account,
+    ) -> Result<()> {
+        for fingerprint in &data.authorised_fingerprints {
+            diesel::insert_into(account_fingerprints::table)
+                .values(AccountFingerprintRow {
+                    account_id: account,
+                    fingerprint: fingerprint.clone(),
+                })
+                .execute(conn)?;
+        }
+
+        for (name, value) in &data.public_fields {
+            diesel::insert_into(account_public_fields::table)
+                .values(AccountPublicFieldRow {
+                    account_id: account,
+                    name: name.clone(),
+                    value: value.clone(),
+                })
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DatabaseConnection for SqlDatabase {
+    fn connect(conn: impl AsRef<str>) -> Result<Self> {
+        let mut connection = Self::establish(conn.as_ref())?;
+
+        Self::run_migrations(&mut connection)?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn new_account(&self, data: state::Account, mut auth: AccountAuth) -> Result<state::Account> {
+        let conn = &mut *self.connection.lock();
+
+        // Just in case
+        auth.account = data.id;
+
+        conn.transaction(|conn| {
+            let row = AccountRow::from(&data);
+
+            let existing: i64 = accounts::table
+                .filter(accounts::id.eq(row.id))
+                .count()
+                .get_result(conn)?;
+            if existing > 0 {
+                return Err(DatabaseError::DuplicateId);
+            }
+
+            diesel::insert_into(accounts::table)
+                .values(&row)
+                .execute(conn)?;
+            diesel::insert_into(account_auth::table)
+                .values(AccountAuthRow::from(&auth))
+                .execute(conn)?;
+
+            Self::insert_account_details(conn, row.id, &data)?;
+
+            Ok(data)
+        })
+    }
+
+    fn account(&self, id: AccountId) -> Result<state::Account> {
+        let conn = &mut *self.connection.lock();
+
+        let row = accounts::table
+            .find(id_to_db(&id))
+            .select(AccountRow::as_select())
+            .first(conn)?;
+
+        Self::load_account(conn, row)
+    }
+
+    fn account_named(&self, name: &Nickname) -> Result<state::Account> {
+        let conn = &mut *self.connection.lock();
+
+        let row = accounts::table
+            .filter(accounts::name_folded.eq(name.casefold()))
+            .select(AccountRow::as_select())
+            .first(conn)?;
+
+        Self::load_account(conn, row)
+    }
+
+    fn update_account(&self, new_data: &state::Account) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        conn.transaction(|conn| {
+            let row = AccountRow::from(new_data);
+
+            let updated = diesel::update(accounts::table.find(row.id))
+                .set((
+                    accounts::name.eq(&row.name),
+                    accounts::name_folded.eq(&row.name_folded),
+                    accounts::registered.eq(row.registered),
+                    accounts::last_login.eq(row.last_login),
+                    accounts::private.eq(row.private),
+                ))
+                .execute(conn)?;
+            if updated == 0 {
+                return Err(DatabaseError::NoSuchId);
+            }
+
+            diesel::delete(
+                account_fingerprints::table.filter(account_fingerprints::account_id.eq(row.id)),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                account_public_fields::table.filter(account_public_fields::account_id.eq(row.id)),
+            )
+            .execute(conn)?;
+
+            Self::insert_account_details(conn, row.id, new_data)?;
+
+            Ok(())
+        })
+    }
+
+    fn all_accounts(&self) -> Result<impl Iterator<Item = state::Account> + '_> {
+        let conn = &mut *self.connection.lock();
+
+        let rows = accounts::table.select(AccountRow::as_select()).load(conn)?;
+
+        let mut fingerprints: HashMap<i64, Vec<String>> = HashMap::new();
+        for row in account_fingerprints::table
+            .select(AccountFingerprintRow::as_select())
+            .order(account_fingerprints::fingerprint)
+            .load(conn)?
+        {
+            fingerprints
+                .entry(row.account_id)
+                .or_default()
+                .push(row.fingerprint);
+        }
+
+        let mut public_fields: HashMap<i64, BTreeMap<String, String>> = HashMap::new();
+        for row in account_public_fields::table
+            .select(AccountPublicFieldRow::as_select())
+            .load(conn)?
+        {
+            public_fields
+                .entry(row.account_id)
+                .or_default()
+                .insert(row.name, row.value);
+        }
+
+        let accounts = rows
+            .into_iter()
+            .map(|row| {
+                let fps = fingerprints.remove(&row.id).unwrap_or_default();
+                let fields = public_fields.remove(&row.id).unwrap_or_default();
+                row.into_state(fps, fields)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(accounts.into_iter())
+    }
+
+    fn auth_for_account(&self, id: AccountId) -> Result<AccountAuth> {
+        let conn = &mut *self.connection.lock();
+
+        Ok(account_auth::table
+            .find(id_to_db(&id))
+            .select(AccountAuthRow::as_select())
+            .first(conn)?
+            .into())
+    }
+
+    fn new_nick_registration(
+        &self,
+        data: state::NickRegistration,
+    ) -> Result<state::NickRegistration> {
+        let conn = &mut *self.connection.lock();
+
+        conn.transaction(|conn| {
+            let row = NickRegistrationRow::from(&data);
+
+            let existing: i64 = nick_registrations::table
+                .filter(nick_registrations::id.eq(row.id))
+                .count()
+                .get_result(conn)?;
+            if existing > 0 {
+                return Err(DatabaseError::DuplicateId);
+            }
+
+            diesel::insert_into(nick_registrations::table)
+                .values(&row)
+                .execute(conn)?;
+
+            Ok(data)
+        })
+    }
+
+    fn nick_registration(&self, id: NickRegistrationId) -> Result<state::NickRegistration> {
+        let conn = &mut *self.connection.lock();
+
+        nick_registrations::table
+            .find(id_to_db(&id))
+            .select(NickRegistrationRow::as_select())
+            .first(conn)?
+            .try_into()
+    }
+
+    fn update_nick_registration(&self, new_data: &state::NickRegistration) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let row = NickRegistrationRow::from(new_data);
+        let updated = diesel::update(nick_registrations::table.find(row.id))
+            .set(&row)
+            .execute(conn)?;
+
+        if updated == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn all_nick_registrations(&self) -> Result<impl Iterator<Item = state::NickRegistration> + '_> {
+        let conn = &mut *self.connection.lock();
+
+        let registrations = nick_registrations::table
+            .select(NickRegistrationRow::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(registrations.into_iter())
+    }
+
+    fn new_channel_registration(
+        &self,
+        data: state::ChannelRegistration,
+    ) -> Result<state::ChannelRegistration> {
+        let conn = &mut *self.connection.lock();
+
+        conn.transaction(|conn| {
+            let row = ChannelRegistrationRow::from(&data);
+
+            let existing: i64 = channel_registrations::table
+                .filter(channel_registrations::id.eq(row.id))
+                .count()
+                .get_result(conn)?;
+            if existing > 0 {
+                return Err(DatabaseError::DuplicateId);
+            }
+
+            diesel::insert_into(channel_registrations::table)
+                .values(&row)
+                .execute(conn)?;
+
+            Ok(data)
+        })
+    }
+
+    fn channel_registration(
+        &self,
+        id: ChannelRegistrationId,
+    ) -> Result<state::ChannelRegistration> {
+        let conn = &mut *self.connection.lock();
+
+        channel_registrations::table
+            .find(id_to_db(&id))
+            .select(ChannelRegistrationRow::as_select())
+            .first(conn)?
+            .try_into()
+    }
+
+    fn update_channel_registration(&self, new_data: &state::ChannelRegistration) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let row = ChannelRegistrationRow::from(new_data);
+        let updated = diesel::update(channel_registrations::table.find(row.id))
+            .set(&row)
+            .execute(conn)?;
+
+        if updated == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn remove_channel_registration(&self, id: ChannelRegistrationId) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        // Roles, accesses, auto-kicks and transfers are removed by their foreign key constraints
+        let deleted =
+            diesel::delete(channel_registrations::table.find(id_to_db(&id))).execute(conn)?;
+
+        if deleted == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn all_channel_registrations(
+        &self,
+    ) -> Result<impl Iterator<Item = state::ChannelRegistration> + '_> {
+        let conn = &mut *self.connection.lock();
+
+        let registrations = channel_registrations::table
+            .select(ChannelRegistrationRow::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(registrations.into_iter())
+    }
+
+    fn new_channel_role(&self, data: state::ChannelRole) -> Result<state::ChannelRole> {
+        let conn = &mut *self.connection.lock();
+
+        conn.transaction(|conn| {
+            let row = ChannelRoleRow::from(&data);
+
+            let existing: i64 = channel_roles::table
+                .filter(channel_roles::id.eq(row.id))
+                .count()
+                .get_result(conn)?;
+            if existing > 0 {
+                return Err(DatabaseError::DuplicateId);
+            }
+
+            diesel::insert_into(channel_roles::table)
+                .values(&row)
+                .execute(conn)?;
+
+            Ok(data)
+        })
+    }
+
+    fn channel_role(&self, id: ChannelRoleId) -> Result<state::ChannelRole> {
+        let conn = &mut *self.connection.lock();
+
+        channel_roles::table
+            .find(id_to_db(&id))
+            .select(ChannelRoleRow::as_select())
+            .first(conn)?
+            .try_into()
+    }
+
+    fn update_channel_role(&self, data: &state::ChannelRole) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let row = ChannelRoleRow::from(data);
+        let updated = diesel::update(channel_roles::table.find(row.id))
+            .set(&row)
+            .execute(conn)?;
+
+        if updated == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn all_channel_roles(&self) -> Result<impl Iterator<Item = state::ChannelRole> + '_> {
+        let conn = &mut *self.connection.lock();
+
+        let roles = channel_roles::table
+            .select(ChannelRoleRow::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(roles.into_iter())
+    }
+
+    fn remove_channel_role(&self, id: ChannelRoleId) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let deleted = diesel::delete(channel_roles::table.find(id_to_db(&id))).execute(conn)?;
+
+        if deleted == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update_channel_access(&self, data: &state::ChannelAccess) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let row = ChannelAccessRow::from(data);
+
+        conn.transaction(|conn| {
+            let updated =
+                diesel::update(channel_accesses::table.find((row.account_id, row.channel_id)))
+                    .set(channel_accesses::role_id.eq(row.role_id))
+                    .execute(conn)?;
+
+            if updated == 0 {
+                diesel::insert_into(channel_accesses::table)
+                    .values(&row)
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn channel_access(&self, id: ChannelAccessId) -> Result<state::ChannelAccess> {
+        let conn = &mut *self.connection.lock();
+
+        Ok(channel_accesses::table
+            .find((id_to_db(&id.account()), id_to_db(&id.channel())))
+            .select(ChannelAccessRow::as_select())
+            .first(conn)?
+            .into())
+    }
+
+    fn all_channel_accesses(&self) -> Result<impl Iterator<Item = state::ChannelAccess> + '_> {
+        let conn = &mut *self.connection.lock();
+
+        Ok(channel_accesses::table
+            .select(ChannelAccessRow::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(state::ChannelAccess::from))
+    }
+
+    fn remove_channel_access(&self, id: ChannelAccessId) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let deleted = diesel::delete(
+            channel_accesses::table.find((id_to_db(&id.account()), id_to_db(&id.channel()))),
+        )
+        .execute(conn)?;
+
+        if deleted == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn new_channel_auto_kick(
+        &self,
+        data: state::ChannelAutoKick,
+    ) -> Result<state::ChannelAutoKick> {
+        let conn = &mut *self.connection.lock();
+
+        conn.transaction(|conn| {
+            let row = ChannelAutoKickRow::from(&data);
+
+            let existing: i64 = channel_auto_kicks::table
+                .filter(channel_auto_kicks::id.eq(row.id))
+                .count()
+                .get_result(conn)?;
+            if existing > 0 {
+                return Err(DatabaseError::DuplicateId);
+            }
+
+            diesel::insert_into(channel_auto_kicks::table)
+                .values(&row)
+                .execute(conn)?;
+
+            Ok(data)
+        })
+    }
+
+    fn channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<state::ChannelAutoKick> {
+        let conn = &mut *self.connection.lock();
+
+        Ok(channel_auto_kicks::table
+            .find(id_to_db(&id))
+            .select(ChannelAutoKickRow::as_select())
+            .first(conn)?
+            .into())
+    }
+
+    fn all_channel_auto_kicks(&self) -> Result<impl Iterator<Item = state::ChannelAutoKick> + '_> {
+        let conn = &mut *self.connection.lock();
+
+        Ok(channel_auto_kicks::table
+            .select(ChannelAutoKickRow::as_select())
+            .load(conn)?
+            .into_iter()
+            .map(state::ChannelAutoKick::from))
+    }
+
+    fn remove_channel_auto_kick(&self, id: ChannelAutoKickId) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let deleted =
+            diesel::delete(channel_auto_kicks::table.find(id_to_db(&id))).execute(conn)?;
+
+        if deleted == 0 {
+            Err(DatabaseError::NoSuchId)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update_channel_transfer(&self, data: &ChannelTransfer) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        let row = ChannelTransferRow::from(data);
+
+        conn.transaction(|conn| {
+            let updated = diesel::update(channel_transfers::table.find(row.channel_id))
+                .set(&row)
+                .execute(conn)?;
+
+            if updated == 0 {
+                diesel::insert_into(channel_transfers::table)
+                    .values(&row)
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn channel_transfer(&self, channel: ChannelRegistrationId) -> Result<ChannelTransfer> {
+        let conn = &mut *self.connection.lock();
+
+        Ok(channel_transfers::table
+            .find(id_to_db(&channel))
+            .select(ChannelTransferRow::as_select())
+            .first(conn)?
+            .into())
+    }
+
+    fn remove_channel_transfer(&self, channel: ChannelRegistrationId) -> Result<()> {
+        let conn = &mut *self.connection.lock();
+
+        diesel::delete(channel_transfers::table.find(id_to_db(&channel))).execute(conn)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+//! Row types for the SQL database provider, and conversions to and from the
+//! network state objects they store
+
+use super::schema::*;
+use super::*;
+
+use std::collections::BTreeMap;
+
+/// Convert a snowflake-based object ID to its database representation
+pub(super) fn id_to_db(id: &Snowflake) -> i64 {
+    id.as_u64() as i64
+}
+
+/// Convert a database ID column back into a snowflake-based object ID
+pub(super) fn id_from_db<T: From<Snowflake>>(id: i64) -> T {
+    Snowflake::from(id as u64).into()
+}
+
+fn parse_validated<T: Validated>(value: &str) -> Result<T> {
+    value.parse().map_err(|_| DatabaseError::InvalidData)
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = accounts)]
+pub(super) struct AccountRow {
+    pub id: i64,
+    pub name: String,
+    pub name_folded: String,
+    pub registered: i64,
+    pub last_login: Option<i64>,
+    pub private: bool,
+}
+
+impl From<&state::Account> for AccountRow {
+    fn from(value: &state::Account) -> Self {
+        Self {
+            id: id_to_db(&value.id),
+            name: value.name.to_string(),
+            name_folded: value.name.casefold(),
+            registered: value.registered,
+            last_login: value.last_login,
+            private: value.private,
+        }
+    }
+}
+
+impl AccountRow {
+    pub fn into_state(
+        self,
+        authorised_fingerprints: Vec<String>,
+        public_fields: BTreeMap<String, String>,
+    ) -> Result<state::Account> {
+        Ok(state::Account {
+            id: id_from_db(self.id),
+            name: parse_validated(&self.name)?,
+            authorised_fingerprints,
+            registered: self.registered,
+            last_login: self.last_login,
+            public_fields,
+            private: self.private,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = account_auth)]
+pub(super) struct AccountAuthRow {
+    pub account_id: i64,
+    pub password_hash: String,
+}
+
+impl From<&AccountAuth> for AccountAuthRow {
+    fn from(value: &AccountAuth) -> Self {
+        Self {
+            account_id: id_to_db(&value.account),
+            password_hash: value.password_hash.clone(),
+        }
+    }
+}
+
+impl From<AccountAuthRow> for AccountAuth {
+    fn from(value: AccountAuthRow) -> Self {
+        Self {
+            account: id_from_db(value.account_id),
+            password_hash: value.password_hash,
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = account_fingerprints)]
+pub(super) struct AccountFingerprintRow {
+    pub account_id: i64,
+    pub fingerprint: String,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = account_public_fields)]
+pub(super) struct AccountPublicFieldRow {
+    pub account_id: i64,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = nick_registrations)]
+pub(super) struct NickRegistrationRow {
+    pub id: i64,
+    pub nick: String,
+    pub nick_folded: String,
+    pub account_id: i64,
+}
+
+impl From<&state::NickRegistration> for NickRegistrationRow {
+    fn from(value: &state::NickRegistration) -> Self {
+        Self {
+            id: id_to_db(&value.id),
+            nick: value.nick.to_string(),
+            nick_folded: value.nick.casefold(),
+            account_id: id_to_db(&value.account),
+        }
+    }
+}
+
+impl TryFrom<NickRegistrationRow> for state::NickRegistration {
+    type Error = DatabaseError;
+
+    fn try_from(value: NickRegistrationRow) -> Result<Self> {
+        Ok(Self {
+            id: id_from_db(value.id),
+            nick: parse_validated(&value.nick)?,
+            account: id_from_db(value.account_id),
+        })
+    }
+}
+
+/// Mode sets are stored as their mode characters, e.g. `nt`
+fn parse_channel_modes(value: &str) -> Result<ChannelModeSet> {
+    value.chars().try_fold(ChannelModeSet::new(), |set, c| {
+        ChannelModeFlag::from_mode_char(c)
+            .map(|flag| set | flag)
+            .ok_or(DatabaseError::InvalidData)
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = channel_registrations)]
+#[diesel(treat_none_as_null = true)]
+pub(super) struct ChannelRegistrationRow {
+    pub id: i64,
+    pub channel_name: String,
+    pub channel_name_folded: String,
+    pub mode_lock_set: String,
+    pub mode_lock_unset: String,
+    pub mode_lock_key: Option<String>,
+    pub keep_topic: bool,
+    pub topic_text: Option<String>,
+    pub topic_setter: Option<String>,
+    pub topic_timestamp: Option<i64>,
+    pub entry_message: Option<String>,
+    pub registered: i64,
+}
+
+impl From<&state::ChannelRegistration> for ChannelRegistrationRow {
+    fn from(value: &state::ChannelRegistration) -> Self {
+        Self {
+            id: id_to_db(&value.id),
+            channel_name: value.channelname.to_string(),
+            channel_name_folded: value.channelname.casefold(),
+            mode_lock_set: value.mode_lock.set.to_chars(),
+            mode_lock_unset: value.mode_lock.unset.to_chars(),
+            mode_lock_key: value.mode_lock.key.map(|k| k.to_string()),
+            keep_topic: value.keep_topic,
+            topic_text: value.topic.as_ref().map(|t| t.text.clone()),
+            topic_setter: value.topic.as_ref().map(|t| t.setter_info.clone()),
+            topic_timestamp: value.topic.as_ref().map(|t| t.timestamp),
+            entry_message: value.entry_message.clone(),
+            registered: value.registered,
+        }
+    }
+}
+
+impl TryFrom<ChannelRegistrationRow> for state::ChannelRegistration {
+    type Error = DatabaseError;
+
+    fn try_from(value: ChannelRegistrationRow) -> Result<Self> {
+        let mode_lock = state::ChannelModeLock {
+            set: parse_channel_modes(&value.mode_lock_set)?,
+            unset: parse_channel_modes(&value.mode_lock_unset)?,
+            key: value
+                .mode_lock_key
+                .as_deref()
+                .map(parse_validated)
+                .transpose()?,
+        };
+
+        let topic = match (value.topic_text, value.topic_setter, value.topic_timestamp) {
+            (Some(text), Some(setter_info), Some(timestamp)) => Some(state::RetainedTopic {
+                text,
+                setter_info,
+                timestamp,
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            id: id_from_db(value.id),
+            channelname: parse_validated(&value.channel_name)?,
+            registered: value.registered,
+            mode_lock,
+            keep_topic: value.keep_topic,
+            topic,
+            entry_message: value.entry_message,
+        })
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = channel_roles)]
+#[diesel(treat_none_as_null = true)]
+pub(super) struct ChannelRoleRow {
+    pub id: i64,
+    pub channel_id: Option<i64>,
+    pub name: String,
+    /// Stored in the same comma-separated form used by the human-readable config format
+    pub flags: String,
+}
+
+impl From<&state::ChannelRole> for ChannelRoleRow {
+    fn from(value: &state::ChannelRole) -> Self {
+        Self {
+            id: id_to_db(&value.id),
+            channel_id: value.channel.as_ref().map(|c| id_to_db(c)),
+            name: value.name.to_string(),
+            flags: state::HumanReadableChannelAccessSet::from(value.flags).to_string(),
+        }
+    }
+}
+
+impl TryFrom<ChannelRoleRow> for state::ChannelRole {
+    type Error = DatabaseError;
+
+    fn try_from(value: ChannelRoleRow) -> Result<Self> {
+        let flags = value
+            .flags
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<state::ChannelAccessFlag>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| DatabaseError::InvalidData)?;
+
+        Ok(Self {
+            id: id_from_db(value.id),
+            channel: value.channel_id.map(id_from_db),
+            name: value.name.parse().map_err(|_| DatabaseError::InvalidData)?,
+            flags: flags.into(),
+        })
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = channel_accesses)]
+pub(super) struct ChannelAccessRow {
+    pub account_id: i64,
+    pub channel_id: i64,
+    pub role_id: i64,
+}
+
+impl From<&state::ChannelAccess> for ChannelAccessRow {
+    fn from(value: &state::ChannelAccess) -> Self {
+        Self {
+            account_id: id_to_db(&value.id.account()),
+            channel_id: id_to_db(&value.id.channel()),
+            role_id: id_to_db(&value.role),
+        }
+    }
+}
+
+impl From<ChannelAccessRow> for state::ChannelAccess {
+    fn from(value: ChannelAccessRow) -> Self {
+        Self {
+            id: ChannelAccessId::new(id_from_db(value.account_id), id_from_db(value.channel_id)),
+            role: id_from_db(value.role_id),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = channel_auto_kicks)]
+pub(super) struct ChannelAutoKickRow {
+    pub id: i64,
+    pub channel_id: i64,
+    pub mask: String,
+    pub reason: Option<String>,
+    pub setter_id: i64,
+    pub timestamp: i64,
+}
+
+impl From<&state::ChannelAutoKick> for ChannelAutoKickRow {
+    fn from(value: &state::ChannelAutoKick) -> Self {
+        Self {
+            id: id_to_db(&value.id),
+            channel_id: id_to_db(&value.channel),
+            mask: value.mask.to_string(),
+            reason: value.reason.clone(),
+            setter_id: id_to_db(&value.setter),
+            timestamp: value.timestamp,
+        }
+    }
+}
+
+impl From<ChannelAutoKickRow> for state::ChannelAutoKick {
+    fn from(value: ChannelAutoKickRow) -> Self {
+        Self {
+            id: id_from_db(value.id),
+            channel: id_from_db(value.channel_id),
+            mask: Pattern::new(value.mask),
+            reason: value.reason,
+            setter: id_from_db(value.setter_id),
+            timestamp: value.timestamp,
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = channel_transfers)]
+pub(super) struct ChannelTransferRow {
+    pub channel_id: i64,
+    pub from_account_id: i64,
+    pub to_account_id: i64,
+    pub timestamp: i64,
+}
+
+impl From<&ChannelTransfer> for ChannelTransferRow {
+    fn from(value: &ChannelTransfer) -> Self {
+        Self {
+            channel_id: id_to_db(&value.channel),
+            from_account_id: id_to_db(&value.from),
+            to_account_id: id_to_db(&value.to),
+            timestamp: value.timestamp,
+        }
+    }
+}
+
+impl From<ChannelTransferRow> for ChannelTransfer {
+    fn from(value: ChannelTransferRow) -> Self {
+        Self {
+            channel: id_from_db(value.channel_id),
+            from: id_from_db(value.from_account_id),
+            to: id_from_db(value.to_account_id),
+            timestamp: value.timestamp,
+        }
+    }
+}
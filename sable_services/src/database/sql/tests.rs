@@ -0,0 +1,367 @@
+use super::*;
+use crate::database::jsonfile::JsonDatabase;
+use state::ChannelAccessFlag;
+use std::str::FromStr;
+
+fn test_db() -> SqlDatabase {
+    SqlDatabase::connect("sqlite://:memory:").unwrap()
+}
+
+fn id(n: u16) -> Snowflake {
+    Snowflake::from_parts(ServerId::new(1), 0, n)
+}
+
+fn account(n: u16, name: &str) -> (state::Account, AccountAuth) {
+    let account = state::Account {
+        id: AccountId::new(id(n)),
+        name: Nickname::from_str(name).unwrap(),
+        authorised_fingerprints: vec!["aaaa".to_string(), "bbbb".to_string()],
+        registered: 1000 + i64::from(n),
+        last_login: Some(2000 + i64::from(n)),
+        public_fields: [("url".to_string(), "https://example.com/".to_string())].into(),
+        private: n.is_multiple_of(2),
+    };
+    let auth = AccountAuth {
+        account: account.id,
+        password_hash: format!("hash-{n}"),
+    };
+    (account, auth)
+}
+
+fn channel(n: u16, name: &str) -> state::ChannelRegistration {
+    state::ChannelRegistration {
+        id: ChannelRegistrationId::new(id(n)),
+        channelname: ChannelName::from_str(name).unwrap(),
+        registered: 3000 + i64::from(n),
+        mode_lock: state::ChannelModeLock {
+            set: ChannelModeSet::new() | ChannelModeFlag::NoExternal | ChannelModeFlag::TopicLock,
+            unset: ChannelModeSet::new() | ChannelModeFlag::InviteOnly,
+            key: Some(ChannelKey::from_str("secret").unwrap()),
+        },
+        keep_topic: true,
+        topic: Some(state::RetainedTopic {
+            text: "a topic".to_string(),
+            setter_info: "nick!user@host".to_string(),
+            timestamp: 4000,
+        }),
+        entry_message: Some("welcome".to_string()),
+    }
+}
+
+/// Populate `db` with one of each kind of object, returning the channel registration
+fn populate(db: &impl DatabaseConnection) -> state::ChannelRegistration {
+    let (account, auth) = account(1, "Alice");
+    let account = db.new_account(account, auth).unwrap();
+
+    db.new_nick_registration(state::NickRegistration {
+        id: NickRegistrationId::new(id(2)),
+        nick: Nickname::from_str("alice_").unwrap(),
+        account: account.id,
+    })
+    .unwrap();
+
+    let channel = db.new_channel_registration(channel(3, "#Chan")).unwrap();
+
+    let role = db
+        .new_channel_role(state::ChannelRole {
+            id: ChannelRoleId::new(id(4)),
+            channel: Some(channel.id),
+            name: state::ChannelRoleName::BuiltinOp,
+            flags: vec![ChannelAccessFlag::OpSelf, ChannelAccessFlag::Topic].into(),
+        })
+        .unwrap();
+
+    db.update_channel_access(&state::ChannelAccess {
+        id: ChannelAccessId::new(account.id, channel.id),
+        role: role.id,
+    })
+    .unwrap();
+
+    db.new_channel_auto_kick(state::ChannelAutoKick {
+        id: ChannelAutoKickId::new(id(5)),
+        channel: channel.id,
+        mask: Pattern::new("*!*@bad.example".to_string()),
+        reason: Some("go away".to_string()),
+        setter: account.id,
+        timestamp: 5000,
+    })
+    .unwrap();
+
+    channel
+}
+
+fn sorted<T, K: Ord>(items: impl Iterator<Item = T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut items: Vec<_> = items.collect();
+    items.sort_by_key(key);
+    items
+}
+
+#[test]
+fn account_round_trip() {
+    let db = test_db();
+    let (account, auth) = account(1, "Alice");
+
+    db.new_account(account.clone(), auth).unwrap();
+
+    assert_eq!(db.account(account.id).unwrap(), account);
+    assert_eq!(
+        db.auth_for_account(account.id).unwrap().password_hash,
+        "hash-1"
+    );
+
+    let mut updated = account.clone();
+    updated.authorised_fingerprints = vec!["cccc".to_string()];
+    updated.public_fields.clear();
+    updated.last_login = None;
+    db.update_account(&updated).unwrap();
+
+    assert_eq!(db.account(account.id).unwrap(), updated);
+    assert_eq!(
+        db.all_accounts().unwrap().collect::<Vec<_>>(),
+        vec![updated]
+    );
+}
+
+#[test]
+fn account_names_are_casefolded() {
+    let db = test_db();
+    let (alice, auth) = account(1, "Alice[]");
+    db.new_account(alice.clone(), auth).unwrap();
+
+    let lookup = Nickname::from_str("aLICE[]").unwrap();
+    assert_eq!(lookup, alice.name);
+    assert_eq!(db.account_named(&lookup).unwrap().id, alice.id);
+
+    let (duplicate, auth) = account(2, "ALICE[]");
+    assert!(matches!(
+        db.new_account(duplicate, auth),
+        Err(DatabaseError::DuplicateName)
+    ));
+
+    let (other, auth) = account(3, "Alice{}");
+    db.new_account(other, auth).unwrap();
+}
+
+#[test]
+fn duplicate_account_id_is_rejected() {
+    let db = test_db();
+    let (first, auth) = account(1, "Alice");
+    db.new_account(first, auth).unwrap();
+
+    let (second, auth) = account(1, "Bob");
+    assert!(matches!(
+        db.new_account(second, auth),
+        Err(DatabaseError::DuplicateId)
+    ));
+}
+
+#[test]
+fn channel_objects_round_trip() {
+    let db = test_db();
+    let channel = populate(&db);
+
+    assert_eq!(db.channel_registration(channel.id).unwrap(), channel);
+
+    let mut updated = channel.clone();
+    updated.mode_lock = Default::default();
+    updated.topic = None;
+    updated.entry_message = None;
+    db.update_channel_registration(&updated).unwrap();
+    assert_eq!(db.channel_registration(channel.id).unwrap(), updated);
+
+    let role = db.all_channel_roles().unwrap().next().unwrap();
+    assert_eq!(db.channel_role(role.id).unwrap(), role);
+    assert!(role.flags.is_set(ChannelAccessFlag::Topic));
+
+    assert_eq!(db.all_channel_accesses().unwrap().count(), 1);
+    let auto_kick = db.all_channel_auto_kicks().unwrap().next().unwrap();
+    assert_eq!(auto_kick.mask, Pattern::new("*!*@bad.example".to_string()));
+}
+
+#[test]
+fn removing_channel_removes_dependents() {
+    let db = test_db();
+    let channel = populate(&db);
+
+    db.remove_channel_registration(channel.id).unwrap();
+
+    assert!(matches!(
+        db.channel_registration(channel.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+    assert_eq!(db.all_channel_roles().unwrap().count(), 0);
+    assert_eq!(db.all_channel_accesses().unwrap().count(), 0);
+    assert_eq!(db.all_channel_auto_kicks().unwrap().count(), 0);
+}
+
+fn duplicate_and_missing_ids(db: &impl DatabaseConnection) {
+    let registration = populate(db);
+    let role = db.all_channel_roles().unwrap().next().unwrap();
+    let access = db.all_channel_accesses().unwrap().next().unwrap();
+    let auto_kick = db.all_channel_auto_kicks().unwrap().next().unwrap();
+    let account = db.all_accounts().unwrap().next().unwrap();
+
+    // Reusing an ID under a different name is a duplicate ID, not a duplicate name
+    assert!(matches!(
+        db.new_nick_registration(state::NickRegistration {
+            id: NickRegistrationId::new(id(2)),
+            nick: Nickname::from_str("alice__").unwrap(),
+            account: account.id,
+        }),
+        Err(DatabaseError::DuplicateId)
+    ));
+    assert!(matches!(
+        db.new_channel_registration(channel(3, "#Other")),
+        Err(DatabaseError::DuplicateId)
+    ));
+    assert!(matches!(
+        db.new_channel_role(state::ChannelRole {
+            name: state::ChannelRoleName::BuiltinVoice,
+            ..role.clone()
+        }),
+        Err(DatabaseError::DuplicateId)
+    ));
+
+    db.remove_channel_auto_kick(auto_kick.id).unwrap();
+    assert!(matches!(
+        db.remove_channel_auto_kick(auto_kick.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+    db.remove_channel_access(access.id).unwrap();
+    assert!(matches!(
+        db.remove_channel_access(access.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+    db.remove_channel_role(role.id).unwrap();
+    assert!(matches!(
+        db.remove_channel_role(role.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+    db.remove_channel_registration(registration.id).unwrap();
+    assert!(matches!(
+        db.remove_channel_registration(registration.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+
+    // Removing a transfer is fine whether or not there was one
+    db.remove_channel_transfer(registration.id).unwrap();
+}
+
+#[test]
+fn duplicate_and_missing_ids_are_errors() {
+    duplicate_and_missing_ids(&test_db());
+}
+
+#[test]
+fn json_duplicate_and_missing_ids_are_errors() {
+    let path =
+        std::env::temp_dir().join(format!("sable-duplicate-test-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    duplicate_and_missing_ids(&JsonDatabase::connect(path.to_string_lossy()).unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn transfer_round_trip(db: &impl DatabaseConnection) {
+    let channel = populate(db);
+    let (bob, auth) = account(6, "Bob");
+    let bob = db.new_account(bob, auth).unwrap();
+    let alice = db
+        .account_named(&Nickname::from_str("Alice").unwrap())
+        .unwrap();
+
+    assert!(matches!(
+        db.channel_transfer(channel.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+
+    let mut transfer = ChannelTransfer {
+        channel: channel.id,
+        from: alice.id,
+        to: bob.id,
+        timestamp: 6000,
+    };
+    db.update_channel_transfer(&transfer).unwrap();
+    assert_eq!(db.channel_transfer(channel.id).unwrap(), transfer);
+
+    // A new offer replaces the old one
+    transfer.to = alice.id;
+    transfer.timestamp = 7000;
+    db.update_channel_transfer(&transfer).unwrap();
+    assert_eq!(db.channel_transfer(channel.id).unwrap(), transfer);
+
+    db.remove_channel_transfer(channel.id).unwrap();
+    assert!(matches!(
+        db.channel_transfer(channel.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+
+    // Dropping the channel drops any pending transfer
+    db.update_channel_transfer(&transfer).unwrap();
+    db.remove_channel_registration(channel.id).unwrap();
+    assert!(matches!(
+        db.channel_transfer(channel.id),
+        Err(DatabaseError::NoSuchId)
+    ));
+}
+
+#[test]
+fn channel_transfers() {
+    transfer_round_trip(&test_db());
+}
+
+#[test]
+fn json_channel_transfers() {
+    let path =
+        std::env::temp_dir().join(format!("sable-transfer-test-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    transfer_round_trip(&JsonDatabase::connect(path.to_string_lossy()).unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn import_from_json() {
+    let path = std::env::temp_dir().join(format!("sable-import-test-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let source = JsonDatabase::connect(path.to_string_lossy()).unwrap();
+    populate(&source);
+    let (bob, auth) = account(6, "Bob");
+    source.new_account(bob, auth).unwrap();
+
+    let dest = test_db();
+    import_database(&source, &dest).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        sorted(dest.all_accounts().unwrap(), |a| a.id),
+        sorted(source.all_accounts().unwrap(), |a| a.id)
+    );
+    for account in source.all_accounts().unwrap() {
+        assert_eq!(
+            dest.auth_for_account(account.id).unwrap().password_hash,
+            source.auth_for_account(account.id).unwrap().password_hash
+        );
+    }
+    assert_eq!(
+        sorted(dest.all_nick_registrations().unwrap(), |n| n.id),
+        sorted(source.all_nick_registrations().unwrap(), |n| n.id)
+    );
+    assert_eq!(
+        sorted(dest.all_channel_registrations().unwrap(), |c| c.id),
+        sorted(source.all_channel_registrations().unwrap(), |c| c.id)
+    );
+    assert_eq!(
+        sorted(dest.all_channel_roles().unwrap(), |r| r.id),
+        sorted(source.all_channel_roles().unwrap(), |r| r.id)
+    );
+    assert_eq!(
+        sorted(dest.all_channel_accesses().unwrap(), |a| a.id),
+        sorted(source.all_channel_accesses().unwrap(), |a| a.id)
+    );
+    assert_eq!(
+        sorted(dest.all_channel_auto_kicks().unwrap(), |k| k.id),
+        sorted(source.all_channel_auto_kicks().unwrap(), |k| k.id)
+    );
+}
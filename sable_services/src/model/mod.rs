@@ -7,6 +7,16 @@ pub struct AccountAuth {
     pub password_hash: String,
 }
 
+/// A channel ownership transfer which has been offered but not yet accepted
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChannelTransfer {
+    pub channel: ChannelRegistrationId,
+    pub from: AccountId,
+    pub to: AccountId,
+    /// When the offer was made, used to expire it
+    pub timestamp: i64,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SaslSession {
     pub id: SaslSessionId,
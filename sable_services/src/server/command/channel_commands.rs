@@ -15,6 +15,11 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
         let new_channel_registration = state::ChannelRegistration {
             id: self.node.ids().next(),
             channelname: *channel.name(),
+            registered: sable_network::utils::now(),
+            mode_lock: Default::default(),
+            keep_topic: false,
+            topic: None,
+            entry_message: None,
         };
 
         let new_channel_registration =
@@ -66,6 +71,151 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
         Ok(RemoteServerResponse::Success)
     }
 
+    pub(crate) fn transfer_channel(
+        &self,
+        source: AccountId,
+        channel: ChannelRegistrationId,
+        target: AccountId,
+    ) -> CommandResult {
+        let net = self.node.network();
+
+        let source = net.account(source)?;
+        let target = net.account(target)?;
+        let channel = net.channel_registration(channel)?;
+
+        self.check_founder_access(&source, channel.id())?;
+
+        self.db.update_channel_transfer(&ChannelTransfer {
+            channel: channel.id(),
+            from: source.id(),
+            to: target.id(),
+            timestamp: sable_network::utils::now(),
+        })?;
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    pub(crate) fn accept_channel_transfer(
+        &self,
+        source: AccountId,
+        channel: ChannelRegistrationId,
+    ) -> CommandResult {
+        let net = self.node.network();
+
+        let channel = net.channel_registration(channel)?;
+
+        let pending = match self.db.channel_transfer(channel.id()) {
+            Ok(pending) if pending.to == source => pending,
+            Ok(_) | Err(DatabaseError::NoSuchId) => {
+                return Err(RemoteServerResponse::Services(
+                    RemoteServicesServerResponse::NoPendingTransfer,
+                )
+                .into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.db.remove_channel_transfer(channel.id())?;
+
+        if transfer_expired(
+            &pending,
+            self.config.channel_transfer_expiry,
+            sable_network::utils::now(),
+        ) {
+            return Err(RemoteServerResponse::Services(
+                RemoteServicesServerResponse::NoPendingTransfer,
+            )
+            .into());
+        }
+
+        // The offer is only good while the account which made it still owns the channel
+        self.check_founder_access(&net.account(pending.from)?, channel.id())?;
+
+        let Some(founder_role) = channel.role_named(&ChannelRoleName::BuiltinFounder) else {
+            return Err("Couldn't find built-in founder role".into());
+        };
+
+        let new_access = state::ChannelAccess {
+            id: ChannelAccessId::new(pending.to, channel.id()),
+            role: founder_role.id(),
+        };
+        self.db.update_channel_access(&new_access)?;
+        self.node.submit_event(
+            new_access.id,
+            ChannelAccessUpdate {
+                data: Some(new_access),
+            },
+        );
+
+        // The previous founder keeps op access, if the channel has an op role
+        let old_access_id = ChannelAccessId::new(pending.from, channel.id());
+        if let Some(op_role) = channel.role_named(&ChannelRoleName::BuiltinOp) {
+            let old_access = state::ChannelAccess {
+                id: old_access_id,
+                role: op_role.id(),
+            };
+            self.db.update_channel_access(&old_access)?;
+            self.node.submit_event(
+                old_access_id,
+                ChannelAccessUpdate {
+                    data: Some(old_access),
+                },
+            );
+        } else {
+            self.db.remove_channel_access(old_access_id)?;
+            self.node
+                .submit_event(old_access_id, ChannelAccessUpdate { data: None });
+        }
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    pub(crate) fn drop_channel(
+        &self,
+        source: AccountId,
+        channel: ChannelRegistrationId,
+    ) -> CommandResult {
+        let net = self.node.network();
+
+        let source = net.account(source)?;
+        let channel = net.channel_registration(channel)?;
+
+        self.check_founder_access(&source, channel.id())?;
+
+        // Any pending transfer is removed along with the registration
+        self.db.remove_channel_registration(channel.id())?;
+
+        // The database removes dependent objects itself; the network needs to be told
+        for access in channel.access_entries() {
+            self.node
+                .submit_event(access.id(), ChannelAccessUpdate { data: None });
+        }
+        for auto_kick in channel.auto_kicks() {
+            self.node
+                .submit_event(auto_kick.id(), ChannelAutoKickUpdate { data: None });
+        }
+        for role in channel.roles() {
+            self.node
+                .submit_event(role.id(), ChannelRoleUpdate { data: None });
+        }
+        self.node
+            .submit_event(channel.id(), ChannelRegistrationUpdate { data: None });
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    fn check_founder_access(
+        &self,
+        source: &wrapper::Account,
+        channel: ChannelRegistrationId,
+    ) -> Result<(), CommandError> {
+        match source.has_access_in(channel) {
+            Some(access) if access.has(ChannelAccessFlag::Founder) => Ok(()),
+            _ => Err(
+                RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied).into(),
+            ),
+        }
+    }
+
     pub(crate) fn modify_channel_access(
         &self,
         source: AccountId,
@@ -127,9 +277,10 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
                 Ok(RemoteServerResponse::Success)
             }
             None => {
-                // We're deleting an access
-                if self.db.remove_channel_access(access_id).is_err() {
-                    return Err("Database update failed".into());
+                // We're deleting an access; if there wasn't one, there's nothing to do
+                match self.db.remove_channel_access(access_id) {
+                    Ok(()) | Err(DatabaseError::NoSuchId) => (),
+                    Err(_) => return Err("Database update failed".into()),
                 }
                 self.node
                     .submit_event(access_id, ChannelAccessUpdate { data: None });
@@ -233,4 +384,192 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
 
         Ok(RemoteServerResponse::Success)
     }
+
+    pub(crate) fn add_auto_kick(
+        &self,
+        source: AccountId,
+        channel: ChannelRegistrationId,
+        mask: Pattern,
+        reason: Option<String>,
+    ) -> CommandResult {
+        let net = self.node.network();
+
+        let source = net.account(source)?;
+        let channel = net.channel_registration(channel)?;
+
+        self.check_auto_kick_access(&source, channel.id())?;
+
+        let new_auto_kick = state::ChannelAutoKick {
+            id: self.node.ids().next(),
+            channel: channel.id(),
+            mask,
+            reason,
+            setter: source.id(),
+            timestamp: sable_network::utils::now(),
+        };
+
+        let new_auto_kick = self.db.new_channel_auto_kick(new_auto_kick)?;
+
+        self.node.submit_event(
+            new_auto_kick.id,
+            ChannelAutoKickUpdate {
+                data: Some(new_auto_kick),
+            },
+        );
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    pub(crate) fn remove_auto_kick(
+        &self,
+        source: AccountId,
+        id: ChannelAutoKickId,
+    ) -> CommandResult {
+        let net = self.node.network();
+
+        let source = net.account(source)?;
+        let existing = net.channel_auto_kick(id)?;
+
+        self.check_auto_kick_access(&source, existing.channel()?.id())?;
+
+        self.db.remove_channel_auto_kick(id)?;
+        self.node
+            .submit_event(id, ChannelAutoKickUpdate { data: None });
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    fn check_auto_kick_access(
+        &self,
+        source: &wrapper::Account,
+        channel: ChannelRegistrationId,
+    ) -> Result<(), CommandError> {
+        match source.has_access_in(channel) {
+            Some(access) if access.has(ChannelAccessFlag::Akick) => Ok(()),
+            _ => Err(
+                RemoteServerResponse::Services(RemoteServicesServerResponse::AccessDenied).into(),
+            ),
+        }
+    }
+
+    pub(crate) fn set_channel_setting(
+        &self,
+        source: AccountId,
+        channel: ChannelRegistrationId,
+        setting: ChannelSetting,
+    ) -> CommandResult {
+        let net = self.node.network();
+
+        let source = net.account(source)?;
+
+        match source.has_access_in(channel) {
+            Some(access) if access.has(ChannelAccessFlag::Set) => (),
+            _ => {
+                return Err(RemoteServerResponse::Services(
+                    RemoteServicesServerResponse::AccessDenied,
+                )
+                .into());
+            }
+        }
+
+        let mut registration = self.db.channel_registration(channel)?;
+
+        match setting {
+            ChannelSetting::ModeLock(lock) => registration.mode_lock = lock,
+            ChannelSetting::KeepTopic(keep) => {
+                registration.keep_topic = keep;
+                // Start from the current topic, if there is one
+                registration.topic = keep
+                    .then(|| {
+                        net.channel_by_name(&registration.channelname)
+                            .ok()
+                            .and_then(|chan| chan.topic().map(|topic| retained_topic_from(&topic)))
+                    })
+                    .flatten();
+            }
+            ChannelSetting::EntryMessage(message) => registration.entry_message = message,
+        }
+
+        self.db.update_channel_registration(&registration)?;
+
+        self.node.submit_event(
+            registration.id,
+            ChannelRegistrationUpdate {
+                data: Some(registration),
+            },
+        );
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    /// Called for every topic change on the network; stores the new topic for registered
+    /// channels which have topic retention enabled
+    pub(crate) fn retain_channel_topic(
+        &self,
+        change: &update::ChannelTopicChange,
+    ) -> Result<(), CommandError> {
+        let net = self.node.network();
+
+        let channel = net.channel(change.channel)?;
+        let Some(registration) = channel.is_registered() else {
+            return Ok(());
+        };
+
+        if !registration.keep_topic() {
+            return Ok(());
+        }
+
+        let topic = retained_topic_from(&net.channel_topic(change.topic)?);
+
+        // Restoring a retained topic produces a change to the same topic
+        if registration.retained_topic() == Some(&topic) {
+            return Ok(());
+        }
+
+        let mut registration = self.db.channel_registration(registration.id())?;
+        registration.topic = Some(topic);
+
+        self.db.update_channel_registration(&registration)?;
+
+        self.node.submit_event(
+            registration.id,
+            ChannelRegistrationUpdate {
+                data: Some(registration),
+            },
+        );
+
+        Ok(())
+    }
+}
+
+/// Whether a transfer offer is older than `expiry` seconds at time `now`
+fn transfer_expired(transfer: &ChannelTransfer, expiry: i64, now: i64) -> bool {
+    now.saturating_sub(transfer.timestamp) > expiry
+}
+
+fn retained_topic_from(topic: &wrapper::ChannelTopic<'_>) -> state::RetainedTopic {
+    state::RetainedTopic {
+        text: topic.text().to_owned(),
+        setter_info: topic.setter().to_owned(),
+        timestamp: topic.timestamp(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfers_expire() {
+        let transfer = ChannelTransfer {
+            channel: ChannelRegistrationId::new(Snowflake::from_parts(ServerId::new(1), 0, 1)),
+            from: AccountId::new(Snowflake::from_parts(ServerId::new(1), 0, 2)),
+            to: AccountId::new(Snowflake::from_parts(ServerId::new(1), 0, 3)),
+            timestamp: 1000,
+        };
+
+        assert!(!transfer_expired(&transfer, 60, 1000));
+        assert!(!transfer_expired(&transfer, 60, 1060));
+        assert!(transfer_expired(&transfer, 60, 1061));
+    }
 }
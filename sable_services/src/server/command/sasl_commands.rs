@@ -34,6 +34,10 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
 
         let response = mechanism.step(self, &session, data)?;
 
+        if let Success(account) = &response {
+            self.record_login(*account);
+        }
+
         Ok(Authenticate(response).into())
     }
 
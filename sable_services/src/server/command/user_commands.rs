@@ -21,6 +21,10 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
             id: new_account_id,
             name: account_name,
             authorised_fingerprints: Vec::new(),
+            registered: sable_network::utils::now(),
+            last_login: None,
+            public_fields: Default::default(),
+            private: false,
         };
         let auth_data = AccountAuth {
             account: new_account_id,
@@ -59,6 +63,7 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
         match bcrypt::verify(password, &auth.password_hash) {
             Ok(true) => {
                 tracing::debug!("login successful");
+                self.record_login(account_id);
                 Ok(RemoteServicesServerResponse::LogUserIn(account_id).into())
             }
             Ok(false) => {
@@ -110,4 +115,81 @@ impl<DB: DatabaseConnection> ServicesServer<DB> {
 
         Ok(RemoteServerResponse::Success)
     }
+
+    pub(crate) fn user_set_field(
+        &self,
+        account_id: AccountId,
+        name: String,
+        value: Option<String>,
+    ) -> CommandResult {
+        let mut account = self.db.account(account_id)?;
+
+        if let Err(error) = account.check_public_field(&name, value.as_deref()) {
+            return Err(RemoteServerResponse::Services(
+                RemoteServicesServerResponse::InvalidAccountField(error),
+            )
+            .into());
+        }
+
+        match value {
+            Some(value) => account.public_fields.insert(name, value),
+            None => account.public_fields.remove(&name),
+        };
+
+        self.db.update_account(&account)?;
+        self.node.submit_event(
+            account.id,
+            event::AccountUpdate {
+                data: Some(account),
+            },
+        );
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    pub(crate) fn user_set_private(&self, account_id: AccountId, private: bool) -> CommandResult {
+        let mut account = self.db.account(account_id)?;
+
+        account.private = private;
+
+        self.db.update_account(&account)?;
+        self.node.submit_event(
+            account.id,
+            event::AccountUpdate {
+                data: Some(account),
+            },
+        );
+
+        Ok(RemoteServerResponse::Success)
+    }
+
+    /// Update the last login time for an account. Failure here shouldn't prevent the
+    /// login itself, so errors are only logged.
+    pub(crate) fn record_login(&self, account_id: AccountId) {
+        let mut account = match self.db.account(account_id) {
+            Ok(account) => account,
+            Err(error) => {
+                tracing::error!(
+                    ?account_id,
+                    ?error,
+                    "Error looking up account to record login"
+                );
+                return;
+            }
+        };
+
+        account.last_login = Some(sable_network::utils::now());
+
+        if let Err(error) = self.db.update_account(&account) {
+            tracing::error!(?account_id, ?error, "Error recording login time");
+            return;
+        }
+
+        self.node.submit_event(
+            account.id,
+            event::AccountUpdate {
+                data: Some(account),
+            },
+        );
+    }
 }
@@ -34,10 +34,19 @@ mod sync;
 
 #[derive(Deserialize, Clone)]
 pub struct ServicesConfig {
+    /// Database connection string. `postgres://` and `sqlite://` URLs select the SQL
+    /// provider; anything else is used as the path to a JSON database file.
     pub database: String,
     pub default_roles: HashMap<ChannelRoleName, Vec<ChannelAccessFlag>>,
     #[serde(default)]
     pub password_hash: HashConfig,
+    /// How long, in seconds, a channel transfer offer remains valid
+    #[serde(default = "default_channel_transfer_expiry")]
+    pub channel_transfer_expiry: i64,
+}
+
+fn default_channel_transfer_expiry() -> i64 {
+    24 * 60 * 60
 }
 
 pub struct ServicesServer<DB> {
@@ -109,12 +118,20 @@ where
                 {
                     if let Some(update) = update
                     {
-                        if let NetworkStateChange::NewServer(new_server) = &update.change
+                        match &update.change
                         {
-                            if new_server.server == self.node.id()
+                            NetworkStateChange::NewServer(new_server) if new_server.server == self.node.id() =>
                             {
                                 self.burst_to_network().await;
                             }
+                            NetworkStateChange::ChannelTopicChange(change) =>
+                            {
+                                if let Err(error) = self.retain_channel_topic(change)
+                                {
+                                    tracing::error!(?error, "Failed to retain channel topic");
+                                }
+                            }
+                            _ => {}
                         }
                     }
                 }
@@ -179,6 +196,49 @@ where
 
                     self.modify_role(source, id, flags)
                 }
+                TransferChannel {
+                    source,
+                    channel,
+                    target,
+                } => {
+                    tracing::debug!(?source, ?channel, ?target, "Got channel transfer");
+
+                    self.transfer_channel(source, channel, target)
+                }
+                AcceptChannelTransfer { source, channel } => {
+                    tracing::debug!(?source, ?channel, "Got channel transfer acceptance");
+
+                    self.accept_channel_transfer(source, channel)
+                }
+                DropChannel { source, channel } => {
+                    tracing::debug!(?source, ?channel, "Got channel drop");
+
+                    self.drop_channel(source, channel)
+                }
+                SetChannelSetting {
+                    source,
+                    channel,
+                    setting,
+                } => {
+                    tracing::debug!(?source, ?channel, ?setting, "Got channel setting change");
+
+                    self.set_channel_setting(source, channel, setting)
+                }
+                AddAutoKick {
+                    source,
+                    channel,
+                    mask,
+                    reason,
+                } => {
+                    tracing::debug!(?source, ?channel, ?mask, ?reason, "Got add auto-kick");
+
+                    self.add_auto_kick(source, channel, mask, reason)
+                }
+                RemoveAutoKick { source, id } => {
+                    tracing::debug!(?source, ?id, "Got remove auto-kick");
+
+                    self.remove_auto_kick(source, id)
+                }
                 BeginAuthenticate(session, mechanism) => {
                     tracing::debug!(?session, ?mechanism, "Got begin authenticate");
 
@@ -194,6 +254,20 @@ where
 
                     self.abort_authenticate(session)
                 }
+                SetAccountField {
+                    account,
+                    name,
+                    value,
+                } => {
+                    tracing::debug!(?account, ?name, ?value, "Got set account field");
+
+                    self.user_set_field(account, name, value)
+                }
+                SetAccountPrivate(acc, private) => {
+                    tracing::debug!(?acc, ?private, "Got set account privacy");
+
+                    self.user_set_private(acc, private)
+                }
                 AddAccountFingerprint(acc, fp) => {
                     tracing::debug!(?acc, ?fp, "Got add fingerprint");
 
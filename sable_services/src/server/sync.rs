@@ -99,6 +99,24 @@ where
             })
             .map(|obj| obj.id());
 
+        let auto_kicks_to_sync = self.db.all_channel_auto_kicks().unwrap().filter(|mine| {
+            if let Ok(existing) = net.channel_auto_kick(mine.id) {
+                existing.raw() != mine
+            } else {
+                true
+            }
+        });
+
+        let auto_kicks_to_delete = net
+            .channel_auto_kicks()
+            .filter(|existing| {
+                matches!(
+                    self.db.channel_auto_kick(existing.id()),
+                    Err(DatabaseError::NoSuchId)
+                )
+            })
+            .map(|obj| obj.id());
+
         for account in accounts_to_sync {
             self.node.submit_event(
                 account.id,
@@ -157,6 +175,20 @@ where
                 .submit_event(role, ChannelRoleUpdate { data: None })
         }
 
+        for auto_kick in auto_kicks_to_sync {
+            self.node.submit_event(
+                auto_kick.id,
+                ChannelAutoKickUpdate {
+                    data: Some(auto_kick),
+                },
+            )
+        }
+
+        for auto_kick in auto_kicks_to_delete {
+            self.node
+                .submit_event(auto_kick, ChannelAutoKickUpdate { data: None })
+        }
+
         // Finally, set ourselves as the active services node
         self.node.submit_event(
             self.node.id(),